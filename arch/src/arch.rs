@@ -0,0 +1,30 @@
+//! interface for implementing new architectures.
+
+/// Single decoded instruction.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// Number of bytes the instruction occupies.
+    pub size: usize,
+    /// Textual representation of the instruction.
+    pub text: String,
+}
+
+/// Object that decodes machine code of one architecture in one mode.
+pub trait Disassembler {
+    /// Decode the instruction at the beginning of `data`, `addr` is the address of
+    /// `data[0]` and is used to resolve relative operands. Returns `None` if `data`
+    /// does not start with a valid instruction.
+    fn disassemble(&mut self, addr: u64, data: &[u8]) -> Option<Instruction>;
+}
+
+/// This trait should be implemented by every architecture supported by rair.
+pub trait Arch {
+    /// Name used to select the architecture (`asm.arch`).
+    fn name(&self) -> &'static str;
+    /// Bit widths supported by the architecture (`asm.bits`).
+    fn bits(&self) -> &'static [u64];
+    /// Size of the longest possible instruction in bytes.
+    fn max_insn_size(&self) -> usize;
+    /// Create disassembler for `bits` mode, `None` if `bits` is not supported.
+    fn disassembler(&self, bits: u64) -> Option<Box<dyn Disassembler>>;
+}
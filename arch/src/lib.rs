@@ -0,0 +1,18 @@
+//! rair architecture plugins library.
+mod arch;
+mod x86;
+
+pub use self::arch::*;
+pub use self::x86::X86;
+
+/// Every architecture known to rair.
+#[must_use]
+pub fn archs() -> Vec<Box<dyn Arch + Sync + Send>> {
+    vec![Box::new(X86)]
+}
+
+/// Look up architecture by its name.
+#[must_use]
+pub fn arch_by_name(name: &str) -> Option<Box<dyn Arch + Sync + Send>> {
+    archs().into_iter().find(|arch| arch.name() == name)
+}
@@ -0,0 +1,89 @@
+//! x86 support backed by iced-x86.
+
+use super::arch::{Arch, Disassembler, Instruction};
+use iced_x86::{Decoder, DecoderOptions, Formatter, IntelFormatter};
+
+/// Intel x86 in 16, 32 and 64 bit modes.
+#[derive(Default)]
+pub struct X86;
+
+struct X86Disassembler {
+    bits: u32,
+    formatter: IntelFormatter,
+}
+
+impl Disassembler for X86Disassembler {
+    fn disassemble(&mut self, addr: u64, data: &[u8]) -> Option<Instruction> {
+        let mut decoder = Decoder::with_ip(self.bits, data, addr, DecoderOptions::NONE);
+        let insn = decoder.decode();
+        if insn.is_invalid() {
+            return None;
+        }
+        let mut text = String::new();
+        self.formatter.format(&insn, &mut text);
+        Some(Instruction {
+            size: insn.len(),
+            text,
+        })
+    }
+}
+
+impl Arch for X86 {
+    fn name(&self) -> &'static str {
+        "x86"
+    }
+    fn bits(&self) -> &'static [u64] {
+        &[16, 32, 64]
+    }
+    fn max_insn_size(&self) -> usize {
+        15
+    }
+    fn disassembler(&self, bits: u64) -> Option<Box<dyn Disassembler>> {
+        if !self.bits().contains(&bits) {
+            return None;
+        }
+        let mut formatter = IntelFormatter::new();
+        let options = formatter.options_mut();
+        options.set_hex_prefix("0x");
+        options.set_hex_suffix("");
+        options.set_uppercase_hex(false);
+        options.set_branch_leading_zeros(false);
+        options.set_space_after_operand_separator(true);
+        Some(Box::new(X86Disassembler {
+            bits: bits as u32,
+            formatter,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test_x86 {
+    use super::*;
+
+    #[test]
+    fn test_x86_64() {
+        let mut dis = X86.disassembler(64).unwrap();
+        // lea rax, [rip + 0x10]
+        let insn = dis
+            .disassemble(0x1000, &[0x48, 0x8d, 0x05, 0x10, 0x00, 0x00, 0x00, 0x90])
+            .unwrap();
+        assert_eq!(insn.size, 7);
+        assert_eq!(insn.text, "lea rax, [0x1017]");
+        let insn = dis
+            .disassemble(0x1000, &[0xe8, 0xfb, 0xff, 0xff, 0xff])
+            .unwrap();
+        assert_eq!(insn.text, "call 0x1000");
+        assert_eq!(dis.disassemble(0x1000, &[0x48, 0x8d]), None);
+        assert_eq!(dis.disassemble(0x1000, &[0x06]), None);
+    }
+
+    #[test]
+    fn test_x86_bits() {
+        let mut dis = X86.disassembler(32).unwrap();
+        assert_eq!(dis.disassemble(0, &[0x06]).unwrap().text, "push es");
+        let mut dis = X86.disassembler(16).unwrap();
+        let insn = dis.disassemble(0, &[0xb8, 0x34, 0x12]).unwrap();
+        assert_eq!((insn.size, insn.text.as_str()), (3, "mov ax, 0x1234"));
+        assert!(X86.disassembler(8).is_none());
+    }
+}
@@ -49,6 +49,7 @@ impl RedPipe {
             | Rule::BIN
             | Rule::HEX
             | Rule::OCT
+            | Rule::FlagName
             | Rule::Command
             | Rule::ArgumentLiteral
             | Rule::Argument
@@ -95,20 +96,29 @@ impl Argument {
         }
     }
 }
+/// Location given to a command after `@`.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Loc {
+    Addr(u64),
+    Flag(String),
+}
+
 #[derive(Default, Debug, PartialEq)]
 pub struct Cmd {
     pub command: String,
     pub args: Vec<Argument>,
-    pub loc: Option<u64>,
+    pub loc: Option<Loc>,
     pub red_pipe: Box<RedPipe>,
 }
 
-fn pair_to_num(root: &Pair<Rule>) -> Result<u64, ParserError> {
+fn pair_to_loc(root: &Pair<Rule>) -> Result<Loc, ParserError> {
     let result = match root.as_rule() {
         Rule::BIN => u64::from_str_radix(&root.as_str()[2..], 2),
         Rule::HEX => u64::from_str_radix(&root.as_str()[2..], 16),
         Rule::OCT => u64::from_str_radix(&root.as_str()[1..], 8),
         Rule::DEC => root.as_str().parse::<u64>(),
+        Rule::FlagName => return Ok(Loc::Flag(root.as_str().to_owned())),
         Rule::EOI
         | Rule::WHITESPACE
         | Rule::CustomAlpha
@@ -132,7 +142,7 @@ fn pair_to_num(root: &Pair<Rule>) -> Result<u64, ParserError> {
         | Rule::HelpAll => unimplemented_pair(root),
     };
     match result {
-        Ok(x) => Ok(x),
+        Ok(x) => Ok(Loc::Addr(x)),
         Err(e) => Err(ParserError::Num(e)),
     }
 }
@@ -143,7 +153,7 @@ impl Cmd {
         for pair in root.into_inner() {
             match pair.as_rule() {
                 Rule::Command => pair.as_str().clone_into(&mut cmd.command),
-                Rule::Loc => cmd.loc = Some(pair_to_num(&pair.into_inner().next().unwrap())?),
+                Rule::Loc => cmd.loc = Some(pair_to_loc(&pair.into_inner().next().unwrap())?),
                 Rule::Arguments => cmd.args = Argument::parse_arguments(pair),
                 Rule::RedPipe => cmd.red_pipe = Box::new(RedPipe::parse_redpipe(pair)),
                 Rule::EOI
@@ -157,6 +167,7 @@ impl Cmd {
                 | Rule::BIN
                 | Rule::HEX
                 | Rule::OCT
+                | Rule::FlagName
                 | Rule::ArgumentLiteral
                 | Rule::Argument
                 | Rule::Pipe
@@ -192,6 +203,37 @@ mod test_normal_cmd {
         assert_eq!(cmd, target);
     }
     #[test]
+    fn test_cmd_slash() {
+        let root = CliParser::parse(Rule::CommandLine, "/ str foo")
+            .unwrap()
+            .next()
+            .unwrap();
+        let cmd = Cmd::parse_cmd(root).unwrap();
+        let target = Cmd {
+            command: "/".to_owned(),
+            args: vec![
+                Argument::Literal("str".to_owned()),
+                Argument::Literal("foo".to_owned()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(cmd, target);
+    }
+    #[test]
+    fn test_cmd_dot() {
+        let root = CliParser::parse(Rule::CommandLine, ". script.rair")
+            .unwrap()
+            .next()
+            .unwrap();
+        let cmd = Cmd::parse_cmd(root).unwrap();
+        let target = Cmd {
+            command: ".".to_owned(),
+            args: vec![Argument::Literal("script.rair".to_owned())],
+            ..Default::default()
+        };
+        assert_eq!(cmd, target);
+    }
+    #[test]
     fn test_cmd_argument() {
         let root = CliParser::parse(Rule::CommandLine, "aa bb \"cc dd\" `ee ff`")
             .unwrap()
@@ -241,7 +283,7 @@ mod test_normal_cmd {
         let mut cmd = Cmd::parse_cmd(root).unwrap();
         let mut target = Cmd {
             command: "aa".to_owned(),
-            loc: Some(0x500),
+            loc: Some(Loc::Addr(0x500)),
             ..Default::default()
         };
         assert_eq!(cmd, target);
@@ -251,7 +293,7 @@ mod test_normal_cmd {
             .next()
             .unwrap();
         cmd = Cmd::parse_cmd(root).unwrap();
-        target.loc = Some(500);
+        target.loc = Some(Loc::Addr(500));
         assert_eq!(cmd, target);
 
         root = CliParser::parse(Rule::CommandLine, "aa @ 0500")
@@ -259,7 +301,7 @@ mod test_normal_cmd {
             .next()
             .unwrap();
         cmd = Cmd::parse_cmd(root).unwrap();
-        target.loc = Some(0o500);
+        target.loc = Some(Loc::Addr(0o500));
         assert_eq!(cmd, target);
 
         root = CliParser::parse(Rule::CommandLine, "aa @ 0500")
@@ -267,7 +309,7 @@ mod test_normal_cmd {
             .next()
             .unwrap();
         cmd = Cmd::parse_cmd(root).unwrap();
-        target.loc = Some(0o500);
+        target.loc = Some(Loc::Addr(0o500));
         assert_eq!(cmd, target);
 
         root = CliParser::parse(Rule::CommandLine, "aa @ 0b10100")
@@ -275,7 +317,15 @@ mod test_normal_cmd {
             .next()
             .unwrap();
         cmd = Cmd::parse_cmd(root).unwrap();
-        target.loc = Some(0b10100);
+        target.loc = Some(Loc::Addr(0b10100));
+        assert_eq!(cmd, target);
+
+        root = CliParser::parse(Rule::CommandLine, "aa @ sym.main_2")
+            .unwrap()
+            .next()
+            .unwrap();
+        cmd = Cmd::parse_cmd(root).unwrap();
+        target.loc = Some(Loc::Flag("sym.main_2".to_owned()));
         assert_eq!(cmd, target);
     }
 
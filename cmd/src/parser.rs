@@ -40,6 +40,7 @@ impl ParseTree {
             | Rule::BIN
             | Rule::HEX
             | Rule::OCT
+            | Rule::FlagName
             | Rule::Command
             | Rule::ArgumentLiteral
             | Rule::Argument
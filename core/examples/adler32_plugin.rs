@@ -0,0 +1,55 @@
+//! Example of a command plugin built as a shared library, it adds the `adler32` command that
+//! prints the Adler-32 checksum of data at current location.
+//!
+//! Build it with `cargo build --example adler32_plugin` then load it with `cmdPluginLoad` or
+//! copy it into the rair command plugin directory.
+
+use rair_core::{error_msg, expect, export_cmd_plugin, str_to_num, Cmd, Core};
+use std::io::Write;
+
+const MOD_ADLER: u32 = 0xfff1;
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1, 0);
+    for byte in data {
+        a = (a + u32::from(*byte)) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+struct Adler32;
+
+impl Cmd for Adler32 {
+    fn commands(&self) -> &'static [&'static str] {
+        &["adler32"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[size]",
+            "Print Adler-32 checksum of [size] bytes at current location.",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        let size = match str_to_num(&args[0]) {
+            Ok(size) => size,
+            Err(e) => return error_msg(core, "Failed to parse size", &e.to_string()),
+        };
+        let mut data = vec![0; size as usize];
+        let loc = core.get_loc();
+        if let Err(e) = core.read(loc, &mut data) {
+            return error_msg(core, "Read Failed", &e.to_string());
+        }
+        writeln!(core.stdout, "0x{:08x}", adler32(&data)).unwrap();
+    }
+}
+
+fn commands() -> Vec<Box<dyn Cmd + Sync + Send>> {
+    vec![Box::new(Adler32)]
+}
+
+export_cmd_plugin!("adler32", "0.0.1", commands);
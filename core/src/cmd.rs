@@ -6,6 +6,27 @@ pub trait Cmd {
     fn commands(&self) -> &'static [&'static str];
     fn help_messages(&self) -> &'static [(&'static str, &'static str)];
     fn run(&mut self, _: &mut Core, _: &[String]);
+    /// Fresh instance to run instead of `self`, the registered instance is then left unlocked
+    /// while the command runs so the command can run itself again (e.g. `source` inside a
+    /// sourced script). Commands that keep state across runs must keep the default.
+    fn fork(&self) -> Option<Box<dyn Cmd>> {
+        None
+    }
+}
+
+impl<T: ?Sized + Cmd> Cmd for Box<T> {
+    fn commands(&self) -> &'static [&'static str] {
+        (**self).commands()
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        (**self).help_messages()
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        (**self).run(core, args);
+    }
+    fn fork(&self) -> Option<Box<dyn Cmd>> {
+        (**self).fork()
+    }
 }
 
 pub trait CmdOps: Cmd {
@@ -2,14 +2,16 @@
 
 use std::collections::HashSet;
 
-use crate::{cmd::Cmd, helper::MRc};
-use alloc::collections::BTreeMap;
+use crate::{cmd::Cmd, helper::MRc, plugin::CmdPlugin};
+use alloc::{collections::BTreeMap, sync::Arc};
 use rair_trees::bktree::SpellTree; // for suffex search
 
 #[derive(Default)]
 pub struct Commands {
     suggestions: SpellTree<()>,
     search: BTreeMap<&'static str, MRc<dyn Cmd + Sync + Send>>,
+    // must be dropped after all commands since they live in the plugins' libraries.
+    plugins: Vec<CmdPlugin>,
 }
 
 impl Commands {
@@ -45,6 +47,78 @@ impl Commands {
             .map(|(k, _)| k)
             .collect()
     }
+    /// Register all `commands` of `plugin`, nothing is registered if any of the command names
+    /// already exists.
+    pub(crate) fn add_plugin(
+        &mut self,
+        mut plugin: CmdPlugin,
+        commands: Vec<MRc<dyn Cmd + Sync + Send>>,
+    ) -> Result<(), String> {
+        if self.plugins.iter().any(|p| p.name == plugin.name) {
+            return Err(format!("Plugin {} is already loaded.", plugin.name));
+        }
+        let mut names = Vec::new();
+        for cmd in &commands {
+            names.extend_from_slice(cmd.lock().commands());
+        }
+        let duplicate = names
+            .iter()
+            .enumerate()
+            .find(|(i, name)| self.search.contains_key(*name) || names[..*i].contains(name));
+        if let Some((_, name)) = duplicate {
+            let err = format!("Command {name} already existed.");
+            // commands must not outlive the library they came from.
+            drop(commands);
+            drop(plugin);
+            return Err(err);
+        }
+        for cmd in commands {
+            for name in cmd.lock().commands() {
+                self.add_command(name, cmd.clone());
+            }
+        }
+        plugin.commands = names;
+        self.plugins.push(plugin);
+        Ok(())
+    }
+    /// Remove all commands of the plugin named `name` then unload it.
+    pub(crate) fn remove_plugin(&mut self, name: &str) -> Result<(), String> {
+        let Some(index) = self.plugins.iter().position(|p| p.name == name) else {
+            return Err(format!("Plugin {name} is not loaded."));
+        };
+        let names = &self.plugins[index].commands;
+        let removed: Vec<_> = names
+            .iter()
+            .filter_map(|name| self.search.remove_entry(name))
+            .collect();
+        // a command that is still running holds one more reference than its names do.
+        let in_use = removed.iter().any(|(_, cmd)| {
+            let aliases = removed.iter().filter(|(_, c)| Arc::ptr_eq(c, cmd)).count();
+            Arc::strong_count(cmd) > aliases
+        });
+        if in_use {
+            self.search.extend(removed);
+            return Err(format!("Plugin {name} is in use."));
+        }
+        drop(removed);
+        self.suggestions = SpellTree::default();
+        for name in self.search.keys() {
+            self.suggestions.insert((*name).to_owned(), ());
+        }
+        self.plugins.remove(index);
+        Ok(())
+    }
+    /// Loaded command plugins.
+    pub fn plugins(&self) -> impl Iterator<Item = &CmdPlugin> {
+        self.plugins.iter()
+    }
+    /// Plugin that provides `command`, if any.
+    #[must_use]
+    pub fn provider(&self, command: &str) -> Option<&CmdPlugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.commands.contains(&command))
+    }
     /// iterate over commands with no duplication
     pub fn iter(&self) -> impl Iterator<Item = MRc<dyn Cmd + Sync + Send>> + '_ {
         let mut dups = HashSet::new();
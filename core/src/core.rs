@@ -2,10 +2,13 @@
 
 use crate::cmd::{Cmd, CmdOps};
 use crate::commands::Commands;
-use crate::helper::{error_msg, AddrMode};
+use crate::flags::{register_flags, FlagStore};
+use crate::helper::{error_msg, is_json, print_json, AddrMode, MRc};
 use crate::io::register_io;
+use crate::loader::register_loader;
 use crate::loc::register_loc;
 use crate::register_diff;
+use crate::search::register_search;
 use crate::utils::register_utils;
 use crate::writer::Writer;
 use alloc::{collections::BTreeMap, sync::Arc};
@@ -14,6 +17,7 @@ use parking_lot::{Mutex, RwLock};
 use rair_env::Environment;
 use rair_io::{IoError, RIO};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::io;
 use std::io::Write;
 use yansi::Paint;
@@ -22,6 +26,8 @@ pub struct Core {
     pub mode: AddrMode,
     pub io: RIO,
     loc: u64,
+    #[serde(default)]
+    pub flags: FlagStore,
     // Every time you add some new serde(skip) variable
     // make sure that this variable is well initialized
     // in the projects commands.
@@ -33,6 +39,8 @@ pub struct Core {
     commands: Arc<Mutex<Commands>>,
     #[serde(skip)]
     pub env: Arc<RwLock<Environment<Core>>>,
+    #[serde(skip)]
+    pub(crate) evaluator: Option<fn(&mut Core, &str)>,
 }
 
 impl Default for Core {
@@ -43,8 +51,10 @@ impl Default for Core {
             stderr: Writer::new_write(Box::new(io::stderr())),
             io: RIO::new(),
             loc: 0,
+            flags: FlagStore::default(),
             commands: Arc::default(),
             env: Arc::default(),
+            evaluator: None,
         }
     }
 }
@@ -62,6 +72,9 @@ impl Core {
         register_loc(self);
         register_utils(self);
         register_diff(self);
+        register_search(self);
+        register_flags(self);
+        register_loader(self);
     }
     /// Returns list of all available commands in [Core].
     pub fn commands(&mut self) -> Arc<Mutex<Commands>> {
@@ -80,6 +93,12 @@ impl Core {
             "Show help for suggestions in case of invalid Command",
         )
         .unwrap();
+        env.add_bool(
+            "cfg.json",
+            false,
+            "Print output of commands as JSON, same as adding `j` suffix to command name",
+        )
+        .unwrap();
     }
     fn init_colors(&mut self, enable: bool) {
         let locked_env = self.env.clone();
@@ -164,24 +183,85 @@ impl Core {
         let cmds = self.commands.clone();
         let cmds_ref = cmds.lock();
         let cmd = cmds_ref.find(command);
+        // `cmdj` is `cmd` with JSON output.
+        let json_cmd = command.strip_suffix('j').and_then(|c| cmds_ref.find(c));
         drop(cmds_ref);
         if let Some(cmd) = cmd {
-            cmd.lock().run(self, args);
+            self.run_cmd(command, &cmd, args);
+        } else if let Some(cmd) = json_cmd {
+            let env = self.env.clone();
+            let json = env.read().get_bool("cfg.json").unwrap();
+            env.write().set_bool("cfg.json", true, self).unwrap();
+            self.run_cmd(command, &cmd, args);
+            env.write().set_bool("cfg.json", json, self).unwrap();
         } else {
             self.command_not_found(command);
         }
     }
 
+    fn run_cmd(&mut self, command: &str, cmd: &MRc<dyn Cmd + Sync + Send>, args: &[String]) {
+        let Some(mut locked) = cmd.try_lock() else {
+            let msg = format!("Command {} is already running.", command.primary().bold());
+            return error_msg(self, "Execution failed", &msg);
+        };
+        if let Some(mut fork) = locked.fork() {
+            drop(locked);
+            fork.run(self, args);
+        } else {
+            locked.run(self, args);
+        }
+    }
+
+    /// Set the function used by [`Core::eval`], the command line evaluator lives outside of
+    /// [`Core`].
+    pub fn set_evaluator(&mut self, evaluator: fn(&mut Core, &str)) {
+        self.evaluator = Some(evaluator);
+    }
+
+    /// Evaluate `line` as if it was typed in the prompt.
+    pub fn eval(&mut self, line: &str) {
+        match self.evaluator {
+            Some(evaluator) => evaluator(self, line),
+            None => error_msg(self, "Failed to evaluate", "No evaluator is set."),
+        }
+    }
+
     pub fn run_at(&mut self, command: &str, args: &[String], at: u64) {
         let old_loc = mem::replace(&mut self.loc, at);
         self.run(command, args);
         self.loc = old_loc;
     }
+    /// Print help of `cmd` followed by the plugin providing it, if any.
+    fn help_cmd(&mut self, commands: &Commands, cmd: &(dyn Cmd + Sync + Send)) {
+        cmd.help(self);
+        if let Some(plugin) = commands.provider(cmd.commands()[0]) {
+            writeln!(self.stdout, "Plugin: {} {}", plugin.name, plugin.version).unwrap();
+        }
+    }
+    /// JSON description of `cmd` followed by the plugin providing it, if any.
+    fn help_json(commands: &Commands, cmd: &(dyn Cmd + Sync + Send)) -> Value {
+        let usage: Vec<Value> = cmd
+            .help_messages()
+            .iter()
+            .map(|(args, description)| json!({"args": args, "description": description}))
+            .collect();
+        let plugin = commands
+            .provider(cmd.commands()[0])
+            .map(|plugin| json!({"name": plugin.name, "version": plugin.version}));
+        json!({"commands": cmd.commands(), "usage": usage, "plugin": plugin})
+    }
     pub fn help_all(&mut self) {
         let cmds = self.commands.clone();
         let cmds_ref = cmds.lock();
+        if is_json(self) {
+            let help = cmds_ref
+                .iter()
+                .map(|cmd| Core::help_json(&cmds_ref, &*cmd.as_ref().lock()))
+                .collect();
+            return print_json(self, &Value::Array(help));
+        }
         for cmd in cmds_ref.iter() {
-            cmd.as_ref().lock().help(self);
+            self.help_cmd(&cmds_ref, &*cmd.as_ref().lock());
         }
     }
     pub fn help(&mut self, command: &str) {
@@ -189,8 +269,11 @@ impl Core {
         let cmds_ref = cmds.lock();
         let cmd = cmds_ref.find(command);
         if let Some(cmd) = cmd {
-            let cmd = cmd.as_ref().lock();
-            cmd.help(self);
+            if is_json(self) {
+                let help = Core::help_json(&cmds_ref, &*cmd.as_ref().lock());
+                return print_json(self, &help);
+            }
+            self.help_cmd(&cmds_ref, &*cmd.as_ref().lock());
         } else {
             drop(cmds_ref);
             self.command_not_found(command);
@@ -281,4 +364,77 @@ mod test_core {
             "Error: Execution failed\nCommand seeker is not found.\nSimilar command: seek.\n"
         );
     }
+    #[test]
+    fn test_help_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let locked_env = core.env.clone();
+        locked_env
+            .write()
+            .set_bool("cfg.json", true, &mut core)
+            .unwrap();
+        core.help("m");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"commands\":[\"mode\",\"m\"],\"plugin\":null,\"usage\":[\
+             {\"args\":\"vir\",\"description\":\"Set view mode to virtual address space.\"},\
+             {\"args\":\"phy\",\"description\":\"Set view mode to physical address space.\"}]}\n"
+        );
+        core.stdout = Writer::new_buf();
+        core.help_all();
+        let help: Value = serde_json::from_str(&core.stdout.utf8_string().unwrap()).unwrap();
+        let help = help.as_array().unwrap();
+        assert_eq!(help.len(), core.commands.lock().iter().count());
+        assert!(help
+            .iter()
+            .any(|cmd| cmd["commands"] == json!(["seek", "s"])));
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+    struct Nested;
+    impl Cmd for Nested {
+        fn run(&mut self, core: &mut Core, _args: &[String]) {
+            core.run("nested", &[]);
+        }
+        fn commands(&self) -> &'static [&'static str] {
+            &["nested"]
+        }
+        fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+            &[]
+        }
+    }
+    #[test]
+    fn test_run_nested() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.add_command(Nested);
+        core.run("nested", &[]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Execution failed\nCommand nested is already running.\n"
+        );
+    }
+    #[test]
+    fn test_eval() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.eval("s 0x10");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to evaluate\nNo evaluator is set.\n"
+        );
+        core.stderr = Writer::new_buf();
+        core.set_evaluator(|core, line| {
+            let mut words = line.split_whitespace().map(str::to_owned);
+            let command = words.next().unwrap();
+            core.run(&command, &words.collect::<Vec<_>>());
+        });
+        core.eval("s 0x10");
+        assert_eq!(core.get_loc(), 0x10);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
 }
@@ -1,5 +1,9 @@
-use crate::{error_msg, expect_range, hex::HexWithoutEnv, str_to_num, Cmd, Core, Writer};
+use crate::{
+    error_msg, expect_range, hex::HexWithoutEnv, is_json, print_json, str_to_addr, str_to_num, Cmd,
+    Core, Writer,
+};
 use core::cmp::min;
+use serde_json::json;
 use std::io::Write;
 
 pub struct HexDiff {
@@ -13,7 +17,18 @@ impl HexDiff {
         }
     }
     fn parse_args(core: &mut Core, args: &[String]) -> Option<(u64, u64, u64)> {
-        let args: Vec<_> = args.iter().map(|s| str_to_num(s)).collect();
+        // last argument is always the size, everything before it is an address.
+        let args: Vec<_> = args
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                if i + 1 == args.len() {
+                    str_to_num(s)
+                } else {
+                    str_to_addr(s, core)
+                }
+            })
+            .collect();
         if args.len() == 2 {
             let addr1 = core.get_loc();
             let addr2 = match &args[0] {
@@ -120,6 +135,19 @@ impl Cmd for HexDiff {
             Ok(d) => d,
             Err(e) => return error_msg(core, "Read Failed", &e.to_string()),
         };
+        if is_json(core) {
+            let bytes1: Vec<_> = (0..size).map(|i| data1.get(&(addr1 + i))).collect();
+            let bytes2: Vec<_> = (0..size).map(|i| data2.get(&(addr2 + i))).collect();
+            return print_json(
+                core,
+                &json!({
+                    "address1": addr1,
+                    "address2": addr2,
+                    "bytes1": bytes1,
+                    "bytes2": bytes2,
+                }),
+            );
+        }
         let env = self.inner.get_env(core);
         env.print_double_banner(&mut core.stdout);
         for i in (0..size).step_by(16) {
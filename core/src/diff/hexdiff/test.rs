@@ -540,3 +540,20 @@ fn test_hd_100() {
     }
     operate_on_file(&test_hd_cb, DATA);
 }
+
+#[test]
+fn test_hd_json() {
+    fn test_hd_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io.open(&path.to_string_lossy(), IoMode::READ).unwrap();
+        core.run("hdj", &["0x1".to_owned(), "0x4".to_owned(), "4".to_owned()]);
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"address1\":1,\"address2\":4,\"bytes1\":[1,1,2,3],\"bytes2\":[3,5,8,13]}\n"
+        );
+    }
+    operate_on_file(&test_hd_cb, DATA);
+}
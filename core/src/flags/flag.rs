@@ -0,0 +1,262 @@
+//! commands for adding, deleting, renaming and listing flags.
+
+use super::store::DEFAULT_FLAGSPACE;
+use crate::core::Core;
+use crate::helper::{error_msg, expect, expect_range, is_color, is_json, print_json, str_to_num};
+use crate::Cmd;
+use serde_json::{json, Value};
+use std::io::Write;
+use yansi::Paint;
+
+#[derive(Default)]
+pub struct Flags;
+
+impl Flags {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_str_with_cb(
+                "flags.headerColor",
+                "color.6",
+                "Color used in the header of `flags` command",
+                core,
+                is_color,
+            )
+            .unwrap();
+        Self
+    }
+    fn list(core: &mut Core) {
+        if is_json(core) {
+            let mut flags: Vec<_> = core.flags.iter().collect();
+            flags.sort_by_key(|(name, flag)| (flag.addr, *name));
+            let flags = flags
+                .into_iter()
+                .map(|(name, flag)| {
+                    json!({"name": name, "address": flag.addr, "size": flag.size, "space": flag.space})
+                })
+                .collect();
+            return print_json(core, &Value::Array(flags));
+        }
+        let env = core.env.read();
+        let color = env.get_str("flags.headerColor").unwrap();
+        let (r, g, b) = env.get_color(color).unwrap();
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{}",
+            "Address".rgb(r, g, b),
+            "Size".rgb(r, g, b),
+            "Flagspace".rgb(r, g, b),
+            "Name".rgb(r, g, b)
+        )
+        .unwrap();
+        let mut flags: Vec<_> = core.flags.iter().collect();
+        flags.sort_by_key(|(name, flag)| (flag.addr, *name));
+        for (name, flag) in flags {
+            writeln!(
+                core.stdout,
+                "{: <20}{: <20}{: <20}{}",
+                format!("0x{:x}", flag.addr),
+                format!("0x{:x}", flag.size),
+                flag.space,
+                name
+            )
+            .unwrap();
+        }
+    }
+    fn add(core: &mut Core, args: &[String]) {
+        let size = match args.get(1).map(|s| str_to_num(s)) {
+            Some(Ok(size)) => size,
+            Some(Err(e)) => return error_msg(core, "Failed to add flag", &e.to_string()),
+            None => 1,
+        };
+        let space = args.get(2).map_or(DEFAULT_FLAGSPACE, String::as_str);
+        let loc = core.get_loc();
+        if let Err(e) = core.flags.add(&args[0], loc, size, space) {
+            error_msg(core, "Failed to add flag", &e.to_string());
+        }
+    }
+}
+
+impl Cmd for Flags {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.is_empty() {
+            Self::list(core);
+        } else if args.len() > 3 {
+            expect_range(core, args.len() as u64, 0, 3);
+        } else {
+            Self::add(core, args);
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["flags", "f"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("", "List all flags."),
+            (
+                "[name] <size> <flagspace>",
+                "Add flag at current location with optional size (default 1) and flagspace (default *).",
+            ),
+        ]
+    }
+}
+
+#[derive(Default)]
+pub struct FlagRename;
+
+impl Cmd for FlagRename {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 2 {
+            expect(core, args.len() as u64, 2);
+            return;
+        }
+        if let Err(e) = core.flags.rename(&args[0], &args[1]) {
+            error_msg(core, "Failed to rename flag", &e.to_string());
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["flagRename", "fr"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("[old] [new]", "Rename flag [old] to [new].")]
+    }
+}
+
+#[derive(Default)]
+pub struct FlagDelete;
+
+impl Cmd for FlagDelete {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.flags.remove(&args[0]) {
+            error_msg(core, "Failed to delete flag", &e.to_string());
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["flagDelete", "fd"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("[name]", "Delete flag [name].")]
+    }
+}
+
+#[cfg(test)]
+mod test_flags {
+    use super::*;
+    use crate::writer::Writer;
+
+    #[test]
+    fn test_flags_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("f");
+        core.help("fr");
+        core.help("fd");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Commands: [flags | f]\n\
+             Usage:\n\
+             f\tList all flags.\n\
+             f [name] <size> <flagspace>\tAdd flag at current location with optional size (default 1) and flagspace (default *).\n\
+             Commands: [flagRename | fr]\n\
+             Usage:\n\
+             fr [old] [new]\tRename flag [old] to [new].\n\
+             Commands: [flagDelete | fd]\n\
+             Usage:\n\
+             fd [name]\tDelete flag [name].\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run_at("f", &["main".to_owned(), "0x20".to_owned()], 0x1000);
+        core.run_at(
+            "f",
+            &["entry".to_owned(), "1".to_owned(), "symbols".to_owned()],
+            0x800,
+        );
+        core.run_at("f", &["zero".to_owned()], 0x1000);
+        core.run("fr", &["zero".to_owned(), "a.zero".to_owned()]);
+        core.run("f", &[]);
+        core.run("fd", &["entry".to_owned()]);
+        core.run("f", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Address             Size                Flagspace           Name\n\
+             0x800               0x1                 symbols             entry\n\
+             0x1000              0x1                 *                   a.zero\n\
+             0x1000              0x20                *                   main\n\
+             Address             Size                Flagspace           Name\n\
+             0x1000              0x1                 *                   a.zero\n\
+             0x1000              0x20                *                   main\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_flags_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run_at("f", &["main".to_owned(), "0x20".to_owned()], 0x1000);
+        core.run("e", &["cfg.json=true".to_owned()]);
+        core.run("f", &[]);
+        core.run("e", &["cfg.json=false".to_owned()]);
+        core.run("fj", &[]);
+        let flags = "[{\"address\":4096,\"name\":\"main\",\"size\":32,\"space\":\"*\"}]\n";
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            format!("{flags}{flags}")
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        assert!(!core.env.read().get_bool("cfg.json").unwrap());
+    }
+
+    #[test]
+    fn test_flags_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut flags = Flags;
+        let mut rename = FlagRename;
+        let mut delete = FlagDelete;
+        flags.run(&mut core, &["0x10".to_owned()]);
+        flags.run(&mut core, &["main".to_owned(), "x".to_owned()]);
+        flags.run(
+            &mut core,
+            &[
+                "a".to_owned(),
+                "1".to_owned(),
+                "*".to_owned(),
+                "b".to_owned(),
+            ],
+        );
+        rename.run(&mut core, &["main".to_owned(), "main2".to_owned()]);
+        rename.run(&mut core, &["main".to_owned()]);
+        delete.run(&mut core, &["main".to_owned()]);
+        delete.run(&mut core, &[]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to add flag\n\
+             Flag name must start with a letter or `_` followed by letters, digits, `_` or `.`.\n\
+             Error: Failed to add flag\n\
+             invalid digit found in string\n\
+             Arguments Error: Expected between 0 and 3 arguments, found 4.\n\
+             Error: Failed to rename flag\n\
+             Flag not found.\n\
+             Arguments Error: Expected 2 argument(s), found 1.\n\
+             Error: Failed to delete flag\n\
+             Flag not found.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n"
+        );
+    }
+}
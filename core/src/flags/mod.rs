@@ -0,0 +1,15 @@
+//! commands for handling flags (named addresses).
+
+mod flag;
+mod store;
+
+use self::flag::{FlagDelete, FlagRename, Flags};
+pub use self::store::{Flag, FlagErr, FlagStore, DEFAULT_FLAGSPACE};
+use crate::core::Core;
+
+pub fn register_flags(core: &mut Core) {
+    let flags = Flags::new(core);
+    core.add_command(flags);
+    core.add_command(FlagRename);
+    core.add_command(FlagDelete);
+}
@@ -0,0 +1,133 @@
+//! storage for named addresses.
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Flagspace used when no flagspace is given.
+pub const DEFAULT_FLAGSPACE: &str = "*";
+
+/// Named address range.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flag {
+    pub addr: u64,
+    pub size: u64,
+    pub space: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlagErr {
+    InvalidName,
+    NotFound,
+    AlreadyExist,
+}
+
+impl fmt::Display for FlagErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagErr::InvalidName => write!(
+                f,
+                "Flag name must start with a letter or `_` followed by letters, digits, `_` or `.`."
+            ),
+            FlagErr::NotFound => write!(f, "Flag not found."),
+            FlagErr::AlreadyExist => write!(f, "Flag already exist."),
+        }
+    }
+}
+
+/// Mapping between flag names and the address ranges they refer to.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct FlagStore {
+    flags: BTreeMap<String, Flag>,
+}
+
+impl FlagStore {
+    /// Check that `name` can be used as flag name, flag names must never be
+    /// confused with numbers or command line symbols.
+    #[must_use]
+    pub fn is_valid_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    }
+    /// Add new flag or replace existing one with the same name.
+    pub fn add(&mut self, name: &str, addr: u64, size: u64, space: &str) -> Result<(), FlagErr> {
+        if !Self::is_valid_name(name) {
+            return Err(FlagErr::InvalidName);
+        }
+        let flag = Flag {
+            addr,
+            size,
+            space: space.to_owned(),
+        };
+        self.flags.insert(name.to_owned(), flag);
+        Ok(())
+    }
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Flag> {
+        self.flags.get(name)
+    }
+    pub fn remove(&mut self, name: &str) -> Result<Flag, FlagErr> {
+        self.flags.remove(name).ok_or(FlagErr::NotFound)
+    }
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), FlagErr> {
+        if !Self::is_valid_name(new) {
+            return Err(FlagErr::InvalidName);
+        }
+        if self.flags.contains_key(new) {
+            return Err(FlagErr::AlreadyExist);
+        }
+        let flag = self.remove(old)?;
+        self.flags.insert(new.to_owned(), flag);
+        Ok(())
+    }
+    /// Iterate over flags sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Flag)> {
+        self.flags.iter().map(|(name, flag)| (name.as_str(), flag))
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_flag_store {
+    use super::*;
+
+    #[test]
+    fn test_valid_names() {
+        assert!(FlagStore::is_valid_name("main"));
+        assert!(FlagStore::is_valid_name("_start"));
+        assert!(FlagStore::is_valid_name("sym.imp.printf"));
+        assert!(!FlagStore::is_valid_name(""));
+        assert!(!FlagStore::is_valid_name("0x10"));
+        assert!(!FlagStore::is_valid_name(".text"));
+        assert!(!FlagStore::is_valid_name("a-b"));
+    }
+
+    #[test]
+    fn test_add_remove_rename() {
+        let mut flags = FlagStore::default();
+        assert!(flags.is_empty());
+        flags.add("main", 0x1000, 0x20, DEFAULT_FLAGSPACE).unwrap();
+        flags.add("entry", 0x800, 1, "symbols").unwrap();
+        assert_eq!(flags.add("1st", 0, 1, "*"), Err(FlagErr::InvalidName));
+        assert_eq!(flags.get("main").unwrap().addr, 0x1000);
+        flags.add("main", 0x2000, 0x20, DEFAULT_FLAGSPACE).unwrap();
+        assert_eq!(flags.get("main").unwrap().addr, 0x2000);
+        assert_eq!(flags.rename("main", "entry"), Err(FlagErr::AlreadyExist));
+        assert_eq!(flags.rename("main", "-"), Err(FlagErr::InvalidName));
+        assert_eq!(flags.rename("foo", "bar"), Err(FlagErr::NotFound));
+        flags.rename("main", "main2").unwrap();
+        assert_eq!(flags.get("main"), None);
+        let names: Vec<&str> = flags.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["entry", "main2"]);
+        let flag = flags.remove("entry").unwrap();
+        assert_eq!(flag.space, "symbols");
+        assert_eq!(flags.remove("entry"), Err(FlagErr::NotFound));
+    }
+}
@@ -28,6 +28,11 @@ pub fn str_to_num(n: &str) -> Result<u64, num::ParseIntError> {
     n.parse::<u64>()
 }
 
+/// Same as [`str_to_num`] but also accepts flag names.
+pub fn str_to_addr(n: &str, core: &Core) -> Result<u64, num::ParseIntError> {
+    str_to_num(n).or_else(|e| core.flags.get(n).map(|flag| flag.addr).ok_or(e))
+}
+
 pub fn expect(core: &mut Core, args_len: u64, expect: u64) {
     let (r, g, b) = core.env.read().get_color("color.4").unwrap();
     let error = "Arguments Error";
@@ -73,6 +78,16 @@ pub fn error_msg(core: &mut Core, title: &str, msg: &str) {
     writeln!(core.stderr, "{msg}").unwrap();
 }
 
+/// Returns `true` if commands should print their output as JSON.
+#[must_use]
+pub fn is_json(core: &Core) -> bool {
+    core.env.read().get_bool("cfg.json").unwrap_or(false)
+}
+
+pub fn print_json(core: &mut Core, value: &serde_json::Value) {
+    writeln!(core.stdout, "{value}").unwrap();
+}
+
 pub fn panic_msg(core: &mut Core, title: &str, msg: &str) -> ! {
     let (r, g, b) = core.env.read().get_color("color.4").unwrap();
     writeln!(
@@ -131,6 +146,15 @@ mod test_helper {
         str_to_num("0x12345123451234512").unwrap_err();
     }
 
+    #[test]
+    fn test_str_to_addr() {
+        let mut core = Core::new_no_colors();
+        core.flags.add("main", 0x1337, 1, "*").unwrap();
+        assert_eq!(str_to_addr("0x20", &core).unwrap(), 0x20);
+        assert_eq!(str_to_addr("main", &core).unwrap(), 0x1337);
+        str_to_addr("foo", &core).unwrap_err();
+    }
+
     #[test]
     fn test_except() {
         let mut core = Core::new_no_colors();
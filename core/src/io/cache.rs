@@ -0,0 +1,246 @@
+//! commands for listing, discarding and committing writes kept in the IO cache.
+
+use crate::helper::{error_msg, expect, is_json, print_json, str_to_addr, str_to_num};
+use crate::{cmd::Cmd, core::Core};
+use rair_env::Environment;
+use serde_json::{json, Value};
+use std::io::Write;
+use yansi::Paint;
+
+fn set_cache(_: &str, value: bool, _: &Environment<Core>, core: &mut Core) -> bool {
+    core.io.set_cache(value);
+    true
+}
+
+/// Parse optional `[paddr] [size]` arguments, the whole physical address space is used if
+/// they are missing.
+fn cache_range(core: &mut Core, args: &[String], title: &str) -> Option<(u64, u64)> {
+    match args {
+        [] => Some((0, u64::MAX)),
+        [paddr, size] => {
+            let paddr = match str_to_addr(paddr, core) {
+                Ok(paddr) => paddr,
+                Err(e) => {
+                    let msg = format!("Failed to parse {}, {e}.", "paddr".primary().bold());
+                    error_msg(core, title, &msg);
+                    return None;
+                }
+            };
+            let size = match str_to_num(size) {
+                Ok(size) => size,
+                Err(e) => {
+                    let msg = format!("Failed to parse {}, {e}.", "size".primary().bold());
+                    error_msg(core, title, &msg);
+                    return None;
+                }
+            };
+            Some((paddr, size))
+        }
+        _ => {
+            expect(core, args.len() as u64, 2);
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ListCache;
+
+impl ListCache {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_bool_with_cb(
+                "io.cache",
+                false,
+                "Keep writes in memory instead of writing them into files, allows writing to read only files",
+                core,
+                set_cache,
+            )
+            .unwrap();
+        Self
+    }
+}
+
+impl Cmd for ListCache {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        let ranges = core.io.cache().ranges();
+        if is_json(core) {
+            let ranges = ranges
+                .iter()
+                .map(|(paddr, data)| json!({"paddr": paddr, "size": data.len(), "data": data}))
+                .collect();
+            return print_json(core, &Value::Array(ranges));
+        }
+        let (r, g, b) = core.env.read().get_color("color.6").unwrap();
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}",
+            "Physical Address".rgb(r, g, b),
+            "Size".rgb(r, g, b),
+            "Data".rgb(r, g, b)
+        )
+        .unwrap();
+        for (paddr, data) in ranges {
+            write!(
+                core.stdout,
+                "{: <20}{: <20}",
+                format!("0x{paddr:x}"),
+                format!("0x{:x}", data.len())
+            )
+            .unwrap();
+            for byte in data.iter().take(16) {
+                write!(core.stdout, "{byte:02x}").unwrap();
+            }
+            if data.len() > 16 {
+                write!(core.stdout, "...").unwrap();
+            }
+            writeln!(core.stdout).unwrap();
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["cache"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List writes kept in the IO cache, see `io.cache`.")]
+    }
+}
+
+#[derive(Default)]
+pub struct DiscardCache;
+
+impl Cmd for DiscardCache {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if let Some((paddr, size)) = cache_range(core, args, "Failed to discard cache") {
+            core.io.discard_cache(paddr, size);
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["cacheDiscard"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("", "Discard all writes kept in the IO cache."),
+            (
+                "[paddr] [size]",
+                "Discard writes kept in the IO cache within given physical range.",
+            ),
+        ]
+    }
+}
+
+#[derive(Default)]
+pub struct CommitCache;
+
+impl Cmd for CommitCache {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        let title = "Failed to commit cache";
+        if let Some((paddr, size)) = cache_range(core, args, title) {
+            if let Err(e) = core.io.commit_cache(paddr, size) {
+                error_msg(core, title, &e.to_string());
+            }
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["cacheCommit"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("", "Write all writes kept in the IO cache into files."),
+            (
+                "[paddr] [size]",
+                "Write writes kept in the IO cache within given physical range into files.",
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test_cache {
+    use super::*;
+    use crate::writer::Writer;
+    use rair_io::IoMode;
+    use std::path::Path;
+    use test_file::*;
+    #[test]
+    fn test_cache_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("cache");
+        core.help("cacheDiscard");
+        core.help("cacheCommit");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [cache]\n\
+             Usage:\n\
+             cache\tList writes kept in the IO cache, see `io.cache`.\n\
+             Command: [cacheDiscard]\n\
+             Usage:\n\
+             cacheDiscard\tDiscard all writes kept in the IO cache.\n\
+             cacheDiscard [paddr] [size]\tDiscard writes kept in the IO cache within given physical range.\n\
+             Command: [cacheCommit]\n\
+             Usage:\n\
+             cacheCommit\tWrite all writes kept in the IO cache into files.\n\
+             cacheCommit [paddr] [size]\tWrite writes kept in the IO cache within given physical range into files.\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+    fn test_cache_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io.open(&path.to_string_lossy(), IoMode::READ).unwrap();
+        core.io
+            .open_at("malloc://0x20", IoMode::READ | IoMode::WRITE, 0x100)
+            .unwrap();
+        core.run("wx", &["1234".to_owned()]);
+        core.run("e", &["io.cache=true".to_owned()]);
+        core.run("wx", &["1234".to_owned()]);
+        core.set_loc(0x100);
+        core.run("wx", &["00112233445566778899aabbccddeeff00".to_owned()]);
+        core.run("cache", &[]);
+        core.run("cachej", &["0x10".to_owned()]);
+        core.run("cacheCommit", &[]);
+        core.run("cacheCommit", &["0x100".to_owned(), "0x20".to_owned()]);
+        core.run("cacheDiscard", &["0x1".to_owned()]);
+        core.run("cacheDiscard", &["0x0".to_owned(), "0x1".to_owned()]);
+        core.run("cachej", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Physical Address    Size                Data\n\
+             0x0                 0x2                 1234\n\
+             0x100               0x11                00112233445566778899aabbccddeeff...\n\
+             [{\"data\":[52],\"paddr\":1,\"size\":1}]\n"
+        );
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Read Failed\nFile Not Writable\n\
+             Arguments Error: Expected 0 argument(s), found 1.\n\
+             Error: Failed to commit cache\nFile Not Writable\n\
+             Arguments Error: Expected 2 argument(s), found 1.\n"
+        );
+        let mut data = [0; 2];
+        core.io.pread(0x100, &mut data).unwrap();
+        assert_eq!(data, [0x00, 0x11]);
+        core.stdout = Writer::new_buf();
+        core.stderr = Writer::new_buf();
+        core.run("e", &["io.cache=false".to_owned()]);
+        core.run("cacheDiscard", &[]);
+        core.set_loc(0);
+        core.run("wx", &["1234".to_owned()]);
+        assert!(core.io.cache().is_empty());
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Read Failed\nFile Not Writable\n"
+        );
+    }
+    #[test]
+    fn test_cache() {
+        operate_on_file(&test_cache_cb, &[0; 0x10]);
+    }
+}
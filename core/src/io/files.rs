@@ -1,9 +1,14 @@
 //! commands for opening, closing and listing files.
 
-use crate::helper::{error_msg, expect, expect_range, is_color, str_to_num};
+use crate::helper::{
+    error_msg, expect, expect_range, is_color, is_json, print_json, str_to_addr, str_to_num,
+};
+use crate::loader::auto_load;
 use crate::{cmd::Cmd, core::Core};
 use rair_io::IoMode;
+use serde_json::{json, Value};
 use std::io::Write;
+use std::path::Path;
 use yansi::Paint;
 
 #[derive(Default)]
@@ -38,6 +43,22 @@ impl Cmd for ListFiles {
             expect(core, args.len() as u64, 0);
             return;
         }
+        if is_json(core) {
+            let files = core
+                .io
+                .uri_iter()
+                .map(|file| {
+                    json!({
+                        "hndl": file.hndl(),
+                        "paddr": file.paddr_base(),
+                        "size": file.size(),
+                        "perm": file.perm().to_string(),
+                        "uri": file.name(),
+                    })
+                })
+                .collect();
+            return print_json(core, &Value::Array(files));
+        }
         let env = core.env.read();
         let color = env.get_str("maps.headerColor").unwrap();
         let (r, g, b) = env.get_color(color).unwrap();
@@ -103,7 +124,7 @@ impl Cmd for OpenFile {
                 Ok(perm) => perm,
                 Err(e) => return error_msg(core, "Failed to parse permission", &e),
             };
-            addr = match str_to_num(&args[2]) {
+            addr = match str_to_addr(&args[2], core) {
                 Ok(addr) => Some(addr),
                 Err(e) => {
                     let err_str = format!("{e}");
@@ -112,7 +133,7 @@ impl Cmd for OpenFile {
                 }
             }
         } else if args.len() == 2 {
-            if let Ok(a) = str_to_num(&args[1]) {
+            if let Ok(a) = str_to_addr(&args[1], core) {
                 addr = Some(a);
                 uri = &args[0];
             } else {
@@ -130,9 +151,12 @@ impl Cmd for OpenFile {
             Some(addr) => core.io.open_at(uri, perm, addr),
             None => core.io.open(uri, perm),
         };
-        if let Err(e) = result {
-            let err_str = format!("{e}");
-            error_msg(core, "Failed to open file", &err_str);
+        match result {
+            Ok(hndl) => auto_load(core, hndl),
+            Err(e) => {
+                let err_str = format!("{e}");
+                error_msg(core, "Failed to open file", &err_str);
+            }
         }
     }
 }
@@ -167,10 +191,264 @@ impl Cmd for CloseFile {
     }
 }
 
+#[derive(Default)]
+pub struct ResizeFile;
+
+impl Cmd for ResizeFile {
+    fn commands(&self) -> &'static [&'static str] {
+        &["resize"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[hndl] [size]",
+            "Truncate or grow file with given hndl to [size] bytes.",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 2 {
+            expect(core, args.len() as u64, 2);
+            return;
+        }
+        let hndl = match str_to_num(&args[0]) {
+            Ok(hndl) => hndl,
+            Err(e) => {
+                let err_str = format!("{e}");
+                error_msg(core, "Invalid hndl", &err_str);
+                return;
+            }
+        };
+        let size = match str_to_num(&args[1]) {
+            Ok(size) => size,
+            Err(e) => {
+                let err_str = format!("{e}");
+                error_msg(core, "Invalid size", &err_str);
+                return;
+            }
+        };
+        if let Err(e) = core.io.resize(hndl, size) {
+            let err_str = format!("{e}");
+            error_msg(core, "Failed to resize file", &err_str);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Commit;
+
+impl Cmd for Commit {
+    fn commands(&self) -> &'static [&'static str] {
+        &["commit"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            (
+                "-f [hndl]",
+                "Overwrite file with given hndl with its Copy-On-Write changes.",
+            ),
+            (
+                "[hndl] [path]",
+                "Save Copy-On-Write changes of file with given hndl into [path].",
+            ),
+        ]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        let (force, args) = match args.split_first() {
+            Some((flag, rest)) if flag == "-f" => (true, rest),
+            _ => (false, args),
+        };
+        if args.is_empty() || args.len() > 2 {
+            expect_range(core, args.len() as u64, 1, 2);
+            return;
+        }
+        let hndl = match str_to_num(&args[0]) {
+            Ok(hndl) => hndl,
+            Err(e) => {
+                let err_str = format!("{e}");
+                error_msg(core, "Invalid hndl", &err_str);
+                return;
+            }
+        };
+        let desc = core.io.hndl_to_desc(hndl);
+        let cow = desc.is_some_and(|desc| desc.perm().contains(IoMode::COW));
+        if args.len() == 1 && cow && !force {
+            let name = desc.unwrap().name().to_owned();
+            writeln!(core.stdout, "Run `commit -f {hndl}` to overwrite {name}.").unwrap();
+            return;
+        }
+        let path = args.get(1).map(Path::new);
+        if let Err(e) = core.io.commit(hndl, path) {
+            let err_str = format!("{e}");
+            error_msg(core, "Failed to commit changes", &err_str);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Archive;
+
+impl Archive {
+    fn list(core: &mut Core, uri: &str) {
+        let members = match core.io.archive_members(uri) {
+            Ok(members) => members,
+            Err(e) => return error_msg(core, "Failed to list archive members", &e.to_string()),
+        };
+        if is_json(core) {
+            let members = members
+                .iter()
+                .map(|member| json!({"name": member.name, "size": member.size, "uri": member.uri}))
+                .collect();
+            return print_json(core, &Value::Array(members));
+        }
+        let env = core.env.read();
+        let color = env.get_str("files.headerColor").unwrap();
+        let (r, g, b) = env.get_color(color).unwrap();
+        writeln!(core.stdout, "{}", "Size		Name".rgb(r, g, b)).unwrap();
+        for member in members {
+            writeln!(core.stdout, "0x{:08x}\t{}", member.size, member.name).unwrap();
+        }
+    }
+}
+
+impl Cmd for Archive {
+    fn commands(&self) -> &'static [&'static str] {
+        &["archive"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("[URI]", "List members of zip or tar archive at [URI]."),
+            (
+                "[URI] [member] [addr] ...",
+                "Open every [member] of archive at [URI] in readonly mode at physical address [addr].",
+            ),
+        ]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.is_empty() {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if args.len() == 1 {
+            return Self::list(core, &args[0]);
+        }
+        if args.len().is_multiple_of(2) {
+            return error_msg(
+                core,
+                "Failed to open archive members",
+                "Expected an address after every member.",
+            );
+        }
+        let members = match core.io.archive_members(&args[0]) {
+            Ok(members) => members,
+            Err(e) => return error_msg(core, "Failed to list archive members", &e.to_string()),
+        };
+        for pair in args[1..].chunks(2) {
+            let Some(member) = members.iter().find(|member| member.name == pair[0]) else {
+                let err_str = format!("Archive has no member named {}.", pair[0]);
+                error_msg(core, "Failed to open archive member", &err_str);
+                continue;
+            };
+            let addr = match str_to_addr(&pair[1], core) {
+                Ok(addr) => addr,
+                Err(e) => {
+                    error_msg(core, "Failed to parse address", &e.to_string());
+                    continue;
+                }
+            };
+            if let Err(e) = core.io.open_at(&member.uri, IoMode::READ, addr) {
+                error_msg(core, "Failed to open archive member", &e.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Ranges;
+
+impl Ranges {
+    /// Split the physical address space of file `hndl` into populated ranges and holes.
+    fn ranges(core: &Core, hndl: u64) -> Option<Vec<(u64, u64, bool)>> {
+        let desc = core.io.hndl_to_desc(hndl)?;
+        let mut ranges = Vec::new();
+        let mut start = desc.paddr_base();
+        for hole in desc.holes() {
+            if hole.start > start {
+                ranges.push((start, hole.start, true));
+            }
+            ranges.push((hole.start, hole.end, false));
+            start = hole.end;
+        }
+        let end = desc.paddr_base() + desc.size();
+        if end > start {
+            ranges.push((start, end, true));
+        }
+        Some(ranges)
+    }
+}
+
+impl Cmd for Ranges {
+    fn commands(&self) -> &'static [&'static str] {
+        &["ranges", "holes"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[hndl]",
+            "List populated ranges and holes of sparse file with given hndl.",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        let hndl = match str_to_num(&args[0]) {
+            Ok(hndl) => hndl,
+            Err(e) => {
+                let err_str = format!("{e}");
+                error_msg(core, "Invalid hndl", &err_str);
+                return;
+            }
+        };
+        let Some(ranges) = Self::ranges(core, hndl) else {
+            let err_str = format!("Handle {hndl} doesn't exist.");
+            return error_msg(core, "Failed to list ranges", &err_str);
+        };
+        if is_json(core) {
+            let ranges = ranges
+                .iter()
+                .map(|(start, end, populated)| {
+                    json!({"start": start, "end": end, "populated": populated})
+                })
+                .collect();
+            return print_json(core, &Value::Array(ranges));
+        }
+        let env = core.env.read();
+        let color = env.get_str("files.headerColor").unwrap();
+        let (r, g, b) = env.get_color(color).unwrap();
+        writeln!(
+            core.stdout,
+            "{}",
+            "Start address\tEnd address\tsize\t\tType".rgb(r, g, b)
+        )
+        .unwrap();
+        for (start, end, populated) in ranges {
+            let kind = if populated { "data" } else { "hole" };
+            writeln!(
+                core.stdout,
+                "0x{start:08x}\t0x{end:08x}\t0x{:08x}\t{kind}",
+                end - start
+            )
+            .unwrap();
+        }
+    }
+}
+
 #[cfg(test)]
 mod test_files {
     use super::*;
     use crate::{writer::Writer, CmdOps};
+    use core::slice;
+    use std::fs;
+    use test_file::*;
     #[test]
     fn test_docs() {
         let mut core = Core::new_no_colors();
@@ -318,4 +596,288 @@ mod test_files {
         // what in between is different between Windows and *Nix
         assert!(err.ends_with("Error: Failed to close file\nHandle Does not exist.\n"));
     }
+
+    #[test]
+    fn test_files_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("o", &["rw".to_owned(), "malloc://0x50".to_owned()]);
+        core.run("filesj", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "[{\"hndl\":0,\"paddr\":0,\"perm\":\"WRITE | READ\",\"size\":80,\"uri\":\"malloc://0x50\"}]\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_commit_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        Commit.help(&mut core);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [commit]\n\
+             Usage:\n\
+             commit -f [hndl]\tOverwrite file with given hndl with its Copy-On-Write changes.\n\
+             commit [hndl] [path]\tSave Copy-On-Write changes of file with given hndl into [path].\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    fn commit_cb(paths: &[&Path]) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut commit = Commit;
+        let cow = paths[0].to_string_lossy().to_string();
+        let out = paths[1].to_string_lossy().to_string();
+        core.run("open", &["c".to_owned(), cow.clone()]);
+        core.run("wx", &["ffff".to_owned()]);
+        let mut expected = DATA.to_vec();
+        expected[..2].copy_from_slice(&[0xff, 0xff]);
+
+        commit.run(&mut core, &["0".to_owned(), out]);
+        assert_eq!(fs::read(paths[1]).unwrap(), expected);
+        assert_eq!(fs::read(paths[0]).unwrap(), DATA);
+        // overwriting the original file needs confirmation
+        commit.run(&mut core, &["0".to_owned()]);
+        commit.run(&mut core, &["0".to_owned()]);
+        assert_eq!(fs::read(paths[0]).unwrap(), DATA);
+        commit.run(&mut core, &["-f".to_owned(), "0".to_owned()]);
+        assert_eq!(fs::read(paths[0]).unwrap(), expected);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            format!(
+                "Run `commit -f 0` to overwrite {cow}.\n\
+                 Run `commit -f 0` to overwrite {cow}.\n"
+            )
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+    #[test]
+    fn test_commit() {
+        operate_on_files(&commit_cb, &[DATA, &[]]);
+    }
+
+    #[test]
+    fn test_commit_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut commit = Commit;
+        core.run("open", &["rw".to_owned(), "malloc://0x50".to_owned()]);
+        commit.run(&mut core, &[]);
+        commit.run(&mut core, &["-f".to_owned()]);
+        commit.run(&mut core, &["0b2".to_owned()]);
+        commit.run(&mut core, &["1".to_owned()]);
+        commit.run(&mut core, &["0".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Arguments Error: Expected between 1 and 2 arguments, found 0.\n\
+             Arguments Error: Expected between 1 and 2 arguments, found 0.\n\
+             Error: Invalid hndl\n\
+             invalid digit found in string\n\
+             Error: Failed to commit changes\n\
+             Handle Does not exist.\n\
+             Error: Failed to commit changes\n\
+             File is not opened in Copy-On-Write mode.\n"
+        );
+    }
+
+    fn archive_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut archive = Archive;
+        let path = path.to_string_lossy().to_string();
+        archive.help(&mut core);
+        archive.run(&mut core, slice::from_ref(&path));
+        core.run("archivej", slice::from_ref(&path));
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            format!(
+                "Command: [archive]\n\
+                 Usage:\n\
+                 archive [URI]\tList members of zip or tar archive at [URI].\n\
+                 archive [URI] [member] [addr] ...\tOpen every [member] of archive at [URI] in readonly mode at physical address [addr].\n\
+                 Size\t\tName\n\
+                 0x00000069\tdata.bin\n\
+                 0x00000006\tfw/boot.img\n\
+                 [{{\"name\":\"data.bin\",\"size\":105,\"uri\":\"tar://{path}!data.bin\"}},\
+                 {{\"name\":\"fw/boot.img\",\"size\":6,\"uri\":\"tar://{path}!fw/boot.img\"}}]\n"
+            )
+        );
+        core.stdout = Writer::new_buf();
+        archive.run(
+            &mut core,
+            &[
+                path.clone(),
+                "fw/boot.img".to_owned(),
+                "0x1000".to_owned(),
+                "data.bin".to_owned(),
+                "0x2000".to_owned(),
+            ],
+        );
+        let mut data = vec![0; 6];
+        core.io.pread(0x1000, &mut data).unwrap();
+        assert_eq!(data, b"booted");
+        let mut data = vec![0; DATA.len()];
+        core.io.pread(0x2000, &mut data).unwrap();
+        assert_eq!(data, DATA);
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        core.stderr = Writer::new_buf();
+
+        archive.run(&mut core, &[]);
+        archive.run(&mut core, &[path.clone(), "data.bin".to_owned()]);
+        archive.run(
+            &mut core,
+            &[
+                path.clone(),
+                "missing".to_owned(),
+                "0x3000".to_owned(),
+                "data.bin".to_owned(),
+                "0b3000".to_owned(),
+            ],
+        );
+        archive.run(&mut core, &["file_that_doesnt_exist".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        let err = core.stderr.utf8_string().unwrap();
+        assert!(err.starts_with(
+            "Arguments Error: Expected 1 argument(s), found 0.\n\
+             Error: Failed to open archive members\n\
+             Expected an address after every member.\n\
+             Error: Failed to open archive member\n\
+             Archive has no member named missing.\n\
+             Error: Failed to parse address\n\
+             invalid digit found in string\n\
+             Error: Failed to list archive members\n"
+        ));
+    }
+
+    #[test]
+    fn test_archive() {
+        operate_on_file(
+            &archive_cb,
+            &tar(&[("data.bin", DATA), ("fw/boot.img", b"booted")]),
+        );
+    }
+
+    fn ranges_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("ranges");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Commands: [ranges | holes]\n\
+             Usage:\n\
+             holes [hndl]\tList populated ranges and holes of sparse file with given hndl.\n"
+        );
+        core.stdout = Writer::new_buf();
+        let uri = format!("ihex://{}?fill=0xff&holes", path.to_string_lossy());
+        core.io.open_at(&uri, IoMode::READ, 0x100).unwrap();
+        core.run("ranges", &["0".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Start address\tEnd address\tsize\t\tType\n\
+             0x00000100\t0x00000102\t0x00000002\tdata\n\
+             0x00000102\t0x00000104\t0x00000002\thole\n\
+             0x00000104\t0x00000106\t0x00000002\tdata\n"
+        );
+        core.stdout = Writer::new_buf();
+        core.run("holesj", &["0".to_owned()]);
+        let out: Value = serde_json::from_str(&core.stdout.utf8_string().unwrap()).unwrap();
+        assert_eq!(
+            out[1],
+            json!({"start": 0x102u64, "end": 0x104u64, "populated": false})
+        );
+        let sparse = core.io.pread_sparce(0x100, 6).unwrap();
+        assert_eq!(
+            sparse.keys().copied().collect::<Vec<_>>(),
+            [0x100, 0x101, 0x104, 0x105]
+        );
+        let mut data = [0; 6];
+        core.io.pread(0x100, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 0xff, 0xff, 3, 4]);
+        core.stdout = Writer::new_buf();
+        core.run("ranges", &[]);
+        core.run("ranges", &["x".to_owned()]);
+        core.run("ranges", &["1".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Arguments Error: Expected 1 argument(s), found 0.\n\
+             Error: Invalid hndl\n\
+             invalid digit found in string\n\
+             Error: Failed to list ranges\n\
+             Handle 1 doesn't exist.\n"
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        operate_on_file(
+            &ranges_cb,
+            b":020000000102FB\n:020004000304F3\n:00000001FF\n",
+        );
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("resize");
+        core.run(
+            "o",
+            &[
+                "rw".to_owned(),
+                "malloc://0x10".to_owned(),
+                "0x0".to_owned(),
+            ],
+        );
+        core.run(
+            "o",
+            &[
+                "rw".to_owned(),
+                "malloc://0x10".to_owned(),
+                "0x20".to_owned(),
+            ],
+        );
+        core.run("resize", &["0".to_owned(), "0x20".to_owned()]);
+        core.run("resize", &["0".to_owned(), "0x8".to_owned()]);
+        core.run("resize", &["1".to_owned(), "0x100".to_owned()]);
+        core.run("resize", &["0".to_owned(), "0x21".to_owned()]);
+        core.run("resize", &["2".to_owned(), "0x10".to_owned()]);
+        core.run("resize", &["0".to_owned(), "0".to_owned()]);
+        core.run("resize", &["x".to_owned(), "0x10".to_owned()]);
+        core.run("resize", &["0".to_owned(), "x".to_owned()]);
+        core.run("resize", &["0".to_owned()]);
+        assert_eq!(core.io.hndl_to_desc(0).unwrap().size(), 0x8);
+        assert_eq!(core.io.hndl_to_desc(1).unwrap().size(), 0x100);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [resize]\n\
+             Usage:\n\
+             resize [hndl] [size]\tTruncate or grow file with given hndl to [size] bytes.\n"
+        );
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to resize file\n\
+             Phyiscal addresses overlap.\n\
+             Error: Failed to resize file\n\
+             Handle Does not exist.\n\
+             Error: Failed to resize file\n\
+             Can not resize file to 0 bytes.\n\
+             Error: Invalid hndl\n\
+             invalid digit found in string\n\
+             Error: Invalid size\n\
+             invalid digit found in string\n\
+             Arguments Error: Expected 2 argument(s), found 1.\n"
+        );
+    }
 }
@@ -1,7 +1,11 @@
-//! commands for mapping/unmapping memory regions and listing mapped regions as well.
+//! commands for mapping/unmapping memory regions, listing mapped regions and managing banks.
 
-use crate::helper::{error_msg, expect, is_color, str_to_num};
+use crate::helper::{
+    error_msg, expect, expect_range, is_color, is_json, print_json, str_to_addr, str_to_num,
+};
 use crate::{cmd::Cmd, core::Core};
+use rair_io::{MapPerm, RIOMap};
+use serde_json::{json, Value};
 use std::io::Write;
 use yansi::Paint;
 
@@ -21,15 +25,15 @@ fn unmap_error(core: &mut Core, name: &str, err: &str) {
 
 impl Cmd for Map {
     fn run(&mut self, core: &mut Core, args: &[String]) {
-        if args.len() != 3 {
-            expect(core, args.len() as u64, 3);
+        if args.len() < 3 || args.len() > 6 {
+            expect_range(core, args.len() as u64, 3, 6);
             return;
         }
-        let phy = match str_to_num(&args[0]) {
+        let phy = match str_to_addr(&args[0], core) {
             Ok(p) => p,
             Err(e) => return map_error(core, "phy", &e.to_string()),
         };
-        let vir = match str_to_num(&args[1]) {
+        let vir = match str_to_addr(&args[1], core) {
             Ok(v) => v,
             Err(e) => return map_error(core, "vir", &e.to_string()),
         };
@@ -37,10 +41,29 @@ impl Cmd for Map {
             Ok(s) => s,
             Err(e) => return map_error(core, "size", &e.to_string()),
         };
+        let perm = match args.get(3).map(|perm| perm.parse::<MapPerm>()) {
+            Some(Ok(perm)) => perm,
+            Some(Err(e)) => return error_msg(core, "Failed to map memory", &e.to_string()),
+            None => MapPerm::all(),
+        };
+        let name = args.get(4).cloned().unwrap_or_default();
+        let priority = match args.get(5).map(|priority| str_to_num(priority)) {
+            Some(Ok(priority)) => priority,
+            Some(Err(e)) => return map_error(core, "priority", &e.to_string()),
+            None => 0,
+        };
         if size == 0 {
             return;
         }
-        if let Err(e) = core.io.map(phy, vir, size) {
+        let map = RIOMap {
+            paddr: phy,
+            vaddr: vir,
+            size,
+            name,
+            perm,
+            priority,
+        };
+        if let Err(e) = core.io.add_map(map) {
             error_msg(core, "Failed to map memory", &e.to_string());
         }
     }
@@ -50,8 +73,8 @@ impl Cmd for Map {
 
     fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
         &[(
-            "[phy] [vir] [size]",
-            "Map region from physical address space to virtual address space.",
+            "[phy] [vir] [size] <perm> <name> <priority>",
+            "Map region from physical address space to virtual address space with optional permissions (rwx by default), name and priority (0 by default). Maps with higher priority hide overlapping maps.",
         )]
     }
 }
@@ -65,7 +88,7 @@ impl Cmd for UnMap {
             expect(core, args.len() as u64, 2);
             return;
         }
-        let vir = match str_to_num(&args[0]) {
+        let vir = match str_to_addr(&args[0], core) {
             Ok(v) => v,
             Err(e) => return unmap_error(core, "vir", &e.to_string()),
         };
@@ -115,24 +138,47 @@ impl Cmd for ListMap {
             expect(core, args.len() as u64, 0);
             return;
         }
+        if is_json(core) {
+            let maps = core
+                .io
+                .map_iter()
+                .map(|map| {
+                    json!({
+                        "vaddr": map.vaddr,
+                        "paddr": map.paddr,
+                        "size": map.size,
+                        "perm": map.perm.to_string(),
+                        "priority": map.priority,
+                        "name": map.name,
+                    })
+                })
+                .collect();
+            return print_json(core, &Value::Array(maps));
+        }
         let env = core.env.read();
         let color = env.get_str("maps.headerColor").unwrap();
         let (r, g, b) = env.get_color(color).unwrap();
         writeln!(
             core.stdout,
-            "{: <20}{: <20}{}",
+            "{: <20}{: <20}{: <20}{: <8}{: <10}{}",
             "Virtual Address".rgb(r, g, b),
             "Physical Address".rgb(r, g, b),
-            "Size".rgb(r, g, b)
+            "Size".rgb(r, g, b),
+            "Perm".rgb(r, g, b),
+            "Priority".rgb(r, g, b),
+            "Name".rgb(r, g, b)
         )
         .unwrap();
         for map in core.io.map_iter() {
             writeln!(
                 core.stdout,
-                "{: <20}{: <20}0x{:x}",
+                "{: <20}{: <20}{: <20}{: <8}{: <10}{}",
                 format!("0x{:x}", map.vaddr),
                 format!("0x{:x}", map.paddr),
-                map.size
+                format!("0x{:x}", map.size),
+                map.perm.to_string(),
+                map.priority,
+                map.name
             )
             .unwrap();
         }
@@ -141,13 +187,110 @@ impl Cmd for ListMap {
         &["maps"]
     }
     fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
-        &[("", "List all memory maps.")]
+        &[("", "List all memory maps of the current bank.")]
+    }
+}
+
+#[derive(Default)]
+pub struct ListBanks;
+
+impl Cmd for ListBanks {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        let current = core.io.bank().to_owned();
+        let banks: Vec<String> = core.io.banks().into_iter().map(str::to_owned).collect();
+        if is_json(core) {
+            let banks = banks
+                .iter()
+                .map(|bank| json!({"name": bank, "current": *bank == current}))
+                .collect();
+            return print_json(core, &Value::Array(banks));
+        }
+        for bank in banks {
+            let marker = if bank == current { '*' } else { ' ' };
+            writeln!(core.stdout, "{marker} {bank}").unwrap();
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["banks"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List all banks, current bank is marked with `*`.")]
+    }
+}
+
+#[derive(Default)]
+pub struct SwitchBank;
+
+impl Cmd for SwitchBank {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.io.switch_bank(&args[0]) {
+            error_msg(core, "Failed to switch bank", &e.to_string());
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["bank"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[name]",
+            "Switch to bank [name], each bank is a separate virtual address space.",
+        )]
+    }
+}
+
+#[derive(Default)]
+pub struct AddBank;
+
+impl Cmd for AddBank {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.io.add_bank(&args[0]) {
+            error_msg(core, "Failed to add bank", &e.to_string());
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["bankAdd"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("[name]", "Create new bank with no maps.")]
+    }
+}
+
+#[derive(Default)]
+pub struct RemoveBank;
+
+impl Cmd for RemoveBank {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.io.remove_bank(&args[0]) {
+            error_msg(core, "Failed to remove bank", &e.to_string());
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["bankRemove"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("[name]", "Remove bank [name] along with all of its maps.")]
     }
 }
 #[cfg(test)]
 mod test_mapping {
     use super::*;
-    use crate::{writer::Writer, CmdOps};
+    use crate::{helper::AddrMode, writer::Writer, CmdOps};
     use rair_io::*;
     use std::path::Path;
     use test_file::*;
@@ -160,7 +303,7 @@ mod test_mapping {
         map.help(&mut core);
         assert_eq!(
             core.stdout.utf8_string().unwrap(),
-            "Command: [map]\nUsage:\nmap [phy] [vir] [size]\tMap region from physical address space to virtual address space.\n"
+            "Command: [map]\nUsage:\nmap [phy] [vir] [size] <perm> <name> <priority>\tMap region from physical address space to virtual address space with optional permissions (rwx by default), name and priority (0 by default). Maps with higher priority hide overlapping maps.\n"
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
     }
@@ -185,7 +328,7 @@ mod test_mapping {
         core.help("maps");
         assert_eq!(
             core.stdout.utf8_string().unwrap(),
-            "Command: [maps]\nUsage:\nmaps\tList all memory maps.\n"
+            "Command: [maps]\nUsage:\nmaps\tList all memory maps of the current bank.\n"
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
     }
@@ -219,10 +362,10 @@ mod test_mapping {
         core.run("maps", &[]);
         assert_eq!(
             core.stdout.utf8_string().unwrap(),
-            "Virtual Address     Physical Address    Size\n\
-             0x500               0x0                 0x20\n\
-             0x520               0x10                0x20\n\
-             0x540               0x20                0x20\n"
+            "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x500               0x0                 0x20                rwx     0         \n\
+             0x520               0x10                0x20                rwx     0         \n\
+             0x540               0x20                0x20                rwx     0         \n"
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
         core.stderr = Writer::new_buf();
@@ -237,10 +380,20 @@ mod test_mapping {
         core.run("maps", &[]);
         assert_eq!(
             core.stdout.utf8_string().unwrap(),
-            "Virtual Address     Physical Address    Size\n\
-             0x500               0x0                 0x10\n\
-             0x515               0x15                0xb\n\
-             0x540               0x20                0x20\n"
+            "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x500               0x0                 0x10                rwx     0         \n\
+             0x515               0x15                0xb                 rwx     0         \n\
+             0x540               0x20                0x20                rwx     0         \n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("mapsj", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "[{\"name\":\"\",\"paddr\":0,\"perm\":\"rwx\",\"priority\":0,\"size\":16,\"vaddr\":1280},\
+             {\"name\":\"\",\"paddr\":21,\"perm\":\"rwx\",\"priority\":0,\"size\":11,\"vaddr\":1301},\
+             {\"name\":\"\",\"paddr\":32,\"perm\":\"rwx\",\"priority\":0,\"size\":32,\"vaddr\":1344}]\n"
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
     }
@@ -259,7 +412,7 @@ mod test_mapping {
         assert_eq!(core.stdout.utf8_string().unwrap(), "");
         assert_eq!(
             core.stderr.utf8_string().unwrap(),
-            "Arguments Error: Expected 3 argument(s), found 0.\n"
+            "Arguments Error: Expected between 3 and 6 arguments, found 0.\n"
         );
         core.stderr = Writer::new_buf();
         core.stdout = Writer::new_buf();
@@ -346,4 +499,186 @@ mod test_mapping {
             "Error: Failed to unmap memory\nCannot resolve address.\n"
         );
     }
+    #[test]
+    fn test_map_perm() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run(
+            "map",
+            &[
+                "0x0".to_owned(),
+                "0x1000".to_owned(),
+                "0x80".to_owned(),
+                "r-x".to_owned(),
+                ".text".to_owned(),
+            ],
+        );
+        core.run(
+            "map",
+            &[
+                "0x80".to_owned(),
+                "0x2000".to_owned(),
+                "0x80".to_owned(),
+                "rw".to_owned(),
+            ],
+        );
+        core.run(
+            "map",
+            &[
+                "0x80".to_owned(),
+                "0x3000".to_owned(),
+                "0x80".to_owned(),
+                "rwz".to_owned(),
+            ],
+        );
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to map memory\nInvalid permissions rwz.\n"
+        );
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("maps", &[]);
+        core.run("mapsj", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x1000              0x0                 0x80                r-x     0         .text\n\
+             0x2000              0x80                0x80                rw-     0         \n\
+             [{\"name\":\".text\",\"paddr\":0,\"perm\":\"r-x\",\"priority\":0,\"size\":128,\"vaddr\":4096},\
+             {\"name\":\"\",\"paddr\":128,\"perm\":\"rw-\",\"priority\":0,\"size\":128,\"vaddr\":8192}]\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.mode = AddrMode::Vir;
+        core.set_loc(0x2000);
+        core.run("wx", &["1234".to_owned()]);
+        core.set_loc(0x1000);
+        core.run("wx", &["1234".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Read Failed\nVirtual address 0x1000 is not writable.\n"
+        );
+        let mut data = [0; 2];
+        core.io.pread(0x80, &mut data).unwrap();
+        assert_eq!(data, [0x12, 0x34]);
+        core.io.pread(0, &mut data).unwrap();
+        assert_eq!(data, [0, 0]);
+    }
+    #[test]
+    fn test_map_priority() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.pwrite(0x80, &[0xff; 0x80]).unwrap();
+        core.run(
+            "map",
+            &["0x0".to_owned(), "0x1000".to_owned(), "0x80".to_owned()],
+        );
+        let overlay = [
+            "0x80".to_owned(),
+            "0x1010".to_owned(),
+            "0x10".to_owned(),
+            "r".to_owned(),
+            "overlay".to_owned(),
+            "1".to_owned(),
+        ];
+        core.run("map", &overlay);
+        core.run("map", &overlay);
+        core.run("map", &overlay[..5]);
+        core.run(
+            "map",
+            &[
+                "0x80".to_owned(),
+                "0x1010".to_owned(),
+                "0x10".to_owned(),
+                "r".to_owned(),
+                "overlay".to_owned(),
+                "z".to_owned(),
+            ],
+        );
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to map memory\nPhyiscal addresses overlap.\n\
+             Error: Failed to map memory\nPhyiscal addresses overlap.\n\
+             Error: Failed to map memory\nFailed to parse priority, invalid digit found in string.\n"
+        );
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.mode = AddrMode::Vir;
+        core.set_loc(0x100e);
+        core.run("maps", &[]);
+        core.run("px", &["4".to_owned()]);
+        core.run("um", &["0x1010".to_owned(), "0x10".to_owned()]);
+        core.run("px", &["4".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x1000              0x0                 0x80                rwx     0         \n\
+             0x1010              0x80                0x10                r--     1         overlay\n\
+             - offset -  0 1  2 3  4 5  6 7  8 9  A B  C D  E F  0123456789ABCDEF\n\
+             0x0000100e 0000 ffff                                ....\n\
+             - offset -  0 1  2 3  4 5  6 7  8 9  A B  C D  E F  0123456789ABCDEF\n\
+             0x0000100e 0000 0000                                ....\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+    #[test]
+    fn test_banks() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run(
+            "map",
+            &["0x0".to_owned(), "0x1000".to_owned(), "0x80".to_owned()],
+        );
+        core.run("bankAdd", &["data".to_owned()]);
+        core.run("bank", &["data".to_owned()]);
+        core.run(
+            "map",
+            &["0x80".to_owned(), "0x1000".to_owned(), "0x80".to_owned()],
+        );
+        core.run("banks", &[]);
+        core.run("banksj", &[]);
+        core.run("maps", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "* data\n  \
+             default\n\
+             [{\"current\":true,\"name\":\"data\"},{\"current\":false,\"name\":\"default\"}]\n\
+             Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x1000              0x80                0x80                rwx     0         \n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("bankAdd", &["data".to_owned()]);
+        core.run("bank", &["code".to_owned()]);
+        core.run("bankRemove", &["data".to_owned()]);
+        core.run("bank", &["default".to_owned()]);
+        core.run("bankRemove", &["data".to_owned()]);
+        core.run("bankRemove", &["data".to_owned()]);
+        core.run("banks", &[]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "* default\n");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to add bank\nBank data already exists.\n\
+             Error: Failed to switch bank\nBank code does not exist.\n\
+             Error: Failed to remove bank\nCan not remove current bank data.\n\
+             Error: Failed to remove bank\nBank data does not exist.\n"
+        );
+    }
 }
@@ -1,29 +1,61 @@
 //! commands handling IO.
 
+mod cache;
 mod files;
 mod map;
+mod plugins;
 mod print;
 mod write;
 
-use self::files::{CloseFile, ListFiles, OpenFile};
-use self::map::{ListMap, Map, UnMap};
-use self::print::{PrintBase, PrintCSV, PrintHex, PrintSignedCSV};
-use self::write::{WriteHex, WriteToFile};
+use self::cache::{CommitCache, DiscardCache, ListCache};
+use self::files::{Archive, CloseFile, Commit, ListFiles, OpenFile, Ranges, ResizeFile};
+use self::map::{AddBank, ListBanks, ListMap, Map, RemoveBank, SwitchBank, UnMap};
+use self::plugins::{ListPlugins, LoadPlugin};
+use self::print::{PrintBase, PrintCSV, PrintDisasm, PrintHex, PrintSignedCSV};
+use self::write::{
+    Export, Insert, Remove, WriteHex, WriteJournal, WriteRedo, WriteToFile, WriteUndo,
+};
 use crate::core::Core;
 pub fn register_io(core: &mut Core) {
     let maps = ListMap::new(core);
     let files = ListFiles::new(core);
+    let plugins = ListPlugins::new(core);
     let px = PrintHex::new(core);
+    let pd = PrintDisasm::new(core);
+    let wj = WriteJournal::new(core);
+    let export = Export::new(core);
+    let cache = ListCache::new(core);
+    let insert = Insert::new(core);
     core.add_command(Map);
     core.add_command(maps);
     core.add_command(px);
+    core.add_command(pd);
     core.add_command(PrintBase);
     core.add_command(PrintCSV);
     core.add_command(PrintSignedCSV);
     core.add_command(UnMap);
+    core.add_command(ListBanks);
+    core.add_command(SwitchBank);
+    core.add_command(AddBank);
+    core.add_command(RemoveBank);
     core.add_command(files);
     core.add_command(OpenFile);
     core.add_command(CloseFile);
+    core.add_command(ResizeFile);
+    core.add_command(Commit);
+    core.add_command(Archive);
+    core.add_command(Ranges);
+    core.add_command(plugins);
+    core.add_command(LoadPlugin);
     core.add_command(WriteHex);
     core.add_command(WriteToFile);
+    core.add_command(insert);
+    core.add_command(Remove);
+    core.add_command(export);
+    core.add_command(WriteUndo);
+    core.add_command(WriteRedo);
+    core.add_command(wj);
+    core.add_command(cache);
+    core.add_command(DiscardCache);
+    core.add_command(CommitCache);
 }
@@ -0,0 +1,168 @@
+//! commands for listing and loading IO plugins.
+
+use crate::helper::{error_msg, expect, is_color, is_json, print_json};
+use crate::{cmd::Cmd, core::Core};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use yansi::Paint;
+
+#[derive(Default)]
+pub struct ListPlugins;
+
+impl ListPlugins {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_str_with_cb(
+                "plugins.headerColor",
+                "color.6",
+                "Color used in the header of `plugins` command",
+                core,
+                is_color,
+            )
+            .unwrap();
+        Self
+    }
+}
+
+impl Cmd for ListPlugins {
+    fn commands(&self) -> &'static [&'static str] {
+        &["plugins"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List all loaded IO plugins.")]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        if is_json(core) {
+            let plugins = core
+                .io
+                .plugins()
+                .map(|plugin| {
+                    json!({
+                        "name": plugin.name,
+                        "version": plugin.version,
+                        "author": plugin.author,
+                        "license": plugin.license,
+                        "desc": plugin.desc,
+                    })
+                })
+                .collect();
+            return print_json(core, &Value::Array(plugins));
+        }
+        let env = core.env.read();
+        let color = env.get_str("plugins.headerColor").unwrap();
+        let (r, g, b) = env.get_color(color).unwrap();
+        writeln!(
+            core.stdout,
+            "{: <12}{: <10}{: <10}{: <12}{}",
+            "Name".rgb(r, g, b),
+            "Version".rgb(r, g, b),
+            "License".rgb(r, g, b),
+            "Author".rgb(r, g, b),
+            "Description".rgb(r, g, b)
+        )
+        .unwrap();
+        for plugin in core.io.plugins() {
+            writeln!(
+                core.stdout,
+                "{: <12}{: <10}{: <10}{: <12}{}",
+                plugin.name, plugin.version, plugin.license, plugin.author, plugin.desc
+            )
+            .unwrap();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LoadPlugin;
+
+impl Cmd for LoadPlugin {
+    fn commands(&self) -> &'static [&'static str] {
+        &["pluginLoad"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("[path]", "Load IO plugin from shared library at [path].")]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.io.load_plugin_file(Path::new(&args[0])) {
+            let err_str = format!("{e}");
+            error_msg(core, "Failed to load plugin", &err_str);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_plugins {
+    use super::*;
+    use crate::{writer::Writer, CmdOps};
+
+    #[test]
+    fn test_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("plugins");
+        LoadPlugin.help(&mut core);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [plugins]\n\
+             Usage:\n\
+             plugins\tList all loaded IO plugins.\n\
+             Command: [pluginLoad]\n\
+             Usage:\n\
+             pluginLoad [path]\tLoad IO plugin from shared library at [path].\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_plugins() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("plugins", &[]);
+        let out = core.stdout.utf8_string().unwrap();
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "Name        Version   License   Author      Description"
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("FilePlugin  0.0.1     LGPL      Oddcoder    "));
+        assert_eq!(lines.count(), core.io.plugins().count() - 1);
+        core.stdout = Writer::new_buf();
+        core.run("pluginsj", &[]);
+        let out: Value = serde_json::from_str(&core.stdout.utf8_string().unwrap()).unwrap();
+        assert_eq!(out[0]["name"], "FilePlugin");
+        assert_eq!(out[0]["license"], "LGPL");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_plugins_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("plugins", &["x".to_owned()]);
+        core.run("pluginLoad", &[]);
+        core.run("pluginLoad", &["file_that_doesnt_exist.so".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        let err = core.stderr.utf8_string().unwrap();
+        assert!(err.starts_with(
+            "Arguments Error: Expected 0 argument(s), found 1.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n\
+             Error: Failed to load plugin\n"
+        ));
+    }
+}
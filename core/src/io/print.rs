@@ -1,11 +1,14 @@
 //! commands handling raw data printing.
 
 use crate::core::Core;
-use crate::helper::{error_msg, expect, str_to_num};
+use crate::helper::{error_msg, expect, is_json, print_json, str_to_num};
 use crate::hex::HexWithoutEnv;
 use crate::writer::Writer;
 use crate::Cmd;
 use core::{cmp, fmt::Write as _};
+use rair_arch::arch_by_name;
+use rair_env::Environment;
+use serde_json::{json, Value};
 use std::io::Write;
 use yansi::Paint;
 
@@ -50,6 +53,10 @@ impl Cmd for PrintHex {
             Ok(d) => d,
             Err(e) => return error_msg(core, "Read Failed", &e.to_string()),
         };
+        if is_json(core) {
+            let bytes: Vec<_> = (loc..loc + size).map(|i| data.get(&i)).collect();
+            return print_json(core, &json!({"address": loc, "bytes": bytes}));
+        }
         let env = self.inner.get_env(core);
         env.print_banner(&mut core.stdout);
         for i in (0..size).step_by(16) {
@@ -80,6 +87,119 @@ impl Cmd for PrintHex {
     }
 }
 
+pub struct PrintDisasm;
+
+fn is_arch(_: &str, value: &str, _: &Environment<Core>, _: &mut Core) -> bool {
+    arch_by_name(value).is_some()
+}
+
+fn is_bits(_: &str, value: u64, env: &Environment<Core>, _: &mut Core) -> bool {
+    env.get_str("asm.arch")
+        .ok()
+        .and_then(arch_by_name)
+        .is_some_and(|arch| arch.bits().contains(&value))
+}
+
+impl PrintDisasm {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_str_with_cb(
+                "asm.arch",
+                "x86",
+                "Architecture used for disassembly",
+                core,
+                is_arch,
+            )
+            .unwrap();
+        env.write()
+            .add_u64_with_cb(
+                "asm.bits",
+                64,
+                "Word size in bits used for disassembly",
+                core,
+                is_bits,
+            )
+            .unwrap();
+        Self
+    }
+}
+
+impl Cmd for PrintDisasm {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        let count = match str_to_num(&args[0]) {
+            Ok(count) => count,
+            Err(e) => return error_msg(core, "Failed to parse count", &e.to_string()),
+        };
+        let (name, bits) = {
+            let env = core.env.read();
+            let name = env.get_str("asm.arch").unwrap().to_owned();
+            (name, env.get_u64("asm.bits").unwrap())
+        };
+        let arch = arch_by_name(&name).unwrap();
+        let Some(mut disasm) = arch.disassembler(bits) else {
+            let msg = format!("Architecture {name} does not support {bits} bits.");
+            return error_msg(core, "Failed to disassemble", &msg);
+        };
+        let max = arch.max_insn_size() as u64;
+        let mut loc = core.get_loc();
+        let json = is_json(core);
+        let mut insns = Vec::new();
+        for _ in 0..count {
+            // instructions can't go past the end of the address space.
+            let len = (u64::MAX - loc).saturating_add(1).min(max);
+            let data = match core.read_sparce(loc, len) {
+                Ok(d) => d,
+                Err(e) => return error_msg(core, "Read Failed", &e.to_string()),
+            };
+            // only bytes up to the first gap can be part of the instruction.
+            let bytes: Vec<u8> = (0..len)
+                .map_while(|i| data.get(&(loc + i)).copied())
+                .collect();
+            let (size, text) = if bytes.is_empty() {
+                (1, "unmapped".to_owned())
+            } else if let Some(insn) = disasm.disassemble(loc, &bytes) {
+                (insn.size, insn.text)
+            } else {
+                (1, "invalid".to_owned())
+            };
+            if json {
+                let bytes = &bytes[..size.min(bytes.len())];
+                insns.push(json!({"address": loc, "bytes": bytes, "text": text}));
+            } else {
+                let hex = if bytes.is_empty() {
+                    "##".to_owned()
+                } else {
+                    encode_hex(&bytes[..size])
+                };
+                writeln!(core.stdout, "0x{loc:08x} {hex: <32}{text}").unwrap();
+            }
+            match loc.checked_add(size as u64) {
+                Some(next) => loc = next,
+                None => break,
+            }
+        }
+        if json {
+            print_json(core, &Value::Array(insns));
+        }
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &["printDisasm", "pd"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[count]",
+            "Disassemble [count] instructions at current location using asm.arch and asm.bits.",
+        )]
+    }
+}
+
 #[derive(Default)]
 pub struct PrintBase;
 
@@ -125,6 +245,9 @@ impl Cmd for PrintBase {
             "16" => encode_hex(&data),
             _ => return error_msg(core, "Failed to print data", "Invalid base"),
         };
+        if is_json(core) {
+            return print_json(core, &json!({"address": loc, "data": data_str}));
+        }
         writeln!(core.stdout, "{data_str}").unwrap();
     }
     fn commands(&self) -> &'static [&'static str] {
@@ -142,6 +265,21 @@ impl Cmd for PrintBase {
 #[derive(Default)]
 pub struct PrintCSV;
 
+/// Values of comma separated `csv` as JSON numbers, values too large for
+/// 64 bits are kept as strings.
+fn csv_to_json(csv: &str) -> Vec<Value> {
+    csv.split(',')
+        .map(str::trim)
+        .map(|v| {
+            let num = match v.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok().map(Value::from),
+                None => v.parse::<i64>().ok().map(Value::from),
+            };
+            num.unwrap_or_else(|| Value::from(v))
+        })
+        .collect()
+}
+
 fn csv8(data: &[u8]) -> String {
     let mut out = String::with_capacity(data.len() * 6);
     let mut terminal;
@@ -324,6 +462,10 @@ impl Cmd for PrintCSV {
             512 => csv512(&data),
             _ => return error_msg(core, "Failed to print data", "Invalid size"),
         };
+        if is_json(core) {
+            let values = csv_to_json(&data_str);
+            return print_json(core, &json!({"address": loc, "values": values}));
+        }
         writeln!(core.stdout, "{data_str}").unwrap();
     }
     fn commands(&self) -> &'static [&'static str] {
@@ -485,6 +627,10 @@ impl Cmd for PrintSignedCSV {
             128 => scsv128(&data),
             _ => return error_msg(core, "Failed to print data", "Invalid size"),
         };
+        if is_json(core) {
+            let values = csv_to_json(&data_str);
+            return print_json(core, &json!({"address": loc, "values": values}));
+        }
         writeln!(core.stdout, "{data_str}").unwrap();
     }
     fn commands(&self) -> &'static [&'static str] {
@@ -520,6 +666,7 @@ mod test_print_hex {
         let pcsv = PrintCSV;
         let pscsv = PrintSignedCSV;
         core.help("px");
+        core.help("pd");
         pb.help(&mut core);
         pcsv.help(&mut core);
         pscsv.help(&mut core);
@@ -528,6 +675,9 @@ mod test_print_hex {
             "Commands: [printHex | px]\n\
              Usage:\n\
              px [size]\tView data at current location in hex format.\n\
+             Commands: [printDisasm | pd]\n\
+             Usage:\n\
+             pd [count]\tDisassemble [count] instructions at current location using asm.arch and asm.bits.\n\
              Commands: [printBase | pb]\n\
              Usage:\n\
              pb [base] [size]\tPrint data stream at current location in [base] format.  Supported bases: 2, 16.\n\
@@ -963,6 +1113,27 @@ mod test_print_hex {
         operate_on_file(&test_px_vir_cb, DATA);
     }
 
+    #[test]
+    fn test_pxj() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.pwrite(0, &[0x41, 0x42]).unwrap();
+        core.mode = AddrMode::Vir;
+        core.io.map(0x0, 0x100, 0x2).unwrap();
+        core.io.map(0x0, 0x104, 0x1).unwrap();
+        core.set_loc(0x100);
+        core.run("pxj", &["6".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"address\":256,\"bytes\":[65,66,null,null,65,null]}\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
     #[test]
     fn test_px_err() {
         let mut core = Core::new_no_colors();
@@ -1628,4 +1799,118 @@ mod test_print_hex {
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
     }
+
+    #[test]
+    fn test_pd() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        // push rbp; mov rbp, rsp; <invalid in 64 bit>; call
+        core.io
+            .pwrite(
+                0x10,
+                &[0x55, 0x48, 0x89, 0xe5, 0x06, 0xe8, 0xf6, 0xff, 0xff, 0xff],
+            )
+            .unwrap();
+        core.io.pwrite(0xfe, &[0x48, 0x89]).unwrap();
+        core.io.map(0x10, 0x1000, 0x10).unwrap();
+        core.io.map(0xfe, 0x2000, 2).unwrap();
+        core.mode = AddrMode::Vir;
+        core.run_at("pd", &["4".to_owned()], 0x1000);
+        // instruction cut by a gap is invalid, bytes after it are unmapped.
+        core.run_at("pd", &["2".to_owned()], 0x1fff);
+        core.run("e", &["asm.bits=32".to_owned()]);
+        core.run_at("pd", &["1".to_owned()], 0x1004);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "0x00001000 55                              push rbp\n\
+             0x00001001 4889e5                          mov rbp, rsp\n\
+             0x00001004 06                              invalid\n\
+             0x00001005 e8f6ffffff                      call 0x1000\n\
+             0x00001fff ##                              unmapped\n\
+             0x00002000 48                              invalid\n\
+             0x00001004 06                              push es\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_pb_pcsv_pd_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        // push rbp; mov rbp, rsp; <invalid in 64 bit>
+        core.io
+            .pwrite(0x10, &[0x55, 0x48, 0x89, 0xe5, 0x06])
+            .unwrap();
+        core.io.map(0x10, 0x1000, 0x5).unwrap();
+        core.set_loc(0x10);
+        core.run("pbj", &["2".to_owned(), "2".to_owned()]);
+        core.run("pcsvj", &["16".to_owned(), "2".to_owned()]);
+        core.mode = AddrMode::Vir;
+        core.run_at("pdj", &["4".to_owned()], 0x1001);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"address\":16,\"data\":\"0101010101001000\"}\n\
+             {\"address\":16,\"values\":[18517,58761]}\n\
+             [{\"address\":4097,\"bytes\":[72,137,229],\"text\":\"mov rbp, rsp\"},\
+             {\"address\":4100,\"bytes\":[6],\"text\":\"invalid\"},\
+             {\"address\":4101,\"bytes\":[],\"text\":\"unmapped\"},\
+             {\"address\":4102,\"bytes\":[],\"text\":\"unmapped\"}]\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_pd_end_of_address_space() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        // push rbp; mov rbp, rsp cut right before the end of the address space.
+        core.io.pwrite(0, &[0x55, 0x48, 0x89]).unwrap();
+        core.io.map(0, u64::MAX - 3, 3).unwrap();
+        core.mode = AddrMode::Vir;
+        core.run_at("pd", &["5".to_owned()], u64::MAX - 3);
+        core.run_at("pdj", &["5".to_owned()], u64::MAX);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "0xfffffffffffffffc 55                              push rbp\n\
+             0xfffffffffffffffd 48                              invalid\n\
+             0xfffffffffffffffe 89                              invalid\n\
+             0xffffffffffffffff ##                              unmapped\n\
+             [{\"address\":18446744073709551615,\"bytes\":[],\"text\":\"unmapped\"}]\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_pd_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("e", &["asm.arch=foo".to_owned()]);
+        core.run("e", &["asm.bits=8".to_owned()]);
+        core.run("pd", &[]);
+        core.run("pd", &["x".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to set variable.\n\
+             Call back failed.\n\
+             Error: Failed to set variable.\n\
+             Call back failed.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n\
+             Error: Failed to parse count\n\
+             invalid digit found in string\n"
+        );
+    }
 }
@@ -1,10 +1,40 @@
 //! commands handling data writing to files.
 
 use crate::core::Core;
-use crate::helper::{error_msg, expect, str_to_num};
+use crate::helper::{error_msg, expect, is_color, is_json, print_json, str_to_num, AddrMode};
 use crate::Cmd;
-use std::fs::File;
+use base64::prelude::*;
+use rair_env::Environment;
+use rair_io::{encode_ihex, encode_srec, IHexAddrMode, IoError, Patch, Shift, SrecAddrMode};
+use serde_json::{json, Value};
+use std::fs::{self, File};
 use std::io::prelude::*;
+use yansi::Paint;
+
+fn parse_hexpairs(core: &mut Core, hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        error_msg(
+            core,
+            "Failed to parse data",
+            "Data can't have odd number of digits.",
+        );
+        return None;
+    }
+    let mut hexpairs = hex.chars().peekable();
+    let mut data = Vec::with_capacity(hex.len() / 2);
+    while hexpairs.peek().is_some() {
+        let chunk: String = hexpairs.by_ref().take(2).collect();
+        match u8::from_str_radix(&chunk, 16) {
+            Ok(byte) => data.push(byte),
+            Err(e) => {
+                let msg = format!("{e}.");
+                error_msg(core, "Failed to parse data", &msg);
+                return None;
+            }
+        }
+    }
+    Some(data)
+}
 
 #[derive(Default)]
 pub struct WriteHex;
@@ -15,27 +45,9 @@ impl Cmd for WriteHex {
             expect(core, args.len() as u64, 1);
             return;
         }
-        if args[0].len() % 2 != 0 {
-            error_msg(
-                core,
-                "Failed to parse data",
-                "Data can't have odd number of digits.",
-            );
+        let Some(data) = parse_hexpairs(core, &args[0]) else {
             return;
-        }
-        let mut hexpairs = args[0].chars().peekable();
-        let mut data = Vec::with_capacity(args.len() / 2);
-        while hexpairs.peek().is_some() {
-            let chunk: String = hexpairs.by_ref().take(2).collect();
-            let byte = match u8::from_str_radix(&chunk, 16) {
-                Ok(byte) => byte,
-                Err(e) => {
-                    let msg = format!("{e}.");
-                    return error_msg(core, "Failed to parse data", &msg);
-                }
-            };
-            data.push(byte);
-        }
+        };
         let loc = core.get_loc();
         if let Err(e) = core.write(loc, &data) {
             error_msg(core, "Read Failed", &e.to_string());
@@ -53,6 +65,148 @@ impl Cmd for WriteHex {
     }
 }
 
+// Insert or remove bytes at current location, moving maps and flags along with the bytes they
+// point to if `io.shift` is set.
+fn edit_at_loc<F>(core: &mut Core, title: &str, edit: F)
+where
+    F: FnOnce(&mut Core, u64, bool) -> Result<Shift, IoError>,
+{
+    let loc = core.get_loc();
+    let paddr = match core.mode {
+        AddrMode::Phy => Some(loc),
+        AddrMode::Vir => core
+            .io
+            .vir_to_phy(loc, 1)
+            .and_then(|maps| maps.first().map(|map| map.paddr)),
+    };
+    let Some(paddr) = paddr else {
+        return error_msg(core, title, &IoError::AddressNotFound.to_string());
+    };
+    let shift = core.env.read().get_bool("io.shift").unwrap_or(false);
+    // physical address of every flag before the edit.
+    let flags: Vec<(String, u64, Option<u64>)> = core
+        .flags
+        .iter()
+        .map(|(name, flag)| {
+            let paddr = match core.mode {
+                AddrMode::Phy => Some(flag.addr),
+                AddrMode::Vir => core
+                    .io
+                    .vir_to_phy(flag.addr, 1)
+                    .and_then(|maps| maps.first().map(|map| map.paddr)),
+            };
+            (name.to_owned(), flag.addr, paddr)
+        })
+        .collect();
+    let moved = match edit(core, paddr, shift) {
+        Ok(moved) => moved,
+        Err(e) => return error_msg(core, title, &e.to_string()),
+    };
+    if !shift {
+        return;
+    }
+    for (name, addr, paddr) in flags {
+        let Some(paddr) = paddr else {
+            continue;
+        };
+        let new_paddr = moved.apply(paddr);
+        let new_addr = match core.mode {
+            AddrMode::Phy => new_paddr,
+            AddrMode::Vir => {
+                let vaddrs = core.io.phy_to_vir(new_paddr);
+                if vaddrs.contains(&addr) {
+                    continue;
+                }
+                match vaddrs.into_iter().min_by_key(|vaddr| vaddr.abs_diff(addr)) {
+                    Some(vaddr) => vaddr,
+                    None => continue,
+                }
+            }
+        };
+        if new_addr != addr {
+            let flag = core.flags.get(&name).unwrap().clone();
+            core.flags
+                .add(&name, new_addr, flag.size, &flag.space)
+                .unwrap();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Insert;
+
+impl Insert {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_bool(
+                "io.shift",
+                false,
+                "Move maps and flags along with the bytes they point to when inserting or removing bytes",
+            )
+            .unwrap();
+        Self
+    }
+}
+
+impl Cmd for Insert {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        let Some(data) = parse_hexpairs(core, &args[0]) else {
+            return;
+        };
+        edit_at_loc(core, "Failed to insert data", |core, paddr, shift| {
+            core.io.insert(paddr, &data, shift)
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["insert"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[hexpairs]",
+            "Insert given hexpairs data at the current address moving the rest of the file forward, see `io.shift`.",
+        )]
+    }
+}
+
+#[derive(Default)]
+pub struct Remove;
+
+impl Cmd for Remove {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        let size = match str_to_num(&args[0]) {
+            Ok(size) => size,
+            Err(e) => {
+                let err_str = format!("{e}.");
+                error_msg(core, "Failed to parse size", &err_str);
+                return;
+            }
+        };
+        edit_at_loc(core, "Failed to remove data", |core, paddr, shift| {
+            core.io.remove(paddr, size, shift)
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["remove"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[size]",
+            "Remove [size] bytes at the current address moving the rest of the file backward, see `io.shift`.",
+        )]
+    }
+}
+
 #[derive(Default)]
 pub struct WriteToFile;
 
@@ -102,6 +256,312 @@ impl Cmd for WriteToFile {
     }
 }
 
+#[derive(Default)]
+pub struct WriteUndo;
+
+impl Cmd for WriteUndo {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        match core.io.undo() {
+            Ok(true) => (),
+            Ok(false) => error_msg(core, "Failed to undo", "Nothing to undo."),
+            Err(e) => error_msg(core, "Failed to undo", &e.to_string()),
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["writeUndo", "wu"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "Revert the most recent write.")]
+    }
+}
+
+#[derive(Default)]
+pub struct WriteRedo;
+
+impl Cmd for WriteRedo {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        match core.io.redo() {
+            Ok(true) => (),
+            Ok(false) => error_msg(core, "Failed to redo", "Nothing to redo."),
+            Err(e) => error_msg(core, "Failed to redo", &e.to_string()),
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["writeRedo", "wr"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "Reapply the most recently reverted write.")]
+    }
+}
+
+#[derive(Default)]
+pub struct WriteJournal;
+
+impl WriteJournal {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_str_with_cb(
+                "journal.headerColor",
+                "color.6",
+                "Color used in the header of `writeJournal` command",
+                core,
+                is_color,
+            )
+            .unwrap();
+        Self
+    }
+}
+
+impl Cmd for WriteJournal {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        if is_json(core) {
+            let journal = core.io.journal();
+            let applied = journal.applied().map(|entry| (entry, "applied"));
+            let undone = journal.undone().map(|entry| (entry, "undone"));
+            let mut patches = Vec::new();
+            for (i, (entry, state)) in applied.chain(undone).enumerate() {
+                for Patch { paddr, old, new } in entry {
+                    patches.push(json!({
+                        "index": i,
+                        "paddr": paddr,
+                        "state": state,
+                        "old": old,
+                        "new": new,
+                    }));
+                }
+            }
+            return print_json(core, &Value::Array(patches));
+        }
+        let env = core.env.read();
+        let color = env.get_str("journal.headerColor").unwrap();
+        let (r, g, b) = env.get_color(color).unwrap();
+        writeln!(
+            core.stdout,
+            "{: <10}{: <20}{: <10}{: <10}{}",
+            "Index".rgb(r, g, b),
+            "Address".rgb(r, g, b),
+            "Size".rgb(r, g, b),
+            "State".rgb(r, g, b),
+            "Data".rgb(r, g, b)
+        )
+        .unwrap();
+        let journal = core.io.journal();
+        let applied = journal.applied().map(|entry| (entry, "applied"));
+        let undone = journal.undone().map(|entry| (entry, "undone"));
+        for (i, (entry, state)) in applied.chain(undone).enumerate() {
+            for Patch { paddr, old, new } in entry {
+                write!(
+                    core.stdout,
+                    "{: <10}{: <20}{: <10}{: <10}",
+                    i,
+                    format!("0x{paddr:x}"),
+                    format!("0x{:x}", new.len()),
+                    state
+                )
+                .unwrap();
+                for byte in old {
+                    write!(core.stdout, "{byte:02x}").unwrap();
+                }
+                write!(core.stdout, " -> ").unwrap();
+                for byte in new {
+                    write!(core.stdout, "{byte:02x}").unwrap();
+                }
+                writeln!(core.stdout).unwrap();
+            }
+        }
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["writeJournal", "wj"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "",
+            "List recorded writes, physical addresses are shown along with old and new data.",
+        )]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    IHex(IHexAddrMode),
+    Srec(SrecAddrMode),
+    Base64,
+    CArray,
+    RustArray,
+}
+
+impl ExportFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        let format = match name {
+            "ihex" => Self::IHex(IHexAddrMode::Auto),
+            "ihex8" => Self::IHex(IHexAddrMode::I8),
+            "ihex16" => Self::IHex(IHexAddrMode::I16),
+            "ihex32" => Self::IHex(IHexAddrMode::I32),
+            "srec" => Self::Srec(SrecAddrMode::Auto),
+            "s19" => Self::Srec(SrecAddrMode::S19),
+            "s28" => Self::Srec(SrecAddrMode::S28),
+            "s37" => Self::Srec(SrecAddrMode::S37),
+            "b64" => Self::Base64,
+            "c" => Self::CArray,
+            "rust" => Self::RustArray,
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
+fn is_record_len(_: &str, value: u64, _: &Environment<Core>, _: &mut Core) -> bool {
+    (1..=0xff).contains(&value)
+}
+
+fn is_identifier(_: &str, value: &str, _: &Environment<Core>, _: &mut Core) -> bool {
+    value.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Source file that defines array `name` holding `data`, rust arrays are upper case.
+fn array_source(data: &[u8], name: &str, rust: bool) -> Vec<u8> {
+    let mut src = Vec::new();
+    if rust {
+        writeln!(
+            src,
+            "pub static {}: [u8; {}] = [",
+            name.to_uppercase(),
+            data.len()
+        )
+        .unwrap();
+    } else {
+        writeln!(src, "unsigned char {name}[] = {{").unwrap();
+    }
+    for line in data.chunks(12) {
+        let bytes: Vec<_> = line.iter().map(|byte| format!("0x{byte:02x}")).collect();
+        writeln!(src, "    {},", bytes.join(", ")).unwrap();
+    }
+    if rust {
+        writeln!(src, "];").unwrap();
+    } else {
+        writeln!(src, "}};").unwrap();
+        writeln!(src, "unsigned int {name}_len = {};", data.len()).unwrap();
+    }
+    src
+}
+
+#[derive(Default)]
+pub struct Export;
+
+impl Export {
+    pub fn new(core: &mut Core) -> Self {
+        let env = core.env.clone();
+        env.write()
+            .add_u64_with_cb(
+                "export.recordLen",
+                0x10,
+                "Number of data bytes per record of files written by `export` command",
+                core,
+                is_record_len,
+            )
+            .unwrap();
+        env.write()
+            .add_str_with_cb(
+                "export.arrayName",
+                "data",
+                "Name of arrays in C and rust files written by `export` command",
+                core,
+                is_identifier,
+            )
+            .unwrap();
+        Self
+    }
+    fn read(core: &mut Core, loc: u64, size: u64) -> Result<Vec<u8>, String> {
+        let mut data = vec![0; size as usize];
+        core.read(loc, &mut data).map_err(|e| e.to_string())?;
+        Ok(data)
+    }
+    fn encode(core: &mut Core, format: ExportFormat, size: u64) -> Result<Vec<u8>, String> {
+        let loc = core.get_loc();
+        let (record_len, name) = {
+            let env = core.env.read();
+            let record_len = env.get_u64("export.recordLen").unwrap() as u8;
+            (
+                record_len,
+                env.get_str("export.arrayName").unwrap().to_owned(),
+            )
+        };
+        let encoded = match format {
+            ExportFormat::IHex(mode) => {
+                let data = core.read_sparce(loc, size).map_err(|e| e.to_string())?;
+                encode_ihex(&data, record_len, mode)
+            }
+            ExportFormat::Srec(mode) => {
+                let data = core.read_sparce(loc, size).map_err(|e| e.to_string())?;
+                encode_srec(&data, record_len, mode)
+            }
+            ExportFormat::Base64 => Ok(BASE64_STANDARD
+                .encode(Self::read(core, loc, size)?)
+                .into_bytes()),
+            ExportFormat::CArray => Ok(array_source(&Self::read(core, loc, size)?, &name, false)),
+            ExportFormat::RustArray => Ok(array_source(&Self::read(core, loc, size)?, &name, true)),
+        };
+        encoded.map_err(|e| e.to_string())
+    }
+}
+
+impl Cmd for Export {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 3 {
+            expect(core, args.len() as u64, 3);
+            return;
+        }
+        let Some(format) = ExportFormat::from_name(&args[0]) else {
+            let err_str = format!("Unknown format {}.", args[0]);
+            return error_msg(core, "Failed to export data", &err_str);
+        };
+        let size = match str_to_num(&args[1]) {
+            Ok(size) => size,
+            Err(e) => {
+                let err_str = format!("{e}.");
+                return error_msg(core, "Failed to parse size", &err_str);
+            }
+        };
+        let data = match Self::encode(core, format, size) {
+            Ok(data) => data,
+            Err(e) => return error_msg(core, "Failed to export data", &e),
+        };
+        if let Err(e) = fs::write(&args[2], data) {
+            let err_str = format!("{e}.");
+            error_msg(core, "Failed to write data to file", &err_str);
+        }
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &["export"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[format] [size] [filepath]",
+            "export data of size [size] at current location to file identified by [filepath], \
+            [format] is one of ihex, ihex8, ihex16, ihex32, srec, s19, s28, s37, b64, c or rust.",
+        )]
+    }
+}
+
 #[cfg(test)]
 
 mod test_write {
@@ -130,6 +590,77 @@ mod test_write {
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
     }
 
+    #[test]
+    fn test_insert_remove() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("insert");
+        core.help("remove");
+        core.io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        let data: Vec<u8> = (0..0x10).collect();
+        core.io.pwrite(0, &data).unwrap();
+        core.io.map(0x8, 0x1000, 0x8).unwrap();
+        core.flags.add("a", 0x2, 1, "*").unwrap();
+        core.flags.add("b", 0x9, 1, "*").unwrap();
+        core.set_loc(0x4);
+        core.run("insert", &["aabb".to_owned()]);
+        assert_eq!(core.flags.get("b").unwrap().addr, 0x9);
+        core.run("e", &["io.shift=true".to_owned()]);
+        core.run("insert", &["cc".to_owned()]);
+        assert_eq!(core.flags.get("a").unwrap().addr, 0x2);
+        assert_eq!(core.flags.get("b").unwrap().addr, 0xa);
+        let mut data = [0; 0x13];
+        core.io.pread(0, &mut data).unwrap();
+        assert_eq!(
+            data,
+            [0, 1, 2, 3, 0xcc, 0xaa, 0xbb, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+        );
+        let mut data = [0; 1];
+        core.io.vread(0x1000, &mut data).unwrap();
+        assert_eq!(data, [6]);
+        core.run("remove", &["2".to_owned()]);
+        assert_eq!(core.flags.get("b").unwrap().addr, 0x8);
+        core.io.vread(0x1000, &mut data).unwrap();
+        assert_eq!(data, [6]);
+        core.io.pread(0x4, &mut data).unwrap();
+        assert_eq!(data, [0xbb]);
+        // flags in virtual address space follow bytes in maps that didn't move
+        core.mode = AddrMode::Vir;
+        core.io.map(0x0, 0x2000, 0x8).unwrap();
+        core.flags.add("c", 0x2003, 1, "*").unwrap();
+        core.flags.add("d", 0x1001, 1, "*").unwrap();
+        core.set_loc(0x2002);
+        core.run("insert", &["ff".to_owned()]);
+        assert_eq!(core.flags.get("c").unwrap().addr, 0x2004);
+        assert_eq!(core.flags.get("d").unwrap().addr, 0x1001);
+        core.io.vread(0x1001, &mut data).unwrap();
+        assert_eq!(data, [7]);
+        core.set_loc(0x3000);
+        core.run("insert", &["ff".to_owned()]);
+        core.run("remove", &["x".to_owned()]);
+        core.run("insert", &["f".to_owned()]);
+        core.run("remove", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [insert]\n\
+             Usage:\n\
+             insert [hexpairs]\tInsert given hexpairs data at the current address moving the rest of the file forward, see `io.shift`.\n\
+             Command: [remove]\n\
+             Usage:\n\
+             remove [size]\tRemove [size] bytes at the current address moving the rest of the file backward, see `io.shift`.\n"
+        );
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to insert data\nCannot resolve address.\n\
+             Error: Failed to parse size\ninvalid digit found in string.\n\
+             Error: Failed to parse data\nData can't have odd number of digits.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n"
+        );
+    }
+
     #[test]
     fn test_wx() {
         let mut core = Core::new_no_colors();
@@ -266,4 +797,268 @@ mod test_write {
             "Error: Failed to read data\nCannot resolve address.\n"
         );
     }
+
+    #[test]
+    fn test_journal_help() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        WriteUndo.help(&mut core);
+        WriteRedo.help(&mut core);
+        WriteJournal.help(&mut core);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Commands: [writeUndo | wu]\n\
+             Usage:\n\
+             wu\tRevert the most recent write.\n\
+             Commands: [writeRedo | wr]\n\
+             Usage:\n\
+             wr\tReapply the most recently reverted write.\n\
+             Commands: [writeJournal | wj]\n\
+             Usage:\n\
+             wj\tList recorded writes, physical addresses are shown along with old and new data.\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_wu_wr() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x50", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run("wx", &["1234".to_owned()]);
+        core.set_loc(0x10);
+        core.run("wx", &["abcd".to_owned()]);
+        core.run("wu", &[]);
+        let mut data = [0; 2];
+        core.io.pread(0x10, &mut data).unwrap();
+        assert_eq!(data, [0, 0]);
+        core.run("wu", &[]);
+        core.io.pread(0x0, &mut data).unwrap();
+        assert_eq!(data, [0, 0]);
+        core.run("wr", &[]);
+        core.io.pread(0x0, &mut data).unwrap();
+        assert_eq!(data, [0x12, 0x34]);
+        core.run("wr", &[]);
+        core.io.pread(0x10, &mut data).unwrap();
+        assert_eq!(data, [0xab, 0xcd]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_wu_wr_error() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("wu", &["1".to_owned()]);
+        core.run("wr", &["1".to_owned()]);
+        core.run("wj", &["1".to_owned()]);
+        core.run("wu", &[]);
+        core.run("wr", &[]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Arguments Error: Expected 0 argument(s), found 1.\n\
+             Arguments Error: Expected 0 argument(s), found 1.\n\
+             Arguments Error: Expected 0 argument(s), found 1.\n\
+             Error: Failed to undo\n\
+             Nothing to undo.\n\
+             Error: Failed to redo\n\
+             Nothing to redo.\n"
+        );
+    }
+
+    #[test]
+    fn test_wj() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x50", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io
+            .open_at("malloc://0x50", IoMode::READ | IoMode::WRITE, 0x100)
+            .unwrap();
+        core.io.map(0x0, 0x1000, 0x50).unwrap();
+        core.io.map(0x100, 0x1050, 0x50).unwrap();
+        core.run("wx", &["1234".to_owned()]);
+        core.set_loc(0x104f);
+        core.mode = AddrMode::Vir;
+        core.run("wx", &["abcdef".to_owned()]);
+        core.set_loc(0x1000);
+        core.run("wx", &["56".to_owned()]);
+        core.run("wu", &[]);
+        core.run("wj", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Index     Address             Size      State     Data\n\
+             0         0x0                 0x2       applied   0000 -> 1234\n\
+             1         0x4f                0x1       applied   00 -> ab\n\
+             1         0x100               0x2       applied   0000 -> cdef\n\
+             2         0x0                 0x1       undone    12 -> 56\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_wj_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x50", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run("wx", &["1234".to_owned()]);
+        core.run("wx", &["56".to_owned()]);
+        core.run("wu", &[]);
+        core.run("wjj", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "[{\"index\":0,\"new\":[18,52],\"old\":[0,0],\"paddr\":0,\"state\":\"applied\"},\
+             {\"index\":1,\"new\":[86],\"old\":[18],\"paddr\":0,\"state\":\"undone\"}]\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_export() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("export");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [export]\n\
+             Usage:\n\
+             export [format] [size] [filepath]\texport data of size [size] at current location to file identified by [filepath], \
+             [format] is one of ihex, ihex8, ihex16, ihex32, srec, s19, s28, s37, b64, c or rust.\n"
+        );
+        core.stdout = Writer::new_buf();
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        let export = |core: &mut Core, format: &str, size: &str, name: &str| {
+            core.run("export", &[format.to_owned(), size.to_owned(), path(name)]);
+            fs::read_to_string(path(name)).unwrap()
+        };
+        core.io
+            .open("malloc://0x20", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.pwrite(0, &[1, 2, 3]).unwrap();
+        core.io.map(0x0, 0x1_0000, 0x20).unwrap();
+        assert_eq!(
+            export(&mut core, "ihex", "2", "phy.hex"),
+            ":020000000102fb\n:00000001FF\n"
+        );
+        assert_eq!(
+            export(&mut core, "s19", "2", "phy.s19"),
+            "S0030000fc\nS10500000102f7\nS9030000fc\n"
+        );
+        core.set_loc(0x1_0000);
+        core.mode = AddrMode::Vir;
+        assert_eq!(
+            export(&mut core, "ihex32", "1", "vir.hex"),
+            ":020000040001f9\n:0100000001fe\n:00000001FF\n"
+        );
+        assert_eq!(
+            export(&mut core, "srec", "1", "vir.srec"),
+            "S0030000fc\nS20501000001f8\nS804000000fb\n"
+        );
+        assert_eq!(export(&mut core, "b64", "3", "vir.b64"), "AQID");
+        core.run(
+            "e",
+            &[
+                "export.arrayName".to_owned(),
+                "=".to_owned(),
+                "fw".to_owned(),
+            ],
+        );
+        assert_eq!(
+            export(&mut core, "c", "13", "vir.c"),
+            "unsigned char fw[] = {\n    \
+             0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,\n    \
+             0x00,\n\
+             };\n\
+             unsigned int fw_len = 13;\n"
+        );
+        assert_eq!(
+            export(&mut core, "rust", "3", "vir.rs"),
+            "pub static FW: [u8; 3] = [\n    0x01, 0x02, 0x03,\n];\n"
+        );
+        core.run(
+            "e",
+            &[
+                "export.recordLen".to_owned(),
+                "=".to_owned(),
+                "2".to_owned(),
+            ],
+        );
+        assert_eq!(
+            export(&mut core, "ihex", "3", "vir2.hex"),
+            ":020000021000ec\n:020000000102fb\n:0100020003fa\n:00000001FF\n"
+        );
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_export_error() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x20000", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        let export = |core: &mut Core, format: &str, size: &str| {
+            core.run(
+                "export",
+                &[format.to_owned(), size.to_owned(), "/".to_owned()],
+            );
+        };
+        core.run("export", &[]);
+        export(&mut core, "hex", "1");
+        export(&mut core, "ihex", "x");
+        export(&mut core, "s19", "0x10001");
+        export(&mut core, "b64", "0x30000");
+        export(&mut core, "b64", "1");
+        core.run(
+            "e",
+            &[
+                "export.recordLen".to_owned(),
+                "=".to_owned(),
+                "0".to_owned(),
+            ],
+        );
+        core.run(
+            "e",
+            &[
+                "export.arrayName".to_owned(),
+                "=".to_owned(),
+                "1x".to_owned(),
+            ],
+        );
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        let err = core.stderr.utf8_string().unwrap();
+        assert!(err.starts_with(
+            "Arguments Error: Expected 3 argument(s), found 0.\n\
+             Error: Failed to export data\n\
+             Unknown format hex.\n\
+             Error: Failed to parse size\n\
+             invalid digit found in string.\n\
+             Error: Failed to export data\n\
+             Address 0x10000 doesn't fit in S19 S-record.\n\
+             Error: Failed to export data\n\
+             Cannot resolve address.\n\
+             Error: Failed to write data to file\n"
+        ));
+        assert!(err.ends_with(
+            "Error: Failed to set variable.\n\
+             Call back failed.\n\
+             Error: Failed to set variable.\n\
+             Call back failed.\n"
+        ));
+    }
 }
@@ -5,16 +5,24 @@ mod cmd;
 mod commands;
 mod core;
 mod diff;
+mod flags;
 mod helper;
 mod hex;
 mod io;
+mod loader;
 mod loc;
+mod plugin;
+mod search;
 mod utils;
 mod writer;
 pub use self::cmd::*;
 pub use self::commands::*;
 pub use self::core::*;
 pub use self::diff::*;
+pub use self::flags::*;
 pub use self::helper::*;
 pub use self::io::*;
+pub use self::loader::*;
+pub use self::plugin::*;
+pub use self::utils::{rair_source, Source};
 pub use self::writer::*;
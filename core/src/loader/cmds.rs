@@ -0,0 +1,232 @@
+//! commands for inspecting the binary backing current location.
+
+use super::{current_hndl, elf, elfcore, macho, parse_binary, pe, read_desc, Binary};
+use crate::core::Core;
+use crate::helper::{error_msg, expect};
+use crate::Cmd;
+use goblin::elf::header::ET_CORE;
+use goblin::elf::Elf;
+
+/// Parse the binary backing current location and print it with `print`.
+fn with_binary(core: &mut Core, args: &[String], print: impl FnOnce(&mut Core, &Binary, &[u8])) {
+    if !args.is_empty() {
+        expect(core, args.len() as u64, 0);
+        return;
+    }
+    let Some(hndl) = current_hndl(core) else {
+        return error_msg(
+            core,
+            "Failed to parse binary",
+            "No file is opened at current location.",
+        );
+    };
+    let data = match read_desc(core, hndl) {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return error_msg(core, "Failed to parse binary", "Unknown binary format.");
+        }
+        Err(e) => return error_msg(core, "Failed to parse binary", &e),
+    };
+    match parse_binary(&data) {
+        Ok(Some(bin)) => print(core, &bin, &data),
+        Ok(None) => error_msg(core, "Failed to parse binary", "Unknown binary format."),
+        Err(e) => error_msg(core, "Failed to parse binary", &e),
+    }
+}
+
+fn unsupported(core: &mut Core, format: &str) {
+    error_msg(
+        core,
+        "Unsupported binary format",
+        &format!("Command is not available for {format} binaries."),
+    );
+}
+
+/// Parse the core dump backing current location and print it with `print`.
+fn with_core_dump(core: &mut Core, args: &[String], print: fn(&mut Core, &Elf, &[u8])) {
+    with_binary(core, args, |core, bin, data| match bin {
+        Binary::Elf(elf) if elf.header.e_type == ET_CORE => print(core, elf, data),
+        Binary::Elf(_) => error_msg(
+            core,
+            "Unsupported binary format",
+            "Command is only available for ELF core dumps.",
+        ),
+        Binary::Pe(_) => unsupported(core, "PE"),
+        Binary::MachO(_) => unsupported(core, "Mach-O"),
+        Binary::Fat(_) => unsupported(core, "FAT"),
+    });
+}
+
+#[derive(Default)]
+pub struct Info;
+
+impl Cmd for Info {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(bin) => elf::print_info(core, bin),
+            Binary::Pe(bin) => pe::print_info(core, bin),
+            Binary::MachO(bin) => macho::print_info(core, bin),
+            Binary::Fat(bin) => macho::print_fat_info(core, bin),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["info"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "Print headers of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Sections;
+
+impl Cmd for Sections {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(bin) => elf::print_sections(core, bin),
+            Binary::Pe(bin) => pe::print_sections(core, bin),
+            Binary::MachO(bin) => macho::print_sections(core, bin),
+            Binary::Fat(_) => unsupported(core, "FAT"),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["sections"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List sections of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Symbols;
+
+impl Cmd for Symbols {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(bin) => elf::print_symbols(core, bin),
+            Binary::Pe(bin) => pe::print_symbols(core, bin),
+            Binary::MachO(bin) => macho::print_symbols(core, bin),
+            Binary::Fat(_) => unsupported(core, "FAT"),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["symbols"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List symbols of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Relocs;
+
+impl Cmd for Relocs {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(bin) => elf::print_relocs(core, bin),
+            Binary::Pe(_) => unsupported(core, "PE"),
+            Binary::MachO(_) => unsupported(core, "Mach-O"),
+            Binary::Fat(_) => unsupported(core, "FAT"),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["relocs"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List relocations of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Imports;
+
+impl Cmd for Imports {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(_) => unsupported(core, "ELF"),
+            Binary::Pe(bin) => pe::print_imports(core, bin),
+            Binary::MachO(bin) => macho::print_imports(core, bin),
+            Binary::Fat(_) => unsupported(core, "FAT"),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["imports"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List imports of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Exports;
+
+impl Cmd for Exports {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_binary(core, args, |core, bin, _| match bin {
+            Binary::Elf(_) => unsupported(core, "ELF"),
+            Binary::Pe(bin) => pe::print_exports(core, bin),
+            Binary::MachO(bin) => macho::print_exports(core, bin),
+            Binary::Fat(_) => unsupported(core, "FAT"),
+        });
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["exports"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[("", "List exports of the binary at current location.")]
+    }
+}
+
+#[derive(Default)]
+pub struct Threads;
+
+impl Cmd for Threads {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_core_dump(core, args, elfcore::print_threads);
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["threads"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "",
+            "List threads of the core dump at current location with their registers.",
+        )]
+    }
+}
+
+#[derive(Default)]
+pub struct Mappings;
+
+impl Cmd for Mappings {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_core_dump(core, args, elfcore::print_mappings);
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["mappings"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "",
+            "List file backed mappings of the core dump at current location.",
+        )]
+    }
+}
+
+#[derive(Default)]
+pub struct Auxv;
+
+impl Cmd for Auxv {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        with_core_dump(core, args, elfcore::print_auxv);
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["auxv"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "",
+            "List auxiliary vector of the core dump at current location.",
+        )]
+    }
+}
@@ -0,0 +1,327 @@
+//! ELF32 and ELF64 loading and information printing.
+
+use super::{add_flag, elfcore, map_segment, print_header, seek_entry, SegmentMap};
+use crate::core::Core;
+use crate::helper::{is_json, print_json};
+use goblin::elf::header::{et_to_str, machine_to_str, ELFCLASS32, ET_CORE};
+use goblin::elf::program_header::{pt_to_str, PT_LOAD};
+use goblin::elf::reloc::{r_to_str, RelocSection};
+use goblin::elf::section_header::{sht_to_str, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_DYNSYM};
+use goblin::elf::sym::{bind_to_str, type_to_str, Symtab, STT_FILE, STT_SECTION};
+use goblin::elf::Elf;
+use goblin::strtab::Strtab;
+use rair_io::MapPerm;
+use serde_json::{json, Value};
+use std::io::Write;
+
+pub fn parse(data: &[u8]) -> Result<Elf<'_>, String> {
+    Elf::parse(data).map_err(|e| e.to_string())
+}
+
+/// Map every loadable segment, flag entry point, sections and symbols then
+/// move to the entry point. Core dumps are further handled by [`elfcore::load`].
+pub fn load(core: &mut Core, base: u64, data: &[u8], elf: &Elf) -> Result<(), String> {
+    let is_core = elf.header.e_type == ET_CORE;
+    let loads = elf.program_headers.iter().filter(|ph| ph.p_type == PT_LOAD);
+    for (i, ph) in loads.enumerate() {
+        if ph.p_offset.saturating_add(ph.p_filesz) > data.len() as u64 {
+            return Err(format!(
+                "Segment at 0x{:x} is out of file bounds.",
+                ph.p_vaddr
+            ));
+        }
+        let size = ph.p_filesz.min(ph.p_memsz);
+        // memory that was left out of core dumps is not zero filled.
+        let mem_size = if is_core { size } else { ph.p_memsz };
+        let mut perm = MapPerm::empty();
+        perm.set(MapPerm::READ, ph.is_read());
+        perm.set(MapPerm::WRITE, ph.is_write());
+        perm.set(MapPerm::EXEC, ph.is_executable());
+        let name = format!("LOAD{i}");
+        let seg = SegmentMap {
+            name: &name,
+            offset: ph.p_offset,
+            vaddr: ph.p_vaddr,
+            size,
+            mem_size,
+            perm,
+        };
+        map_segment(core, base, &seg)?;
+    }
+    for sh in elf.section_headers.iter().filter(|sh| sh.sh_addr != 0) {
+        if let Some(name) = elf.shdr_strtab.get_at(sh.sh_name) {
+            add_flag(core, "section", name, sh.sh_addr, sh.sh_size, "sections");
+        }
+    }
+    for (syms, strtab) in [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)] {
+        for sym in syms {
+            if sym.st_value == 0 || matches!(sym.st_type(), STT_FILE | STT_SECTION) {
+                continue;
+            }
+            if let Some(name) = strtab.get_at(sym.st_name).filter(|n| !n.is_empty()) {
+                add_flag(core, "sym", name, sym.st_value, sym.st_size, "symbols");
+            }
+        }
+    }
+    if elf.entry != 0 {
+        core.flags.add("entry0", elf.entry, 1, "symbols").unwrap();
+    }
+    seek_entry(core, elf.entry);
+    if is_core {
+        elfcore::load(core, elf, data);
+    }
+    Ok(())
+}
+
+fn perms(read: bool, write: bool, exec: bool) -> String {
+    let mut perms = String::with_capacity(3);
+    perms.push(if read { 'r' } else { '-' });
+    perms.push(if write { 'w' } else { '-' });
+    perms.push(if exec { 'x' } else { '-' });
+    perms
+}
+
+pub fn print_info(core: &mut Core, elf: &Elf) {
+    let class = if elf.header.e_ident[4] == ELFCLASS32 {
+        "ELF32"
+    } else {
+        "ELF64"
+    };
+    let endian = if elf.little_endian { "little" } else { "big" };
+    if is_json(core) {
+        let segments: Vec<_> = elf
+            .program_headers
+            .iter()
+            .map(|ph| {
+                json!({
+                    "type": pt_to_str(ph.p_type).trim_start_matches("PT_"),
+                    "offset": ph.p_offset,
+                    "vaddr": ph.p_vaddr,
+                    "file_size": ph.p_filesz,
+                    "mem_size": ph.p_memsz,
+                    "perms": perms(ph.is_read(), ph.is_write(), ph.is_executable()),
+                })
+            })
+            .collect();
+        let info = json!({
+            "format": class,
+            "endianness": endian,
+            "type": et_to_str(elf.header.e_type),
+            "machine": machine_to_str(elf.header.e_machine),
+            "entry": elf.entry,
+            "segments": segments,
+        });
+        return print_json(core, &info);
+    }
+    writeln!(core.stdout, "{: <20}{}", "Format", class).unwrap();
+    writeln!(core.stdout, "{: <20}{}", "Endianness", endian).unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}{}",
+        "Type",
+        et_to_str(elf.header.e_type)
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}{}",
+        "Machine",
+        machine_to_str(elf.header.e_machine)
+    )
+    .unwrap();
+    writeln!(core.stdout, "{: <20}0x{:x}", "Entry", elf.entry).unwrap();
+    if elf.program_headers.is_empty() {
+        return;
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            "Type", "Offset", "VirtAddr", "FileSize", "MemSize", "Perms"
+        ),
+    );
+    for ph in &elf.program_headers {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            pt_to_str(ph.p_type).trim_start_matches("PT_"),
+            format!("0x{:x}", ph.p_offset),
+            format!("0x{:x}", ph.p_vaddr),
+            format!("0x{:x}", ph.p_filesz),
+            format!("0x{:x}", ph.p_memsz),
+            perms(ph.is_read(), ph.is_write(), ph.is_executable())
+        )
+        .unwrap();
+    }
+}
+
+fn section_perms(flags: u64) -> String {
+    perms(
+        flags & u64::from(SHF_ALLOC) != 0,
+        flags & u64::from(SHF_WRITE) != 0,
+        flags & u64::from(SHF_EXECINSTR) != 0,
+    )
+}
+
+pub fn print_sections(core: &mut Core, elf: &Elf) {
+    if is_json(core) {
+        let sections = elf
+            .section_headers
+            .iter()
+            .map(|sh| {
+                json!({
+                    "address": sh.sh_addr,
+                    "offset": sh.sh_offset,
+                    "size": sh.sh_size,
+                    "type": sht_to_str(sh.sh_type).trim_start_matches("SHT_"),
+                    "perms": section_perms(sh.sh_flags),
+                    "name": elf.shdr_strtab.get_at(sh.sh_name).unwrap_or(""),
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(sections));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            "Address", "Offset", "Size", "Type", "Perms", "Name"
+        ),
+    );
+    for sh in &elf.section_headers {
+        let name = elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("");
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", sh.sh_addr),
+            format!("0x{:x}", sh.sh_offset),
+            format!("0x{:x}", sh.sh_size),
+            sht_to_str(sh.sh_type).trim_start_matches("SHT_"),
+            section_perms(sh.sh_flags),
+            name
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_symbols(core: &mut Core, elf: &Elf) {
+    if is_json(core) {
+        let mut symbols = Vec::new();
+        for (syms, strtab) in [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)] {
+            for sym in syms {
+                let Some(name) = strtab.get_at(sym.st_name).filter(|n| !n.is_empty()) else {
+                    continue;
+                };
+                symbols.push(json!({
+                    "address": sym.st_value,
+                    "size": sym.st_size,
+                    "type": type_to_str(sym.st_type()),
+                    "bind": bind_to_str(sym.st_bind()),
+                    "name": name,
+                }));
+            }
+        }
+        return print_json(core, &Value::Array(symbols));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{}",
+            "Address", "Size", "Type", "Bind", "Name"
+        ),
+    );
+    for (syms, strtab) in [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)] {
+        for sym in syms {
+            let Some(name) = strtab.get_at(sym.st_name).filter(|n| !n.is_empty()) else {
+                continue;
+            };
+            writeln!(
+                core.stdout,
+                "{: <20}{: <20}{: <20}{: <20}{}",
+                format!("0x{:x}", sym.st_value),
+                format!("0x{:x}", sym.st_size),
+                type_to_str(sym.st_type()),
+                bind_to_str(sym.st_bind()),
+                name
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Every relocation section along with the symbol table its entries refer to.
+fn reloc_sections<'a>(elf: &'a Elf) -> Vec<(&'a RelocSection<'a>, &'a Symtab<'a>, &'a Strtab<'a>)> {
+    if elf.shdr_relocs.is_empty() {
+        return [&elf.dynrelas, &elf.dynrels, &elf.pltrelocs]
+            .into_iter()
+            .map(|relocs| (relocs, &elf.dynsyms, &elf.dynstrtab))
+            .collect();
+    }
+    elf.shdr_relocs
+        .iter()
+        .map(|(idx, relocs)| {
+            let dynamic = elf
+                .section_headers
+                .get(*idx)
+                .and_then(|sh| elf.section_headers.get(sh.sh_link as usize))
+                .is_some_and(|link| link.sh_type == SHT_DYNSYM);
+            if dynamic {
+                (relocs, &elf.dynsyms, &elf.dynstrtab)
+            } else {
+                (relocs, &elf.syms, &elf.strtab)
+            }
+        })
+        .collect()
+}
+
+pub fn print_relocs(core: &mut Core, elf: &Elf) {
+    let sections = reloc_sections(elf);
+    let json = is_json(core);
+    if !json {
+        print_header(
+            core,
+            &format!(
+                "{: <20}{: <20}{: <20}{}",
+                "Offset", "Type", "Addend", "Symbol"
+            ),
+        );
+    }
+    let mut rows = Vec::new();
+    for (relocs, syms, strtab) in sections {
+        for reloc in relocs {
+            let name = syms
+                .get(reloc.r_sym)
+                .and_then(|sym| strtab.get_at(sym.st_name))
+                .unwrap_or("");
+            let typ = r_to_str(reloc.r_type, elf.header.e_machine);
+            if json {
+                rows.push(json!({
+                    "offset": reloc.r_offset,
+                    "type": typ,
+                    "addend": reloc.r_addend,
+                    "symbol": name,
+                }));
+                continue;
+            }
+            let addend = reloc.r_addend.map_or(String::new(), |a| {
+                if a < 0 {
+                    format!("-0x{:x}", a.unsigned_abs())
+                } else {
+                    format!("0x{a:x}")
+                }
+            });
+            writeln!(
+                core.stdout,
+                "{: <20}{: <20}{: <20}{}",
+                format!("0x{:x}", reloc.r_offset),
+                typ,
+                addend,
+                name
+            )
+            .unwrap();
+        }
+    }
+    if json {
+        print_json(core, &Value::Array(rows));
+    }
+}
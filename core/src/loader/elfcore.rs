@@ -0,0 +1,326 @@
+//! ELF core dump notes: thread registers, file backed mappings and auxiliary vector.
+
+use super::{add_flag, print_header, seek_entry};
+use crate::core::Core;
+use crate::helper::{error_msg, is_json, print_json};
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+use goblin::elf::note::{NT_FILE, NT_PRSTATUS};
+use goblin::elf::Elf;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+
+const NT_AUXV: u32 = 6;
+
+const X86_64_REGS: [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi",
+    "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs",
+    "gs",
+];
+
+const I386_REGS: [&str; 17] = [
+    "ebx", "ecx", "edx", "esi", "edi", "ebp", "eax", "ds", "es", "fs", "gs", "orig_eax", "eip",
+    "cs", "eflags", "esp", "ss",
+];
+
+const AARCH64_REGS: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "pc", "pstate",
+];
+
+const ARM_REGS: [&str; 18] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc", "cpsr", "orig_r0",
+];
+
+const AUXV_TYPES: [(u64, &str); 22] = [
+    (3, "AT_PHDR"),
+    (4, "AT_PHENT"),
+    (5, "AT_PHNUM"),
+    (6, "AT_PAGESZ"),
+    (7, "AT_BASE"),
+    (8, "AT_FLAGS"),
+    (9, "AT_ENTRY"),
+    (11, "AT_UID"),
+    (12, "AT_EUID"),
+    (13, "AT_GID"),
+    (14, "AT_EGID"),
+    (15, "AT_PLATFORM"),
+    (16, "AT_HWCAP"),
+    (17, "AT_CLKTCK"),
+    (23, "AT_SECURE"),
+    (24, "AT_BASE_PLATFORM"),
+    (25, "AT_RANDOM"),
+    (26, "AT_HWCAP2"),
+    (31, "AT_EXECFN"),
+    (32, "AT_SYSINFO"),
+    (33, "AT_SYSINFO_EHDR"),
+    (51, "AT_MINSIGSTKSZ"),
+];
+
+/// Register set of one thread as recorded in `NT_PRSTATUS`.
+pub struct Thread {
+    pub pid: u32,
+    pub signal: u16,
+    pub regs: Vec<(&'static str, u64)>,
+}
+
+/// File backed mapping as recorded in `NT_FILE`.
+pub struct FileMapping {
+    pub start: u64,
+    pub end: u64,
+    pub offset: u64,
+    pub path: String,
+}
+
+/// Reads address sized words out of note descriptors.
+struct Reader<'a> {
+    desc: &'a [u8],
+    word: usize,
+    le: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(elf: &Elf, desc: &'a [u8]) -> Self {
+        Reader {
+            desc,
+            word: if elf.is_64 { 8 } else { 4 },
+            le: elf.little_endian,
+        }
+    }
+    fn uint(&self, off: usize, size: usize) -> Option<u64> {
+        let bytes = self.desc.get(off..off.checked_add(size)?)?;
+        let fold = |acc: u64, b: &u8| (acc << 8u32) | u64::from(*b);
+        if self.le {
+            Some(bytes.iter().rev().fold(0, fold))
+        } else {
+            Some(bytes.iter().fold(0, fold))
+        }
+    }
+    fn word(&self, index: usize) -> Option<u64> {
+        self.uint(index.checked_mul(self.word)?, self.word)
+    }
+}
+
+/// Descriptors of every note of type `n_type`.
+fn notes<'a>(elf: &Elf<'a>, data: &'a [u8], n_type: u32) -> Vec<&'a [u8]> {
+    let Some(notes) = elf.iter_note_headers(data) else {
+        return Vec::new();
+    };
+    notes
+        .filter_map(Result::ok)
+        .filter(|note| note.n_type == n_type && note.name == "CORE")
+        .map(|note| note.desc)
+        .collect()
+}
+
+fn reg_names(machine: u16) -> &'static [&'static str] {
+    match machine {
+        EM_X86_64 => &X86_64_REGS,
+        EM_386 => &I386_REGS,
+        EM_AARCH64 => &AARCH64_REGS,
+        EM_ARM => &ARM_REGS,
+        _ => &[],
+    }
+}
+
+/// Threads of the crashed process, the thread that caused the dump comes first.
+pub fn threads(elf: &Elf, data: &[u8]) -> Vec<Thread> {
+    // offsets of pr_pid and pr_reg inside struct elf_prstatus.
+    let (pid_off, regs_off) = if elf.is_64 { (32, 112) } else { (24, 72) };
+    let mut threads = Vec::new();
+    for desc in notes(elf, data, NT_PRSTATUS) {
+        let reader = Reader::new(elf, desc);
+        let (Some(signal), Some(pid)) = (reader.uint(12, 2), reader.uint(pid_off, 4)) else {
+            continue;
+        };
+        let regs = reg_names(elf.header.e_machine)
+            .iter()
+            .enumerate()
+            .map_while(|(i, name)| {
+                Some((*name, reader.uint(regs_off + i * reader.word, reader.word)?))
+            })
+            .collect();
+        threads.push(Thread {
+            pid: pid as u32,
+            signal: signal as u16,
+            regs,
+        });
+    }
+    threads
+}
+
+/// Parse one `NT_FILE` note, failing if its counts or offsets overflow.
+fn parse_file_note(reader: &Reader, desc: &[u8]) -> Result<Vec<FileMapping>, String> {
+    let (Some(count), Some(page_size)) = (reader.word(0), reader.word(1)) else {
+        return Ok(Vec::new());
+    };
+    let overflow = || "NT_FILE note has out of range values.".to_owned();
+    let count = usize::try_from(count).map_err(|_| overflow())?;
+    let names_off = count
+        .checked_mul(3)
+        .and_then(|words| words.checked_add(2))
+        .and_then(|words| words.checked_mul(reader.word))
+        .ok_or_else(overflow)?;
+    let mut names = desc.get(names_off..).unwrap_or_default().split(|b| *b == 0);
+    let mut mappings = Vec::new();
+    for i in 0..count {
+        let entry = 2 + i * 3;
+        let (Some(start), Some(end), Some(offset)) = (
+            reader.word(entry),
+            reader.word(entry + 1),
+            reader.word(entry + 2),
+        ) else {
+            break;
+        };
+        let path = String::from_utf8_lossy(names.next().unwrap_or_default()).into_owned();
+        mappings.push(FileMapping {
+            start,
+            end,
+            offset: offset.checked_mul(page_size).ok_or_else(overflow)?,
+            path,
+        });
+    }
+    Ok(mappings)
+}
+
+/// Files that were mapped into memory of the crashed process, malformed notes are reported
+/// and skipped.
+pub fn file_mappings(core: &mut Core, elf: &Elf, data: &[u8]) -> Vec<FileMapping> {
+    let mut mappings = Vec::new();
+    for desc in notes(elf, data, NT_FILE) {
+        let reader = Reader::new(elf, desc);
+        match parse_file_note(&reader, desc) {
+            Ok(note) => mappings.extend(note),
+            Err(e) => error_msg(core, "Failed to parse core dump", &e),
+        }
+    }
+    mappings
+}
+
+/// Entries of the auxiliary vector of the crashed process.
+pub fn auxv(elf: &Elf, data: &[u8]) -> Vec<(u64, u64)> {
+    let mut entries = Vec::new();
+    for desc in notes(elf, data, NT_AUXV) {
+        let reader = Reader::new(elf, desc);
+        let mut i = 0;
+        while let (Some(typ), Some(value)) = (reader.word(i), reader.word(i + 1)) {
+            if typ == 0 {
+                break;
+            }
+            entries.push((typ, value));
+            i += 2;
+        }
+    }
+    entries
+}
+
+fn auxv_name(typ: u64) -> String {
+    AUXV_TYPES
+        .iter()
+        .find(|(t, _)| *t == typ)
+        .map_or_else(|| typ.to_string(), |(_, name)| (*name).to_owned())
+}
+
+/// Flag file backed mappings and move to the program counter of the crashing thread.
+pub fn load(core: &mut Core, elf: &Elf, data: &[u8]) {
+    for mapping in file_mappings(core, elf, data) {
+        let name = mapping.path.rsplit('/').next().unwrap_or_default();
+        if !name.is_empty() {
+            let name = format!("{name}.{:x}", mapping.start);
+            let size = mapping.end.saturating_sub(mapping.start);
+            add_flag(core, "map", &name, mapping.start, size, "maps");
+        }
+    }
+    let pc = threads(elf, data).first().and_then(|thread| {
+        thread
+            .regs
+            .iter()
+            .find(|(name, _)| matches!(*name, "rip" | "eip" | "pc"))
+            .map(|(_, pc)| *pc)
+    });
+    if let Some(pc) = pc {
+        core.flags.add("pc", pc, 1, "symbols").unwrap();
+        seek_entry(core, pc);
+    }
+}
+
+pub fn print_threads(core: &mut Core, elf: &Elf, data: &[u8]) {
+    let threads = threads(elf, data);
+    if is_json(core) {
+        let threads = threads
+            .iter()
+            .map(|thread| {
+                let regs: Map<String, Value> = thread
+                    .regs
+                    .iter()
+                    .map(|(name, value)| ((*name).to_owned(), json!(value)))
+                    .collect();
+                json!({
+                    "pid": thread.pid,
+                    "signal": thread.signal,
+                    "registers": regs,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(threads));
+    }
+    for (i, thread) in threads.iter().enumerate() {
+        if i != 0 {
+            writeln!(core.stdout).unwrap();
+        }
+        writeln!(core.stdout, "{: <20}{}", "Pid", thread.pid).unwrap();
+        writeln!(core.stdout, "{: <20}{}", "Signal", thread.signal).unwrap();
+        for (name, value) in &thread.regs {
+            writeln!(core.stdout, "{name: <20}0x{value:x}").unwrap();
+        }
+    }
+}
+
+pub fn print_mappings(core: &mut Core, elf: &Elf, data: &[u8]) {
+    let mappings = file_mappings(core, elf, data);
+    if is_json(core) {
+        let mappings = mappings
+            .iter()
+            .map(|mapping| {
+                json!({
+                    "start": mapping.start,
+                    "end": mapping.end,
+                    "offset": mapping.offset,
+                    "path": mapping.path,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(mappings));
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{: <20}{}", "Start", "End", "Offset", "Path"),
+    );
+    for mapping in mappings {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", mapping.start),
+            format!("0x{:x}", mapping.end),
+            format!("0x{:x}", mapping.offset),
+            mapping.path
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_auxv(core: &mut Core, elf: &Elf, data: &[u8]) {
+    let entries = auxv(elf, data);
+    if is_json(core) {
+        let entries = entries
+            .iter()
+            .map(|(typ, value)| json!({"type": auxv_name(*typ), "value": value}))
+            .collect();
+        return print_json(core, &Value::Array(entries));
+    }
+    print_header(core, &format!("{: <20}{}", "Type", "Value"));
+    for (typ, value) in entries {
+        writeln!(core.stdout, "{: <20}0x{value:x}", auxv_name(typ)).unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+//! Mach-O and fat (universal) binaries loading and information printing.
+
+use super::{add_flag, map_segment, print_header, seek_entry, SegmentMap};
+use crate::core::Core;
+use crate::helper::{is_json, print_json};
+use goblin::mach::constants::cputype::get_arch_name_from_types;
+use goblin::mach::header::filetype_to_str;
+use goblin::mach::load_command::cmd_to_str;
+use goblin::mach::segment::Segment;
+use goblin::mach::{MachO, MultiArch};
+use rair_io::MapPerm;
+use serde_json::{json, Value};
+use std::io::Write;
+
+const VM_PROT_READ: u32 = 1;
+const VM_PROT_WRITE: u32 = 2;
+const VM_PROT_EXECUTE: u32 = 4;
+
+pub fn parse(data: &[u8]) -> Result<MachO<'_>, String> {
+    MachO::parse(data, 0).map_err(|e| e.to_string())
+}
+
+pub fn parse_fat(data: &[u8]) -> Result<MultiArch<'_>, String> {
+    MultiArch::new(data).map_err(|e| e.to_string())
+}
+
+/// Address the Mach-O header is loaded at, export addresses are relative to it.
+/// Malformed `__TEXT` segments that would place the header below 0 are treated as 0.
+fn image_base(macho: &MachO) -> u64 {
+    macho
+        .segments
+        .iter()
+        .find(|seg| seg.name().is_ok_and(|name| name == "__TEXT"))
+        .and_then(|seg| seg.vmaddr.checked_sub(seg.fileoff))
+        .unwrap_or(0)
+}
+
+fn perms(prot: u32) -> String {
+    let mut perms = String::with_capacity(3);
+    for (flag, c) in [
+        (VM_PROT_READ, 'r'),
+        (VM_PROT_WRITE, 'w'),
+        (VM_PROT_EXECUTE, 'x'),
+    ] {
+        perms.push(if prot & flag == 0 { '-' } else { c });
+    }
+    perms
+}
+
+fn map_perm(prot: u32) -> MapPerm {
+    let mut perm = MapPerm::empty();
+    perm.set(MapPerm::READ, prot & VM_PROT_READ != 0);
+    perm.set(MapPerm::WRITE, prot & VM_PROT_WRITE != 0);
+    perm.set(MapPerm::EXEC, prot & VM_PROT_EXECUTE != 0);
+    perm
+}
+
+fn segment_name<'a>(seg: &'a Segment) -> &'a str {
+    seg.name().unwrap_or("")
+}
+
+fn machine(cputype: u32, cpusubtype: u32) -> String {
+    get_arch_name_from_types(cputype, cpusubtype)
+        .map_or_else(|| format!("0x{cputype:x}"), str::to_owned)
+}
+
+/// Map every segment, flag entry point, sections, symbols, imports and
+/// exports then move to the entry point.
+pub fn load(core: &mut Core, base: u64, data: &[u8], macho: &MachO) -> Result<(), String> {
+    for seg in &macho.segments {
+        // __PAGEZERO and alike are only there to reserve address space.
+        if seg.vmsize == 0 || seg.initprot == 0 {
+            continue;
+        }
+        if seg.fileoff.saturating_add(seg.filesize) > data.len() as u64 {
+            return Err(format!(
+                "Segment {} is out of file bounds.",
+                segment_name(seg)
+            ));
+        }
+        let size = seg.filesize.min(seg.vmsize);
+        let map = SegmentMap {
+            name: segment_name(seg),
+            offset: seg.fileoff,
+            vaddr: seg.vmaddr,
+            size,
+            mem_size: seg.vmsize,
+            perm: map_perm(seg.initprot),
+        };
+        map_segment(core, base, &map)?;
+        for (section, _) in seg.sections().map_err(|e| e.to_string())? {
+            if let (Ok(seg), Ok(sect)) = (section.segname(), section.name()) {
+                let name = format!("{seg}.{sect}");
+                add_flag(
+                    core,
+                    "section",
+                    &name,
+                    section.addr,
+                    section.size,
+                    "sections",
+                );
+            }
+        }
+    }
+    for sym in macho.symbols() {
+        let (name, nlist) = sym.map_err(|e| e.to_string())?;
+        if nlist.is_stab() || nlist.is_undefined() || nlist.n_value == 0 || name.is_empty() {
+            continue;
+        }
+        add_flag(core, "sym", name, nlist.n_value, 0, "symbols");
+    }
+    for import in macho.imports().map_err(|e| e.to_string())? {
+        let size = import.size as u64;
+        add_flag(core, "imp", import.name, import.address, size, "imports");
+    }
+    let image_base = image_base(macho);
+    for export in macho.exports().map_err(|e| e.to_string())? {
+        let addr = image_base.wrapping_add(export.offset);
+        add_flag(
+            core,
+            "exp",
+            &export.name,
+            addr,
+            export.size as u64,
+            "exports",
+        );
+    }
+    if macho.entry != 0 {
+        core.flags.add("entry0", macho.entry, 1, "symbols").unwrap();
+    }
+    seek_entry(core, macho.entry);
+    Ok(())
+}
+
+/// Load the first architecture of fat binary, other architectures can be
+/// opened through `fat://` uris.
+pub fn load_fat(core: &mut Core, base: u64, data: &[u8], fat: &MultiArch) -> Result<(), String> {
+    let arches = fat.arches().map_err(|e| e.to_string())?;
+    let Some(arch) = arches.first() else {
+        return Ok(());
+    };
+    if u64::from(arch.offset) + u64::from(arch.size) > data.len() as u64 {
+        return Err("Fat slice is out of file bounds.".to_owned());
+    }
+    let slice = arch.slice(data);
+    if !super::is_macho(slice) {
+        return Ok(());
+    }
+    let macho = parse(slice)?;
+    load(core, base + u64::from(arch.offset), slice, &macho)
+}
+
+pub fn print_info(core: &mut Core, macho: &MachO) {
+    let format = if macho.is_64 { "Mach-O64" } else { "Mach-O32" };
+    let endian = if macho.little_endian { "little" } else { "big" };
+    let header = &macho.header;
+    let entry_cmd = if macho.old_style_entry {
+        "LC_UNIXTHREAD"
+    } else {
+        "LC_MAIN"
+    };
+    if is_json(core) {
+        let cmds: Vec<_> = macho
+            .load_commands
+            .iter()
+            .map(|lc| {
+                json!({
+                    "offset": lc.offset,
+                    "size": lc.command.cmdsize(),
+                    "command": cmd_to_str(lc.command.cmd()),
+                })
+            })
+            .collect();
+        let mut info = json!({
+            "format": format,
+            "endianness": endian,
+            "type": filetype_to_str(header.filetype),
+            "machine": machine(header.cputype, header.cpusubtype),
+            "flags": header.flags,
+            "entry": macho.entry,
+            "libraries": &macho.libs[1..],
+            "load_commands": cmds,
+        });
+        if macho.entry != 0 {
+            info["entry_command"] = json!(entry_cmd);
+        }
+        return print_json(core, &info);
+    }
+    writeln!(core.stdout, "{: <20}{}", "Format", format).unwrap();
+    writeln!(core.stdout, "{: <20}{}", "Endianness", endian).unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}{}",
+        "Type",
+        filetype_to_str(header.filetype)
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}{}",
+        "Machine",
+        machine(header.cputype, header.cpusubtype)
+    )
+    .unwrap();
+    writeln!(core.stdout, "{: <20}0x{:x}", "Flags", header.flags).unwrap();
+    if macho.entry != 0 {
+        writeln!(
+            core.stdout,
+            "{: <20}0x{:x} ({entry_cmd})",
+            "Entry", macho.entry
+        )
+        .unwrap();
+    }
+    // first library is always the binary itself.
+    for lib in &macho.libs[1..] {
+        writeln!(core.stdout, "{: <20}{}", "Library", lib).unwrap();
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{}", "Offset", "Size", "LoadCommand"),
+    );
+    for lc in &macho.load_commands {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}",
+            format!("0x{:x}", lc.offset),
+            format!("0x{:x}", lc.command.cmdsize()),
+            cmd_to_str(lc.command.cmd())
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_fat_info(core: &mut Core, fat: &MultiArch) {
+    let arches = fat.arches().unwrap_or_default();
+    if is_json(core) {
+        let arches: Vec<_> = arches
+            .iter()
+            .map(|arch| {
+                json!({
+                    "machine": machine(arch.cputype, arch.cpusubtype),
+                    "offset": arch.offset,
+                    "size": arch.size,
+                    "align": 1u64 << arch.align.min(63),
+                })
+            })
+            .collect();
+        return print_json(core, &json!({"format": "FAT", "arches": arches}));
+    }
+    writeln!(core.stdout, "{: <20}FAT", "Format").unwrap();
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{}",
+            "Index", "Offset", "Size", "Align", "Machine"
+        ),
+    );
+    for (i, arch) in arches.iter().enumerate() {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{: <20}{}",
+            i,
+            format!("0x{:x}", arch.offset),
+            format!("0x{:x}", arch.size),
+            format!("0x{:x}", 1u64 << arch.align.min(63)),
+            machine(arch.cputype, arch.cpusubtype)
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_sections(core: &mut Core, macho: &MachO) {
+    let mut sections = Vec::new();
+    for seg in &macho.segments {
+        for (section, _) in seg.sections().unwrap_or_default() {
+            let name = format!(
+                "{}.{}",
+                section.segname().unwrap_or(""),
+                section.name().unwrap_or("")
+            );
+            sections.push((section, perms(seg.initprot), name));
+        }
+    }
+    if is_json(core) {
+        let sections = sections
+            .into_iter()
+            .map(|(section, perms, name)| {
+                json!({
+                    "address": section.addr,
+                    "offset": section.offset,
+                    "size": section.size,
+                    "perms": perms,
+                    "name": name,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(sections));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{}",
+            "Address", "Offset", "Size", "Perms", "Name"
+        ),
+    );
+    for (section, perms, name) in sections {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", section.addr),
+            format!("0x{:x}", section.offset),
+            format!("0x{:x}", section.size),
+            perms,
+            name
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_symbols(core: &mut Core, macho: &MachO) {
+    let symbols: Vec<_> = macho
+        .symbols()
+        .filter_map(Result::ok)
+        .filter(|(name, nlist)| !nlist.is_stab() && !name.is_empty())
+        .collect();
+    if is_json(core) {
+        let symbols = symbols
+            .into_iter()
+            .map(|(name, nlist)| {
+                json!({
+                    "address": nlist.n_value,
+                    "type": nlist.type_str().trim_start_matches("N_"),
+                    "global": nlist.is_global(),
+                    "name": name,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(symbols));
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{: <20}{}", "Address", "Type", "Bind", "Name"),
+    );
+    for (name, nlist) in symbols {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", nlist.n_value),
+            nlist.type_str().trim_start_matches("N_"),
+            if nlist.is_global() { "GLOBAL" } else { "LOCAL" },
+            name
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_imports(core: &mut Core, macho: &MachO) {
+    let imports = macho.imports().unwrap_or_default();
+    let bind = |lazy: bool, weak: bool| match (lazy, weak) {
+        (true, _) => "lazy",
+        (false, true) => "weak",
+        (false, false) => "bind",
+    };
+    if is_json(core) {
+        let imports = imports
+            .iter()
+            .map(|import| {
+                json!({
+                    "address": import.address,
+                    "bind": bind(import.is_lazy, import.is_weak),
+                    "library": import.dylib,
+                    "name": import.name,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(imports));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{}",
+            "Address", "Bind", "Name", "Library"
+        ),
+    );
+    for import in &imports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", import.address),
+            bind(import.is_lazy, import.is_weak),
+            import.name,
+            import.dylib
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_exports(core: &mut Core, macho: &MachO) {
+    let image_base = image_base(macho);
+    let exports = macho.exports().unwrap_or_default();
+    if is_json(core) {
+        let exports = exports
+            .iter()
+            .map(|export| {
+                json!({
+                    "address": image_base.wrapping_add(export.offset),
+                    "size": export.size,
+                    "name": export.name,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(exports));
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{}", "Address", "Size", "Name"),
+    );
+    for export in &exports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}",
+            format!("0x{:x}", image_base.wrapping_add(export.offset)),
+            format!("0x{:x}", export.size),
+            export.name
+        )
+        .unwrap();
+    }
+}
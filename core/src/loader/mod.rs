@@ -0,0 +1,289 @@
+//! commands for loading executable formats into the virtual address space.
+
+mod cmds;
+mod elf;
+mod elfcore;
+mod macho;
+mod pe;
+
+use self::cmds::{Auxv, Exports, Imports, Info, Mappings, Relocs, Sections, Symbols, Threads};
+use crate::core::Core;
+use crate::flags::FlagStore;
+use crate::helper::{error_msg, is_color, AddrMode};
+use alloc::sync::Arc;
+use goblin::elf::Elf;
+use goblin::mach::{MachO, MultiArch};
+use goblin::pe::PE;
+use rair_io::{IoMode, MapPerm, RIODesc, RIOMap, RIORegion};
+use std::io::Write;
+use yansi::Paint;
+
+pub fn register_loader(core: &mut Core) {
+    let env = core.env.clone();
+    env.write()
+        .add_bool(
+            "loader.autoLoad",
+            true,
+            "Map executable formats into virtual address space once opened",
+        )
+        .unwrap();
+    env.write()
+        .add_str_with_cb(
+            "loader.headerColor",
+            "color.6",
+            "Color used in the header of binary information commands",
+            core,
+            is_color,
+        )
+        .unwrap();
+    core.add_command(Info);
+    core.add_command(Sections);
+    core.add_command(Symbols);
+    core.add_command(Relocs);
+    core.add_command(Imports);
+    core.add_command(Exports);
+    core.add_command(Threads);
+    core.add_command(Mappings);
+    core.add_command(Auxv);
+}
+
+/// Bytes read from the start of a file to detect its format.
+const HEADER_SIZE: u64 = 0x1000;
+
+/// Read the full content of the file opened as `hndl` if it is of a known binary format,
+/// only the first [`HEADER_SIZE`] bytes are read for files of any other format.
+fn read_desc(core: &mut Core, hndl: u64) -> Result<Option<Vec<u8>>, String> {
+    let Some(desc) = core.io.hndl_to_desc(hndl) else {
+        return Err(format!("Handle {hndl} does not exist."));
+    };
+    if !desc.regions().is_empty() {
+        return Err("File is already mapped into virtual address space.".to_owned());
+    }
+    let (base, size) = (desc.paddr_base(), desc.size());
+    let mut header = vec![0; size.min(HEADER_SIZE) as usize];
+    core.io
+        .pread(base, &mut header)
+        .map_err(|e| e.to_string())?;
+    if !is_binary(&header) {
+        return Ok(None);
+    }
+    let size = usize::try_from(size).map_err(|_| "File is too large to be parsed.".to_owned())?;
+    let mut data = vec![0; size];
+    core.io.pread(base, &mut data).map_err(|e| e.to_string())?;
+    Ok(Some(data))
+}
+
+/// Handle of the file backing current location.
+fn current_hndl(core: &Core) -> Option<u64> {
+    let loc = core.get_loc();
+    let paddr = match core.mode {
+        AddrMode::Phy => loc,
+        AddrMode::Vir => core.io.vir_to_phy(loc, 1)?.first()?.paddr,
+    };
+    core.io
+        .uri_iter()
+        .find(|desc| desc.has_paddr(paddr))
+        .map(RIODesc::hndl)
+}
+
+/// Part of a binary to be mapped into virtual address space, `size` bytes at `vaddr` come
+/// from file offset `offset` and the remaining `mem_size - size` bytes are zero filled.
+struct SegmentMap<'a> {
+    name: &'a str,
+    offset: u64,
+    vaddr: u64,
+    size: u64,
+    mem_size: u64,
+    perm: MapPerm,
+}
+
+/// Map `seg` of the file loaded at physical address `base`, zero filled memory is backed
+/// by a new malloc file.
+fn map_segment(core: &mut Core, base: u64, seg: &SegmentMap) -> Result<(), String> {
+    seg.vaddr.checked_add(seg.mem_size).ok_or_else(|| {
+        format!(
+            "Segment {} at 0x{:x} exceeds virtual address space.",
+            seg.name, seg.vaddr
+        )
+    })?;
+    if seg.size != 0 {
+        core.io
+            .map_named(base + seg.offset, seg.vaddr, seg.size, seg.name, seg.perm)
+            .map_err(|e| e.to_string())?;
+    }
+    if seg.mem_size > seg.size {
+        let zeros = seg.mem_size - seg.size;
+        let uri = format!("malloc://0x{zeros:x}");
+        let hndl = core
+            .io
+            .open(&uri, IoMode::READ | IoMode::WRITE)
+            .map_err(|e| e.to_string())?;
+        let paddr = core.io.hndl_to_desc(hndl).unwrap().paddr_base();
+        core.io
+            .map_named(paddr, seg.vaddr + seg.size, zeros, seg.name, seg.perm)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Add flag named `prefix.name` replacing characters that are not allowed in flag names.
+fn add_flag(core: &mut Core, prefix: &str, name: &str, addr: u64, size: u64, space: &str) {
+    let name: String = name
+        .trim_start_matches('.')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = format!("{prefix}.{name}");
+    if FlagStore::is_valid_name(&name) {
+        core.flags.add(&name, addr, size, space).unwrap();
+    }
+}
+
+/// Move to `entry` in virtual addressing mode.
+fn seek_entry(core: &mut Core, entry: u64) {
+    if entry != 0 {
+        core.mode = AddrMode::Vir;
+        core.set_loc(entry);
+    }
+}
+
+/// Parsed executable.
+enum Binary<'a> {
+    Elf(Box<Elf<'a>>),
+    Pe(Box<PE<'a>>),
+    MachO(Box<MachO<'a>>),
+    Fat(MultiArch<'a>),
+}
+
+/// Check for 32 or 64 bit Mach-O magic in either byte order.
+fn is_macho(data: &[u8]) -> bool {
+    data.get(..4).is_some_and(|magic| {
+        matches!(
+            magic,
+            b"\xfe\xed\xfa\xce" | b"\xce\xfa\xed\xfe" | b"\xfe\xed\xfa\xcf" | b"\xcf\xfa\xed\xfe"
+        )
+    })
+}
+
+/// Check for fat binary magic, java class files share the same magic but
+/// their version number is way larger than any sane count of architectures.
+fn is_fat(data: &[u8]) -> bool {
+    data.starts_with(b"\xca\xfe\xba\xbe")
+        && data
+            .get(4..8)
+            .is_some_and(|n| u32::from_be_bytes([n[0], n[1], n[2], n[3]]) < 0x20)
+}
+
+/// Check if `data` starts with the magic of any format [`parse_binary`] knows.
+fn is_binary(data: &[u8]) -> bool {
+    data.starts_with(b"\x7fELF") || data.starts_with(b"MZ") || is_macho(data) || is_fat(data)
+}
+
+/// Detect format of `data` and parse it, `None` means unknown format.
+fn parse_binary(data: &[u8]) -> Result<Option<Binary<'_>>, String> {
+    if data.starts_with(b"\x7fELF") {
+        Ok(Some(Binary::Elf(Box::new(elf::parse(data)?))))
+    } else if data.starts_with(b"MZ") {
+        Ok(Some(Binary::Pe(Box::new(pe::parse(data)?))))
+    } else if is_macho(data) {
+        Ok(Some(Binary::MachO(Box::new(macho::parse(data)?))))
+    } else if is_fat(data) {
+        Ok(Some(Binary::Fat(macho::parse_fat(data)?)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Flag every named region of a file that maps itself into virtual address space,
+/// such as memory of running processes.
+fn flag_regions(core: &mut Core, regions: &[RIORegion]) {
+    for region in regions {
+        let name = region.name.rsplit('/').next().unwrap_or_default();
+        let name = name.trim_start_matches('[').trim_end_matches(']');
+        if !name.is_empty() {
+            let name = format!("{name}.{:x}", region.vaddr);
+            add_flag(core, "map", &name, region.vaddr, region.size, "maps");
+        }
+    }
+}
+
+/// Detect format of file opened as `hndl` and map it into virtual address space.
+/// Files of unknown format are left untouched.
+pub fn load_binary(core: &mut Core, hndl: u64) -> Result<(), String> {
+    let Some(desc) = core.io.hndl_to_desc(hndl) else {
+        return Err(format!("Handle {hndl} does not exist."));
+    };
+    let regions = desc.regions();
+    if !regions.is_empty() {
+        flag_regions(core, &regions);
+        return Ok(());
+    }
+    let Some(data) = read_desc(core, hndl)? else {
+        return Ok(());
+    };
+    let base = core.io.hndl_to_desc(hndl).unwrap().paddr_base();
+    let maps: Vec<Arc<RIOMap>> = core.io.map_iter().collect();
+    let hndls: Vec<u64> = core.io.uri_iter().map(RIODesc::hndl).collect();
+    let flags = core.flags.clone();
+    let loaded = match parse_binary(&data)? {
+        Some(Binary::Elf(bin)) => elf::load(core, base, &data, &bin),
+        Some(Binary::Pe(bin)) => pe::load(core, base, &data, &bin),
+        Some(Binary::MachO(bin)) => macho::load(core, base, &data, &bin),
+        Some(Binary::Fat(bin)) => macho::load_fat(core, base, &data, &bin),
+        None => Ok(()),
+    };
+    if loaded.is_err() {
+        rollback(core, &maps, &hndls, flags);
+    }
+    loaded
+}
+
+/// Remove maps and close files that are neither in `maps` nor in `hndls` then restore `flags`,
+/// that is undo everything a failed [`load_binary`] did.
+fn rollback(core: &mut Core, maps: &[Arc<RIOMap>], hndls: &[u64], flags: FlagStore) {
+    core.flags = flags;
+    let created: Vec<Arc<RIOMap>> = core
+        .io
+        .map_iter()
+        .filter(|map| !maps.contains(map))
+        .collect();
+    for map in created {
+        core.io.remove_map(&map).unwrap();
+    }
+    let created: Vec<u64> = core
+        .io
+        .uri_iter()
+        .map(RIODesc::hndl)
+        .filter(|hndl| !hndls.contains(hndl))
+        .collect();
+    for hndl in created {
+        core.io.close(hndl).unwrap();
+    }
+}
+
+/// Same as [`load_binary`] but only if `loader.autoLoad` is set, errors are reported to stderr.
+pub fn auto_load(core: &mut Core, hndl: u64) {
+    if !core.env.read().get_bool("loader.autoLoad").unwrap() {
+        return;
+    }
+    if let Err(e) = load_binary(core, hndl) {
+        error_msg(core, "Failed to load binary", &e);
+    }
+}
+
+/// Print table header using `loader.headerColor`.
+fn print_header(core: &mut Core, header: &str) {
+    let env = core.env.read();
+    let color = env.get_str("loader.headerColor").unwrap();
+    let (r, g, b) = env.get_color(color).unwrap();
+    writeln!(core.stdout, "{}", header.rgb(r, g, b)).unwrap();
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,401 @@
+//! PE32 and PE32+ loading and information printing.
+
+use super::{add_flag, map_segment, print_header, seek_entry, SegmentMap};
+use crate::core::Core;
+use crate::helper::{is_json, print_json};
+use goblin::pe::header::machine_to_str;
+use goblin::pe::section_table::{
+    SectionTable, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+};
+use goblin::pe::PE;
+use rair_io::MapPerm;
+use serde_json::{json, Value};
+use std::io::Write;
+
+pub fn parse(data: &[u8]) -> Result<PE<'_>, String> {
+    PE::parse(data).map_err(|e| e.to_string())
+}
+
+fn section_name(section: &SectionTable) -> &str {
+    section.name().unwrap_or("")
+}
+
+/// Bytes backed by the file and total bytes of `section` once loaded.
+fn section_sizes(section: &SectionTable) -> (u64, u64) {
+    let raw = u64::from(section.size_of_raw_data);
+    let mem = match section.virtual_size {
+        0 => raw,
+        size => u64::from(size),
+    };
+    (raw.min(mem), mem)
+}
+
+/// Virtual address of `rva` for printing, wraps around for malformed image bases.
+fn va(pe: &PE, rva: u64) -> u64 {
+    (pe.image_base as u64).wrapping_add(rva)
+}
+
+/// Virtual address of `rva` for loading, fails for malformed image bases.
+fn checked_va(pe: &PE, rva: u64) -> Result<u64, String> {
+    (pe.image_base as u64).checked_add(rva).ok_or_else(|| {
+        format!(
+            "RVA 0x{rva:x} exceeds virtual address space with image base 0x{:x}.",
+            pe.image_base
+        )
+    })
+}
+
+fn section_perm(section: &SectionTable) -> MapPerm {
+    let mut perm = MapPerm::empty();
+    for (flag, p) in [
+        (IMAGE_SCN_MEM_READ, MapPerm::READ),
+        (IMAGE_SCN_MEM_WRITE, MapPerm::WRITE),
+        (IMAGE_SCN_MEM_EXECUTE, MapPerm::EXEC),
+    ] {
+        perm.set(p, section.characteristics & flag != 0);
+    }
+    perm
+}
+
+/// Map headers and every section at `ImageBase + RVA`, flag entry point,
+/// sections, imports and exports then move to the entry point.
+pub fn load(core: &mut Core, base: u64, data: &[u8], pe: &PE) -> Result<(), String> {
+    let image_base = pe.image_base as u64;
+    let data_len = data.len() as u64;
+    if let Some(opt) = pe.header.optional_header {
+        let headers = u64::from(opt.windows_fields.size_of_headers).min(data_len);
+        let seg = SegmentMap {
+            name: "headers",
+            offset: 0,
+            vaddr: image_base,
+            size: headers,
+            mem_size: headers,
+            perm: MapPerm::READ,
+        };
+        map_segment(core, base, &seg)?;
+    }
+    for section in &pe.sections {
+        let offset = u64::from(section.pointer_to_raw_data);
+        let (raw, mem) = section_sizes(section);
+        let raw = raw.min(data_len.saturating_sub(offset));
+        let vaddr = checked_va(pe, u64::from(section.virtual_address))?;
+        let seg = SegmentMap {
+            name: section_name(section),
+            offset,
+            vaddr,
+            size: raw,
+            mem_size: mem,
+            perm: section_perm(section),
+        };
+        map_segment(core, base, &seg)?;
+        add_flag(
+            core,
+            "section",
+            section_name(section),
+            vaddr,
+            mem,
+            "sections",
+        );
+    }
+    for import in &pe.imports {
+        let name = format!("{}.{}", import.dll, import.name);
+        let addr = checked_va(pe, import.offset as u64)?;
+        add_flag(core, "imp", &name, addr, import.size as u64, "imports");
+    }
+    for export in &pe.exports {
+        if let Some(name) = export.name {
+            let addr = checked_va(pe, export.rva as u64)?;
+            add_flag(core, "exp", name, addr, export.size as u64, "exports");
+        }
+    }
+    let entry = checked_va(pe, pe.entry as u64)?;
+    core.flags.add("entry0", entry, 1, "symbols").unwrap();
+    seek_entry(core, entry);
+    Ok(())
+}
+
+fn perms(characteristics: u32) -> String {
+    let mut perms = String::with_capacity(3);
+    for (flag, c) in [
+        (IMAGE_SCN_MEM_READ, 'r'),
+        (IMAGE_SCN_MEM_WRITE, 'w'),
+        (IMAGE_SCN_MEM_EXECUTE, 'x'),
+    ] {
+        perms.push(if characteristics & flag == 0 { '-' } else { c });
+    }
+    perms
+}
+
+fn info_json(pe: &PE, format: &str) -> Value {
+    let image_base = pe.image_base as u64;
+    let coff = &pe.header.coff_header;
+    let mut info = json!({
+        "format": format,
+        "machine": machine_to_str(coff.machine),
+        "nt_headers": pe.header.dos_header.pe_pointer,
+        "time_date_stamp": coff.time_date_stamp,
+        "characteristics": coff.characteristics,
+    });
+    let Some(opt) = pe.header.optional_header else {
+        return info;
+    };
+    let win = &opt.windows_fields;
+    let dirs: Vec<_> = opt
+        .data_directories
+        .dirs()
+        .map(|(typ, dir)| {
+            json!({
+                "type": format!("{typ:?}"),
+                "vaddr": va(pe, u64::from(dir.virtual_address)),
+                "size": dir.size,
+            })
+        })
+        .collect();
+    info["image_base"] = json!(image_base);
+    info["entry"] = json!(va(pe, pe.entry as u64));
+    info["section_alignment"] = json!(win.section_alignment);
+    info["file_alignment"] = json!(win.file_alignment);
+    info["size_of_image"] = json!(win.size_of_image);
+    info["size_of_headers"] = json!(win.size_of_headers);
+    info["subsystem"] = json!(win.subsystem);
+    info["data_directories"] = Value::Array(dirs);
+    info
+}
+
+pub fn print_info(core: &mut Core, pe: &PE) {
+    let image_base = pe.image_base as u64;
+    let coff = &pe.header.coff_header;
+    let format = if pe.is_64 { "PE32+" } else { "PE32" };
+    if is_json(core) {
+        return print_json(core, &info_json(pe, format));
+    }
+    writeln!(core.stdout, "{: <20}{}", "Format", format).unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}{}",
+        "Machine",
+        machine_to_str(coff.machine)
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "NtHeaders", pe.header.dos_header.pe_pointer
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "TimeDateStamp", coff.time_date_stamp
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "Characteristics", coff.characteristics
+    )
+    .unwrap();
+    let Some(opt) = pe.header.optional_header else {
+        return;
+    };
+    let win = &opt.windows_fields;
+    writeln!(core.stdout, "{: <20}0x{:x}", "ImageBase", image_base).unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "Entry",
+        va(pe, pe.entry as u64)
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "SectionAlignment", win.section_alignment
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "FileAlignment", win.file_alignment
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "SizeOfImage", win.size_of_image
+    )
+    .unwrap();
+    writeln!(
+        core.stdout,
+        "{: <20}0x{:x}",
+        "SizeOfHeaders", win.size_of_headers
+    )
+    .unwrap();
+    writeln!(core.stdout, "{: <20}0x{:x}", "Subsystem", win.subsystem).unwrap();
+    print_header(
+        core,
+        &format!("{: <24}{: <20}{}", "DataDirectory", "VirtAddr", "Size"),
+    );
+    for (typ, dir) in opt.data_directories.dirs() {
+        writeln!(
+            core.stdout,
+            "{: <24}{: <20}0x{:x}",
+            format!("{typ:?}"),
+            format!("0x{:x}", va(pe, u64::from(dir.virtual_address))),
+            dir.size
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_sections(core: &mut Core, pe: &PE) {
+    if is_json(core) {
+        let sections = pe
+            .sections
+            .iter()
+            .map(|section| {
+                json!({
+                    "address": va(pe, u64::from(section.virtual_address)),
+                    "offset": section.pointer_to_raw_data,
+                    "virt_size": section.virtual_size,
+                    "raw_size": section.size_of_raw_data,
+                    "perms": perms(section.characteristics),
+                    "name": section_name(section),
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(sections));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            "Address", "Offset", "VirtSize", "RawSize", "Perms", "Name"
+        ),
+    );
+    for section in &pe.sections {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", va(pe, u64::from(section.virtual_address))),
+            format!("0x{:x}", section.pointer_to_raw_data),
+            format!("0x{:x}", section.virtual_size),
+            format!("0x{:x}", section.size_of_raw_data),
+            perms(section.characteristics),
+            section_name(section)
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_imports(core: &mut Core, pe: &PE) {
+    if is_json(core) {
+        let imports = pe
+            .imports
+            .iter()
+            .map(|import| {
+                json!({
+                    "address": va(pe, import.offset as u64),
+                    "ordinal": import.ordinal,
+                    "library": import.dll,
+                    "name": import.name,
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(imports));
+    }
+    print_header(
+        core,
+        &format!(
+            "{: <20}{: <20}{: <20}{}",
+            "Address", "Ordinal", "Library", "Name"
+        ),
+    );
+    for import in &pe.imports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{: <20}{}",
+            format!("0x{:x}", va(pe, import.offset as u64)),
+            import.ordinal,
+            import.dll,
+            import.name
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_exports(core: &mut Core, pe: &PE) {
+    if is_json(core) {
+        let exports = pe
+            .exports
+            .iter()
+            .map(|export| {
+                json!({
+                    "address": va(pe, export.rva as u64),
+                    "size": export.size,
+                    "name": export.name.unwrap_or(""),
+                })
+            })
+            .collect();
+        return print_json(core, &Value::Array(exports));
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{}", "Address", "Size", "Name"),
+    );
+    for export in &pe.exports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}",
+            format!("0x{:x}", va(pe, export.rva as u64)),
+            format!("0x{:x}", export.size),
+            export.name.unwrap_or("")
+        )
+        .unwrap();
+    }
+}
+
+pub fn print_symbols(core: &mut Core, pe: &PE) {
+    if is_json(core) {
+        let exports = pe.exports.iter().map(|export| {
+            json!({
+                "address": va(pe, export.rva as u64),
+                "type": "export",
+                "name": export.name.unwrap_or(""),
+            })
+        });
+        let imports = pe.imports.iter().map(|import| {
+            json!({
+                "address": va(pe, import.offset as u64),
+                "type": "import",
+                "name": format!("{}.{}", import.dll, import.name),
+            })
+        });
+        return print_json(core, &Value::Array(exports.chain(imports).collect()));
+    }
+    print_header(
+        core,
+        &format!("{: <20}{: <20}{}", "Address", "Type", "Name"),
+    );
+    for export in &pe.exports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}",
+            format!("0x{:x}", va(pe, export.rva as u64)),
+            "export",
+            export.name.unwrap_or("")
+        )
+        .unwrap();
+    }
+    for import in &pe.imports {
+        writeln!(
+            core.stdout,
+            "{: <20}{: <20}{}.{}",
+            format!("0x{:x}", va(pe, import.offset as u64)),
+            "import",
+            import.dll,
+            import.name
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,1398 @@
+use super::cmds::{Auxv, Exports, Imports, Info, Mappings, Relocs, Sections, Symbols, Threads};
+use crate::core::Core;
+use crate::helper::AddrMode;
+use crate::writer::Writer;
+use crate::{Cmd, CmdOps};
+use core::mem;
+use serde_json::json;
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+use test_file::*;
+
+/// Minimal executable writer for tests, fields are written at current
+/// offset in the requested class and byte order.
+struct Builder {
+    data: Vec<u8>,
+    off: usize,
+    is64: bool,
+    le: bool,
+}
+
+impl Builder {
+    fn seek(&mut self, off: usize) -> &mut Self {
+        self.off = off;
+        self
+    }
+    fn put(&mut self, be_bytes: &[u8]) -> &mut Self {
+        let mut bytes = be_bytes.to_vec();
+        if self.le {
+            bytes.reverse();
+        }
+        let end = self.off + bytes.len();
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        self.data[self.off..end].copy_from_slice(&bytes);
+        self.off = end;
+        self
+    }
+    fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        let le = self.le;
+        self.le = false;
+        self.put(bytes);
+        self.le = le;
+        self
+    }
+    fn u8(&mut self, v: u8) -> &mut Self {
+        self.raw(&[v])
+    }
+    fn u16(&mut self, v: u16) -> &mut Self {
+        self.put(&v.to_be_bytes())
+    }
+    fn u32(&mut self, v: u32) -> &mut Self {
+        self.put(&v.to_be_bytes())
+    }
+    fn u64(&mut self, v: u64) -> &mut Self {
+        self.put(&v.to_be_bytes())
+    }
+    /// Address sized word.
+    fn word(&mut self, v: u64) -> &mut Self {
+        if self.is64 {
+            self.u64(v)
+        } else {
+            self.u32(v as u32)
+        }
+    }
+    fn shdr(&mut self, hdr: [u64; 10]) -> &mut Self {
+        let [name, typ, flags, addr, offset, size, link, info, align, entsize] = hdr;
+        self.u32(name as u32).u32(typ as u32).word(flags).word(addr);
+        self.word(offset)
+            .word(size)
+            .u32(link as u32)
+            .u32(info as u32);
+        self.word(align).word(entsize)
+    }
+    fn sym(&mut self, name: u32, info: u8, shndx: u16, value: u64, size: u64) -> &mut Self {
+        if self.is64 {
+            self.u32(name)
+                .u8(info)
+                .u8(0)
+                .u16(shndx)
+                .u64(value)
+                .u64(size)
+        } else {
+            self.u32(name).u32(value as u32).u32(size as u32);
+            self.u8(info).u8(0).u16(shndx)
+        }
+    }
+
+    /// Executable with one `PT_LOAD` segment backing `.text` and 0x20 bytes
+    /// of bss, a symbol table and one relocation.
+    fn elf(is64: bool, le: bool) -> Vec<u8> {
+        let mut elf = Builder {
+            data: Vec::new(),
+            off: 0,
+            is64,
+            le,
+        };
+        let (ehsize, phsize, shsize, symsize, relasize): (u16, u16, u16, u16, u16) = if is64 {
+            (64, 56, 64, 24, 24)
+        } else {
+            (52, 32, 40, 16, 12)
+        };
+        let machine = if is64 { 62 } else { 3 };
+        elf.raw(b"\x7fELF")
+            .u8(if is64 { 2 } else { 1 })
+            .u8(if le { 1 } else { 2 })
+            .u8(1)
+            .seek(16);
+        elf.u16(2)
+            .u16(machine)
+            .u32(1)
+            .word(0x40_0100)
+            .word(ehsize.into());
+        elf.word(0x200).u32(0).u16(ehsize).u16(phsize);
+        elf.u16(1).u16(shsize).u16(6).u16(5);
+        // PT_LOAD, r-x
+        if is64 {
+            elf.u32(1).u32(5).u64(0x100).u64(0x40_0100).u64(0x40_0100);
+            elf.u64(0x10).u64(0x30).u64(0x1000);
+        } else {
+            elf.u32(1).u32(0x100).u32(0x40_0100).u32(0x40_0100);
+            elf.u32(0x10).u32(0x30).u32(5).u32(0x1000);
+        }
+        elf.seek(0x100).raw(&[0x90; 0x10]);
+        elf.seek(0x110).raw(b"\0_start\0data_end\0");
+        elf.seek(0x130)
+            .raw(b"\0.text\0.symtab\0.strtab\0.rela.text\0.shstrtab\0");
+        elf.seek(0x160).sym(0, 0, 0, 0, 0);
+        elf.sym(8, 0x01, 1, 0x40_0110, 0x20);
+        elf.sym(1, 0x12, 1, 0x40_0100, 0x10);
+        let rela = 0x160 + 3 * usize::from(symsize);
+        if is64 {
+            elf.seek(rela)
+                .u64(0x40_0104)
+                .u64((2 << 32) | 1)
+                .u64((-8i64).cast_unsigned());
+        } else {
+            elf.seek(rela)
+                .u32(0x40_0104)
+                .u32((2 << 8) | 1)
+                .u32((-8i32).cast_unsigned());
+        }
+        let ss = u64::from(symsize);
+        let rs = u64::from(relasize);
+        elf.seek(0x200).shdr([0; 10]);
+        elf.shdr([1, 1, 6, 0x40_0100, 0x100, 0x10, 0, 0, 16, 0]);
+        elf.shdr([7, 2, 0, 0, 0x160, 3 * ss, 3, 2, 8, ss]);
+        elf.shdr([15, 3, 0, 0, 0x110, 17, 0, 0, 1, 0]);
+        elf.shdr([23, 4, 0, 0, rela as u64, rs, 2, 1, 8, rs]);
+        elf.shdr([34, 3, 0, 0, 0x130, 44, 0, 0, 1, 0]);
+        elf.data
+    }
+
+    /// Executable with `.text` and `.data` sections, the later holding
+    /// 0x100 bytes of uninitialized data, an export and an import.
+    fn pe(is64: bool) -> Vec<u8> {
+        let mut pe = Builder {
+            data: Vec::new(),
+            off: 0,
+            is64,
+            le: true,
+        };
+        let (machine, opt_size, magic, image_base) = if is64 {
+            (0x8664, 240, 0x20b, 0x1_4000_0000)
+        } else {
+            (0x14c, 224, 0x10b, 0x40_0000)
+        };
+        pe.raw(b"MZ").seek(0x3c).u32(0x80);
+        pe.seek(0x80).raw(b"PE\0\0");
+        pe.u16(machine)
+            .u16(2)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .u16(opt_size)
+            .u16(0x22);
+        pe.u16(magic).u16(0).u32(0x200).u32(0x200).u32(0);
+        pe.u32(0x1000).u32(0x1000);
+        if !is64 {
+            pe.u32(0x2000);
+        }
+        pe.word(image_base).u32(0x1000).u32(0x200);
+        pe.u16(6).u16(0).u16(0).u16(0).u16(6).u16(0).u32(0);
+        pe.u32(0x3000).u32(0x200).u32(0).u16(3).u16(0);
+        pe.word(0x10_0000).word(0x1000).word(0x10_0000).word(0x1000);
+        pe.u32(0).u32(16);
+        // export and import directories, then IAT at index 12
+        pe.u32(0x2000).u32(0x40).u32(0x2040).u32(0x28);
+        let iat = pe.off + 10 * 8;
+        pe.seek(iat).u32(0x20a0).u32(0x10).seek(iat + 4 * 8);
+        pe.raw(b".text\0\0\0")
+            .u32(0x10)
+            .u32(0x1000)
+            .u32(0x200)
+            .u32(0x200);
+        pe.u32(0).u32(0).u16(0).u16(0).u32(0x6000_0020);
+        pe.raw(b".data\0\0\0")
+            .u32(0x300)
+            .u32(0x2000)
+            .u32(0x200)
+            .u32(0x400);
+        pe.u32(0).u32(0).u16(0).u16(0).u32(0xc000_0040);
+        pe.seek(0x200).raw(&[0xc3; 0x10]);
+        pe.seek(0x400).u32(0).u32(0).u32(0).u32(0x2100).u32(1);
+        pe.u32(1).u32(1).u32(0x2028).u32(0x202c).u32(0x2030);
+        pe.u32(0x1000).u32(0x2110).u16(0);
+        pe.seek(0x440)
+            .u32(0x2080)
+            .u32(0)
+            .u32(0)
+            .u32(0x2120)
+            .u32(0x20a0);
+        pe.seek(0x480).word(0x2130).word(0);
+        pe.seek(0x4a0).word(0x2130).word(0);
+        pe.seek(0x500).raw(b"test.dll\0");
+        pe.seek(0x510).raw(b"start\0");
+        pe.seek(0x520).raw(b"KERNEL32.dll\0");
+        pe.seek(0x530).u16(0).raw(b"ExitProcess\0");
+        pe.seek(0x5ff).u8(0);
+        pe.data
+    }
+
+    /// Executable with `__PAGEZERO` and `__TEXT` segments, the later holding
+    /// `__text` section, a symbol table, an import bound through dyld info
+    /// and an exported `_main` as entry point.
+    fn macho(is64: bool) -> Vec<u8> {
+        let mut macho = Builder {
+            data: Vec::new(),
+            off: 0,
+            is64,
+            le: true,
+        };
+        let (magic, cputype, seg_cmd, seg_size, sect_size) = if is64 {
+            (0xfeed_facf, 0x0100_0007, 0x19, 72, 80)
+        } else {
+            (0xfeed_face, 7, 0x1, 56, 68)
+        };
+        let entry_size = if is64 { 24 } else { 80 };
+        let cmds_size = 2 * seg_size + sect_size + 24 + entry_size + 56 + 48;
+        macho.u32(magic).u32(cputype).u32(3).u32(2);
+        macho.u32(6).u32(cmds_size).u32(0x85);
+        if is64 {
+            macho.u32(0);
+        }
+        macho
+            .u32(seg_cmd)
+            .u32(seg_size)
+            .raw(b"__PAGEZERO\0\0\0\0\0\0");
+        macho.word(0).word(0x1000).word(0).word(0);
+        macho.u32(0).u32(0).u32(0).u32(0);
+        macho
+            .u32(seg_cmd)
+            .u32(seg_size + sect_size)
+            .raw(b"__TEXT\0\0\0\0\0\0\0\0\0\0");
+        macho.word(0x1000).word(0x1000).word(0).word(0x400);
+        macho.u32(5).u32(5).u32(1).u32(0);
+        macho.raw(b"__text\0\0\0\0\0\0\0\0\0\0");
+        macho.raw(b"__TEXT\0\0\0\0\0\0\0\0\0\0");
+        macho.word(0x1300).word(0x10).u32(0x300).u32(4);
+        macho.u32(0).u32(0).u32(0x8000_0400).u32(0).u32(0);
+        if is64 {
+            macho.u32(0);
+        }
+        // LC_SYMTAB
+        macho.u32(2).u32(24).u32(0x200).u32(2).u32(0x240).u32(15);
+        if is64 {
+            // LC_MAIN
+            macho.u32(0x8000_0028).u32(24).u64(0x300).u64(0);
+        } else {
+            // LC_UNIXTHREAD with eip as the 11th register
+            macho.u32(5).u32(80).u32(1).u32(16);
+            let thread = macho.off;
+            macho.seek(thread + 10 * 4).u32(0x1300).seek(thread + 64);
+        }
+        // LC_LOAD_DYLIB
+        macho.u32(0xc).u32(56).u32(24).u32(0).u32(0).u32(0);
+        let lib = macho.off;
+        macho.raw(b"/usr/lib/libSystem.B.dylib\0").seek(lib + 32);
+        // LC_DYLD_INFO_ONLY with bind info and export trie
+        macho.u32(0x8000_0022).u32(48).u32(0).u32(0);
+        macho.u32(0x260).u32(0x10).u32(0).u32(0).u32(0).u32(0);
+        macho.u32(0x280).u32(14);
+        macho.seek(0x200);
+        for (strx, typ, sect, value) in [(1, 0x0f, 1, 0x1300), (7, 0x01, 0, 0)] {
+            macho.u32(strx).u8(typ).u8(sect).u16(0).word(value);
+        }
+        macho.seek(0x240).raw(b"\0_main\0_printf\0");
+        macho
+            .seek(0x260)
+            .raw(b"\x11\x40_printf\0\x51\x71\x88\x06\x90\x00");
+        macho
+            .seek(0x280)
+            .raw(b"\x00\x01_main\0\x09\x03\x00\x80\x06\x00");
+        macho.seek(0x300).raw(&[0xc3; 0x10]);
+        macho.seek(0x3ff).u8(0);
+        macho.data
+    }
+
+    /// Fat binary holding `macho(false)` then `macho(true)`.
+    fn fat() -> Vec<u8> {
+        let mut fat = Builder {
+            data: Vec::new(),
+            off: 0,
+            is64: false,
+            le: false,
+        };
+        fat.u32(0xcafe_babe).u32(2);
+        fat.u32(7).u32(3).u32(0x1000).u32(0x400).u32(12);
+        fat.u32(0x0100_0007).u32(3).u32(0x2000).u32(0x400).u32(12);
+        fat.seek(0x1000).raw(&Self::macho(false));
+        fat.seek(0x2000).raw(&Self::macho(true));
+        fat.data
+    }
+
+    fn phdr(&mut self, typ: u32, flags: u32, offset: u64, vaddr: u64, size: [u64; 2]) -> &mut Self {
+        let [file_size, mem_size] = size;
+        if self.is64 {
+            self.u32(typ).u32(flags).u64(offset).u64(vaddr).u64(vaddr);
+            self.u64(file_size).u64(mem_size).u64(4)
+        } else {
+            self.u32(typ)
+                .u32(offset as u32)
+                .u32(vaddr as u32)
+                .u32(vaddr as u32);
+            self.u32(file_size as u32)
+                .u32(mem_size as u32)
+                .u32(flags)
+                .u32(4)
+        }
+    }
+    /// Note header owned by `CORE`, returns offset of its descriptor.
+    fn note(&mut self, typ: u32, size: usize) -> usize {
+        self.u32(5).u32(size as u32).u32(typ).raw(b"CORE\0\0\0\0");
+        self.off
+    }
+    /// `NT_PRSTATUS` of thread `pid` stopped by `signal` at `pc`.
+    fn prstatus(&mut self, pid: u32, signal: u16, pc: u64) -> &mut Self {
+        let (size, pid_off, regs_off, pc_index) = if self.is64 {
+            (336, 32, 112, 16)
+        } else {
+            (144, 24, 72, 12)
+        };
+        let desc = self.note(1, size);
+        let word = if self.is64 { 8 } else { 4 };
+        self.seek(desc + 12).u16(signal);
+        self.seek(desc + pid_off).u32(pid);
+        self.seek(desc + regs_off + pc_index * word).word(pc);
+        self.seek(desc + size)
+    }
+
+    /// Core dump of a process that crashed at 0x400008 with two threads, two
+    /// file backed mappings and an auxiliary vector. Only 0x10 bytes of its
+    /// text segment and 0x20 bytes of its stack are dumped.
+    fn elf_core(is64: bool) -> Vec<u8> {
+        let mut elf = Builder {
+            data: Vec::new(),
+            off: 0,
+            is64,
+            le: true,
+        };
+        let (ehsize, phsize, machine) = if is64 { (64, 56, 62) } else { (52, 32, 3) };
+        elf.raw(b"\x7fELF")
+            .u8(if is64 { 2 } else { 1 })
+            .u8(1)
+            .u8(1)
+            .seek(16);
+        elf.u16(4).u16(machine).u32(1).word(0).word(ehsize.into());
+        elf.word(0).u32(0).u16(ehsize).u16(phsize);
+        elf.u16(3).u16(0).u16(0).u16(0);
+        elf.phdr(4, 0, 0x100, 0, [0x400, 0]);
+        elf.phdr(1, 5, 0x1000, 0x40_0000, [0x10, 0x1000]);
+        elf.phdr(1, 6, 0x1010, 0x7ffe_0000, [0x20, 0x20]);
+        elf.seek(0x100).prstatus(0x1234, 11, 0x40_0008);
+        elf.prstatus(0x1235, 0, 0x7f00_0010);
+        let names = b"/bin/crash\0/lib/libc.so.6\0\0\0";
+        let word = if is64 { 8 } else { 4 };
+        let desc = elf.note(0x4649_4c45, 8 * word + names.len());
+        elf.seek(desc).word(2).word(0x1000);
+        elf.word(0x40_0000).word(0x40_1000).word(0);
+        elf.word(0x7f00_0000).word(0x7f00_2000).word(1);
+        elf.raw(names);
+        let desc = elf.note(6, 6 * word);
+        elf.seek(desc).word(6).word(0x1000).word(9).word(0x40_0000);
+        elf.word(0).word(0);
+        let notes_end = elf.off as u64;
+        elf.seek(ehsize.into())
+            .phdr(4, 0, 0x100, 0, [notes_end - 0x100, 0]);
+        elf.seek(0x1000).raw(&[0xcc; 0x10]).raw(&[0x41; 0x20]);
+        elf.data
+    }
+}
+
+fn run(core: &mut Core, cmd: &mut dyn Cmd) -> String {
+    core.stdout = Writer::new_buf();
+    cmd.run(core, &[]);
+    mem::take(&mut core.stdout).utf8_string().unwrap()
+}
+
+/// Parsed JSON output of `command`.
+fn run_json(core: &mut Core, command: &str) -> serde_json::Value {
+    core.stdout = Writer::new_buf();
+    core.run(command, &[]);
+    serde_json::from_str(&mem::take(&mut core.stdout).utf8_string().unwrap()).unwrap()
+}
+
+/// Virtual address, size, permissions and name of every map.
+fn maps(core: &Core) -> Vec<(u64, u64, String, String)> {
+    core.io
+        .map_iter()
+        .map(|map| (map.vaddr, map.size, map.perm.to_string(), map.name.clone()))
+        .collect()
+}
+
+fn test_load_elf_cb(path: &Path, is64: bool, le: bool) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    assert_eq!(core.mode, AddrMode::Vir);
+    assert_eq!(core.get_loc(), 0x40_0100);
+    let mut text = [0; 0x30];
+    core.read(0x40_0100, &mut text).unwrap();
+    assert_eq!(text[..0x10], [0x90; 0x10]);
+    assert_eq!(text[0x10..], [0; 0x20]);
+    assert_eq!(core.flags.get("entry0").unwrap().addr, 0x40_0100);
+    assert_eq!(core.flags.get("section.text").unwrap().size, 0x10);
+    assert_eq!(core.flags.get("sym._start").unwrap().space, "symbols");
+    assert_eq!(core.flags.get("sym.data_end").unwrap().addr, 0x40_0110);
+    assert_eq!(
+        maps(&core),
+        [
+            (0x40_0100, 0x10, "r-x".to_owned(), "LOAD0".to_owned()),
+            (0x40_0110, 0x20, "r-x".to_owned(), "LOAD0".to_owned())
+        ]
+    );
+
+    let out = run(&mut core, &mut Info);
+    let (format, machine) = if is64 {
+        ("ELF64", "X86_64")
+    } else {
+        ("ELF32", "386")
+    };
+    assert_eq!(
+        out,
+        format!(
+            "Format              {format}\n\
+             Endianness          {}\n\
+             Type                EXEC\n\
+             Machine             {machine}\n\
+             Entry               0x400100\n\
+             Type                Offset              VirtAddr            FileSize            MemSize             Perms\n\
+             LOAD                0x100               0x400100            0x10                0x30                r-x\n",
+            if le {
+                "little"
+            } else {
+                "big"
+            }
+        )
+    );
+    let out = run(&mut core, &mut Symbols);
+    assert_eq!(
+        out,
+        "Address             Size                Type                Bind                Name\n\
+         0x400110            0x20                OBJECT              LOCAL               data_end\n\
+         0x400100            0x10                FUNC                GLOBAL              _start\n"
+    );
+    let out = run(&mut core, &mut Relocs);
+    let reloc = if is64 { "X86_64_64" } else { "386_32" };
+    assert_eq!(
+        out,
+        format!(
+            "Offset              Type                Addend              Symbol\n\
+             0x400104            {reloc: <20}-0x8                _start\n"
+        )
+    );
+    let info = run_json(&mut core, "infoj");
+    assert_eq!(
+        info,
+        json!({
+            "format": format,
+            "endianness": if le { "little" } else { "big" },
+            "type": "EXEC",
+            "machine": machine,
+            "entry": 0x40_0100u64,
+            "segments": [{
+                "type": "LOAD",
+                "offset": 0x100u64,
+                "vaddr": 0x40_0100u64,
+                "file_size": 0x10u64,
+                "mem_size": 0x30u64,
+                "perms": "r-x"
+            }]
+        })
+    );
+    assert_eq!(
+        run_json(&mut core, "symbolsj"),
+        json!([
+            {"address": 0x40_0110u64, "size": 0x20u64, "type": "OBJECT", "bind": "LOCAL", "name": "data_end"},
+            {"address": 0x40_0100u64, "size": 0x10u64, "type": "FUNC", "bind": "GLOBAL", "name": "_start"}
+        ])
+    );
+    assert_eq!(
+        run_json(&mut core, "relocsj"),
+        json!([{"offset": 0x40_0104u64, "type": reloc, "addend": -8i64, "symbol": "_start"}])
+    );
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_loader_docs() {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    Info.help(&mut core);
+    Sections.help(&mut core);
+    Symbols.help(&mut core);
+    Relocs.help(&mut core);
+    Imports.help(&mut core);
+    Exports.help(&mut core);
+    Threads.help(&mut core);
+    Mappings.help(&mut core);
+    Auxv.help(&mut core);
+    assert_eq!(
+        core.stdout.utf8_string().unwrap(),
+        "Command: [info]\n\
+         Usage:\n\
+         info\tPrint headers of the binary at current location.\n\
+         Command: [sections]\n\
+         Usage:\n\
+         sections\tList sections of the binary at current location.\n\
+         Command: [symbols]\n\
+         Usage:\n\
+         symbols\tList symbols of the binary at current location.\n\
+         Command: [relocs]\n\
+         Usage:\n\
+         relocs\tList relocations of the binary at current location.\n\
+         Command: [imports]\n\
+         Usage:\n\
+         imports\tList imports of the binary at current location.\n\
+         Command: [exports]\n\
+         Usage:\n\
+         exports\tList exports of the binary at current location.\n\
+         Command: [threads]\n\
+         Usage:\n\
+         threads\tList threads of the core dump at current location with their registers.\n\
+         Command: [mappings]\n\
+         Usage:\n\
+         mappings\tList file backed mappings of the core dump at current location.\n\
+         Command: [auxv]\n\
+         Usage:\n\
+         auxv\tList auxiliary vector of the core dump at current location.\n"
+    );
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_load_elf64() {
+    operate_on_file(
+        &|path| test_load_elf_cb(path, true, true),
+        &Builder::elf(true, true),
+    );
+    operate_on_file(
+        &|path| test_load_elf_cb(path, true, false),
+        &Builder::elf(true, false),
+    );
+}
+
+#[test]
+fn test_load_elf32() {
+    operate_on_file(
+        &|path| test_load_elf_cb(path, false, true),
+        &Builder::elf(false, true),
+    );
+    operate_on_file(
+        &|path| test_load_elf_cb(path, false, false),
+        &Builder::elf(false, false),
+    );
+}
+
+#[test]
+fn test_sections() {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    let elf = Builder::elf(true, true);
+    let hndl = core
+        .io
+        .open(
+            &format!("malloc://0x{:x}", elf.len()),
+            rair_io::IoMode::READ | rair_io::IoMode::WRITE,
+        )
+        .unwrap();
+    core.io.pwrite(0, &elf).unwrap();
+    super::load_binary(&mut core, hndl).unwrap();
+    let out = run(&mut core, &mut Sections);
+    assert_eq!(
+        out,
+        "Address             Offset              Size                Type                Perms               Name\n\
+         0x0                 0x0                 0x0                 NULL                ---                 \n\
+         0x400100            0x100               0x10                PROGBITS            r-x                 .text\n\
+         0x0                 0x160               0x48                SYMTAB              ---                 .symtab\n\
+         0x0                 0x110               0x11                STRTAB              ---                 .strtab\n\
+         0x0                 0x1a8               0x18                RELA                ---                 .rela.text\n\
+         0x0                 0x130               0x2c                STRTAB              ---                 .shstrtab\n"
+    );
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_loader_errors() {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    Info.run(&mut core, &[]);
+    core.io
+        .open(
+            "malloc://0x100",
+            rair_io::IoMode::READ | rair_io::IoMode::WRITE,
+        )
+        .unwrap();
+    Symbols.run(&mut core, &[]);
+    Sections.run(&mut core, &["foo".to_owned()]);
+    core.io.close_all();
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert_eq!(core.mode, AddrMode::Phy);
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\nMalformed entity: Too small\n"
+            );
+        },
+        b"\x7fELF\x02\x01\x01",
+    );
+    assert_eq!(core.stdout.utf8_string().unwrap(), "");
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Failed to parse binary\nNo file is opened at current location.\n\
+         Error: Failed to parse binary\nUnknown binary format.\n\
+         Arguments Error: Expected 0 argument(s), found 1.\n"
+    );
+}
+
+#[test]
+fn test_segment_overflow() {
+    let mut elf = Builder {
+        data: Builder::elf(true, true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    elf.seek(0x38).u16(2);
+    elf.seek(64 + 56)
+        .phdr(1, 6, 0x100, 0xffff_ffff_ffff_f000, [0x10, 0x2000]);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert!(maps(&core).is_empty());
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\n\
+                 Segment LOAD1 at 0xfffffffffffff000 exceeds virtual address space.\n"
+            );
+        },
+        &elf.data,
+    );
+}
+
+#[test]
+fn test_load_large_file() {
+    // sparse file that would take 8GiB of memory if it were read at once.
+    let file = tempfile::NamedTempFile::new().unwrap();
+    file.as_file().set_len(0x2_0000_0000).unwrap();
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[file.path().to_string_lossy().to_string()]);
+    Info.run(&mut core, &[]);
+    assert_eq!(core.mode, AddrMode::Phy);
+    assert_eq!(core.stdout.utf8_string().unwrap(), "");
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Failed to parse binary\nUnknown binary format.\n"
+    );
+}
+
+#[test]
+fn test_failed_load() {
+    // second PT_LOAD comes after the first one is mapped along with its bss.
+    let mut elf = Builder {
+        data: Builder::elf(true, true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    elf.seek(0x38).u16(2);
+    elf.seek(64 + 56)
+        .phdr(1, 6, 0x1000, 0x60_0000, [0x10, 0x10]);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert_eq!(core.mode, AddrMode::Phy);
+            assert!(maps(&core).is_empty());
+            assert_eq!(core.io.uri_iter().count(), 1);
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\nSegment at 0x600000 is out of file bounds.\n"
+            );
+        },
+        &elf.data,
+    );
+}
+
+fn test_load_pe_cb(path: &Path, is64: bool) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    let base = if is64 { 0x1_4000_0000 } else { 0x40_0000 };
+    assert_eq!(core.mode, AddrMode::Vir);
+    assert_eq!(core.get_loc(), base + 0x1000);
+    let mut mz = [0; 2];
+    core.read(base, &mut mz).unwrap();
+    assert_eq!(&mz, b"MZ");
+    let mut text = [0; 0x10];
+    core.read(base + 0x1000, &mut text).unwrap();
+    assert_eq!(text, [0xc3; 0x10]);
+    let mut data = [0xff; 0x300];
+    core.read(base + 0x2000, &mut data).unwrap();
+    assert_eq!(&data[0x110..0x116], b"start\0");
+    assert_eq!(data[0x200..], [0; 0x100]);
+    let flag = core.flags.get("imp.KERNEL32.dll.ExitProcess").unwrap();
+    assert_eq!((flag.addr, flag.space.as_str()), (base + 0x20a0, "imports"));
+    let flag = core.flags.get("exp.start").unwrap();
+    assert_eq!((flag.addr, flag.space.as_str()), (base + 0x1000, "exports"));
+    assert_eq!(core.flags.get("section.data").unwrap().size, 0x300);
+    assert_eq!(core.flags.get("entry0").unwrap().addr, base + 0x1000);
+    assert_eq!(
+        maps(&core),
+        [
+            (base, 0x200, "r--".to_owned(), "headers".to_owned()),
+            (base + 0x1000, 0x10, "r-x".to_owned(), ".text".to_owned()),
+            (base + 0x2000, 0x200, "rw-".to_owned(), ".data".to_owned()),
+            (base + 0x2200, 0x100, "rw-".to_owned(), ".data".to_owned())
+        ]
+    );
+
+    let out = run(&mut core, &mut Imports);
+    assert_eq!(
+        out,
+        format!(
+            "Address             Ordinal             Library             Name\n\
+             0x{:<18x}0                   KERNEL32.dll        ExitProcess\n",
+            base + 0x20a0
+        )
+    );
+    let out = run(&mut core, &mut Exports);
+    assert_eq!(
+        out,
+        format!(
+            "Address             Size                Name\n\
+             0x{:<18x}0x0                 start\n",
+            base + 0x1000
+        )
+    );
+    let out = run(&mut core, &mut Symbols);
+    assert_eq!(
+        out,
+        format!(
+            "Address             Type                Name\n\
+             0x{:<18x}export              start\n\
+             0x{:<18x}import              KERNEL32.dll.ExitProcess\n",
+            base + 0x1000,
+            base + 0x20a0
+        )
+    );
+    let out = run(&mut core, &mut Sections);
+    assert_eq!(
+        out,
+        format!(
+            "Address             Offset              VirtSize            RawSize             Perms               Name\n\
+             0x{:<18x}0x200               0x10                0x200               r-x                 .text\n\
+             0x{:<18x}0x400               0x300               0x200               rw-                 .data\n",
+            base + 0x1000,
+            base + 0x2000
+        )
+    );
+    assert_eq!(
+        run_json(&mut core, "importsj"),
+        json!([{"address": base + 0x20a0u64, "ordinal": 0u64, "library": "KERNEL32.dll", "name": "ExitProcess"}])
+    );
+    assert_eq!(
+        run_json(&mut core, "exportsj"),
+        json!([{"address": base + 0x1000u64, "size": 0u64, "name": "start"}])
+    );
+    assert_eq!(
+        run_json(&mut core, "symbolsj"),
+        json!([
+            {"address": base + 0x1000u64, "type": "export", "name": "start"},
+            {"address": base + 0x20a0u64, "type": "import", "name": "KERNEL32.dll.ExitProcess"}
+        ])
+    );
+    assert_eq!(
+        run_json(&mut core, "sectionsj"),
+        json!([
+            {"address": base + 0x1000u64, "offset": 0x200u64, "virt_size": 0x10u64, "raw_size": 0x200u64, "perms": "r-x", "name": ".text"},
+            {"address": base + 0x2000u64, "offset": 0x400u64, "virt_size": 0x300u64, "raw_size": 0x200u64, "perms": "rw-", "name": ".data"}
+        ])
+    );
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_load_pe() {
+    operate_on_file(&|path| test_load_pe_cb(path, true), &Builder::pe(true));
+    operate_on_file(&|path| test_load_pe_cb(path, false), &Builder::pe(false));
+}
+
+#[test]
+fn test_pe_info() {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    let pe = Builder::pe(true);
+    let hndl = core
+        .io
+        .open(
+            &format!("malloc://0x{:x}", pe.len()),
+            rair_io::IoMode::READ | rair_io::IoMode::WRITE,
+        )
+        .unwrap();
+    core.io.pwrite(0, &pe).unwrap();
+    super::load_binary(&mut core, hndl).unwrap();
+    let out = run(&mut core, &mut Info);
+    assert_eq!(
+        out,
+        "Format              PE32+\n\
+         Machine             X86_64\n\
+         NtHeaders           0x80\n\
+         TimeDateStamp       0x0\n\
+         Characteristics     0x22\n\
+         ImageBase           0x140000000\n\
+         Entry               0x140001000\n\
+         SectionAlignment    0x1000\n\
+         FileAlignment       0x200\n\
+         SizeOfImage         0x3000\n\
+         SizeOfHeaders       0x200\n\
+         Subsystem           0x3\n\
+         DataDirectory           VirtAddr            Size\n\
+         ExportTable             0x140002000         0x40\n\
+         ImportTable             0x140002040         0x28\n\
+         ImportAddressTable      0x1400020a0         0x10\n"
+    );
+    assert_eq!(
+        run_json(&mut core, "infoj"),
+        json!({
+            "format": "PE32+",
+            "machine": "X86_64",
+            "nt_headers": 0x80u64,
+            "time_date_stamp": 0u64,
+            "characteristics": 0x22u64,
+            "image_base": 0x1_4000_0000u64,
+            "entry": 0x1_4000_1000u64,
+            "section_alignment": 0x1000u64,
+            "file_alignment": 0x200u64,
+            "size_of_image": 0x3000u64,
+            "size_of_headers": 0x200u64,
+            "subsystem": 3u64,
+            "data_directories": [
+                {"type": "ExportTable", "vaddr": 0x1_4000_2000u64, "size": 0x40u64},
+                {"type": "ImportTable", "vaddr": 0x1_4000_2040u64, "size": 0x28u64},
+                {"type": "ImportAddressTable", "vaddr": 0x1_4000_20a0u64, "size": 0x10u64}
+            ]
+        })
+    );
+    Relocs.run(&mut core, &[]);
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Unsupported binary format\nCommand is not available for PE binaries.\n"
+    );
+}
+
+#[test]
+fn test_pe_image_base_overflow() {
+    let mut pe = Builder {
+        data: Builder::pe(true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    pe.seek(0xb0).u64(0xffff_ffff_ffff_f000);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert!(maps(&core).is_empty());
+            let out = run(&mut core, &mut Info);
+            assert!(
+                out.contains("ImageBase           0xfffffffffffff000\nEntry               0x0\n")
+            );
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\n\
+                 RVA 0x1000 exceeds virtual address space with image base 0xfffffffffffff000.\n"
+            );
+        },
+        &pe.data,
+    );
+}
+
+#[test]
+fn test_pe_failed_load_flags() {
+    // .text is mapped and flagged before .data overflows.
+    let mut pe = Builder {
+        data: Builder::pe(true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    pe.seek(0xb0).u64(0xffff_ffff_ffff_e000);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.flags.add("section.text", 0x10, 0x20, "*").unwrap();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert!(maps(&core).is_empty());
+            let flags: Vec<_> = core
+                .flags
+                .iter()
+                .map(|(name, flag)| (name, flag.addr))
+                .collect();
+            assert_eq!(flags, [("section.text", 0x10)]);
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\n\
+                 RVA 0x2000 exceeds virtual address space with image base 0xffffffffffffe000.\n"
+            );
+        },
+        &pe.data,
+    );
+}
+
+fn test_load_macho_cb(path: &Path, is64: bool) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    assert_eq!(core.mode, AddrMode::Vir);
+    assert_eq!(core.get_loc(), 0x1300);
+    let mut text = [0; 0x10];
+    core.read(0x1300, &mut text).unwrap();
+    assert_eq!(text, [0xc3; 0x10]);
+    assert!(core.io.vir_to_phy(0x0, 1).is_none());
+    assert_eq!(core.flags.get("entry0").unwrap().addr, 0x1300);
+    assert_eq!(core.flags.get("section.__TEXT.__text").unwrap().size, 0x10);
+    assert_eq!(core.flags.get("sym._main").unwrap().addr, 0x1300);
+    let flag = core.flags.get("imp._printf").unwrap();
+    assert_eq!((flag.addr, flag.space.as_str()), (0x1308, "imports"));
+    let flag = core.flags.get("exp._main").unwrap();
+    assert_eq!((flag.addr, flag.space.as_str()), (0x1300, "exports"));
+    assert_eq!(
+        maps(&core),
+        [
+            (0x1000, 0x400, "r-x".to_owned(), "__TEXT".to_owned()),
+            (0x1400, 0xc00, "r-x".to_owned(), "__TEXT".to_owned())
+        ]
+    );
+
+    let out = run(&mut core, &mut Info);
+    let info = if is64 {
+        "Format              Mach-O64\n\
+         Endianness          little\n\
+         Type                EXECUTE\n\
+         Machine             x86_64\n\
+         Flags               0x85\n\
+         Entry               0x1300 (LC_MAIN)\n\
+         Library             /usr/lib/libSystem.B.dylib\n\
+         Offset              Size                LoadCommand\n\
+         0x20                0x48                LC_SEGMENT_64\n\
+         0x68                0x98                LC_SEGMENT_64\n\
+         0x100               0x18                LC_SYMTAB\n\
+         0x118               0x18                LC_MAIN\n\
+         0x130               0x38                LC_LOAD_DYLIB\n\
+         0x168               0x30                LC_DYLD_INFO_ONLY\n"
+    } else {
+        "Format              Mach-O32\n\
+         Endianness          little\n\
+         Type                EXECUTE\n\
+         Machine             i386\n\
+         Flags               0x85\n\
+         Entry               0x1300 (LC_UNIXTHREAD)\n\
+         Library             /usr/lib/libSystem.B.dylib\n\
+         Offset              Size                LoadCommand\n\
+         0x1c                0x38                LC_SEGMENT\n\
+         0x54                0x7c                LC_SEGMENT\n\
+         0xd0                0x18                LC_SYMTAB\n\
+         0xe8                0x50                LC_UNIXTHREAD\n\
+         0x138               0x38                LC_LOAD_DYLIB\n\
+         0x170               0x30                LC_DYLD_INFO_ONLY\n"
+    };
+    assert_eq!(out, info);
+    let out = run(&mut core, &mut Symbols);
+    assert_eq!(
+        out,
+        "Address             Type                Bind                Name\n\
+         0x1300              SECT                GLOBAL              _main\n\
+         0x0                 UNDF                GLOBAL              _printf\n"
+    );
+    let out = run(&mut core, &mut Sections);
+    assert_eq!(
+        out,
+        "Address             Offset              Size                Perms               Name\n\
+         0x1300              0x300               0x10                r-x                 __TEXT.__text\n"
+    );
+    let out = run(&mut core, &mut Imports);
+    assert_eq!(
+        out,
+        "Address             Bind                Name                Library\n\
+         0x1308              bind                _printf             /usr/lib/libSystem.B.dylib\n"
+    );
+    let out = run(&mut core, &mut Exports);
+    assert_eq!(
+        out,
+        "Address             Size                Name\n\
+         0x1300              0x0                 _main\n"
+    );
+    Relocs.run(&mut core, &[]);
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Unsupported binary format\nCommand is not available for Mach-O binaries.\n"
+    );
+}
+
+fn test_macho_json_cb(path: &Path, is64: bool) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    let (format, entry_command, dylib) = if is64 {
+        ("Mach-O64", "LC_MAIN", 0x130u64)
+    } else {
+        ("Mach-O32", "LC_UNIXTHREAD", 0x138u64)
+    };
+    let info = run_json(&mut core, "infoj");
+    assert_eq!(info["format"], format);
+    assert_eq!(info["entry"], 0x1300u64);
+    assert_eq!(info["entry_command"], entry_command);
+    assert_eq!(info["libraries"], json!(["/usr/lib/libSystem.B.dylib"]));
+    assert_eq!(info["load_commands"].as_array().unwrap().len(), 6);
+    assert_eq!(
+        info["load_commands"][4],
+        json!({"offset": dylib, "size": 0x38u64, "command": "LC_LOAD_DYLIB"})
+    );
+    assert_eq!(
+        run_json(&mut core, "symbolsj"),
+        json!([
+            {"address": 0x1300u64, "type": "SECT", "global": true, "name": "_main"},
+            {"address": 0u64, "type": "UNDF", "global": true, "name": "_printf"}
+        ])
+    );
+    assert_eq!(
+        run_json(&mut core, "sectionsj"),
+        json!([{"address": 0x1300u64, "offset": 0x300u64, "size": 0x10u64, "perms": "r-x", "name": "__TEXT.__text"}])
+    );
+    assert_eq!(
+        run_json(&mut core, "importsj"),
+        json!([{"address": 0x1308u64, "bind": "bind", "name": "_printf", "library": "/usr/lib/libSystem.B.dylib"}])
+    );
+    assert_eq!(
+        run_json(&mut core, "exportsj"),
+        json!([{"address": 0x1300u64, "size": 0u64, "name": "_main"}])
+    );
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_load_macho() {
+    for is64 in [true, false] {
+        operate_on_file(
+            &|path| test_load_macho_cb(path, is64),
+            &Builder::macho(is64),
+        );
+        operate_on_file(
+            &|path| test_macho_json_cb(path, is64),
+            &Builder::macho(is64),
+        );
+    }
+}
+
+#[test]
+fn test_macho_malformed_text() {
+    // __TEXT vmaddr, vmsize, fileoff and filesize.
+    let mut macho = Builder {
+        data: Builder::macho(true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    macho.seek(128).u64(0xffff_ffff_ffff_f000).u64(0x1000);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert!(maps(&core).is_empty());
+            assert_eq!(
+                core.stderr.utf8_string().unwrap(),
+                "Error: Failed to load binary\n\
+                 Segment __TEXT at 0xfffffffffffff000 exceeds virtual address space.\n"
+            );
+        },
+        &macho.data,
+    );
+    // header would be loaded below address 0, goblin itself rejects that along with LC_MAIN.
+    let mut macho = Builder {
+        data: Builder::macho(false),
+        off: 0,
+        is64: false,
+        le: true,
+    };
+    macho.seek(108).u32(0x100).u32(0x1000).u32(0x200).u32(0x10);
+    operate_on_file(
+        &|path| {
+            let mut core = Core::new_no_colors();
+            core.stderr = Writer::new_buf();
+            core.stdout = Writer::new_buf();
+            core.run("open", &[path.to_string_lossy().to_string()]);
+            assert_eq!(core.flags.get("exp._main").unwrap().addr, 0x300);
+            core.mode = AddrMode::Phy;
+            core.set_loc(0);
+            let out = run(&mut core, &mut Exports);
+            assert_eq!(
+                out,
+                "Address             Size                Name\n\
+                 0x300               0x0                 _main\n"
+            );
+            assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        },
+        &macho.data,
+    );
+}
+
+fn test_load_fat_cb(path: &Path) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    assert_eq!(core.get_loc(), 0x1300);
+    // first slice is loaded by default.
+    assert_eq!(core.io.vir_to_phy(0x1300, 1).unwrap()[0].paddr, 0x1300);
+    core.mode = AddrMode::Phy;
+    core.set_loc(0);
+    let out = run(&mut core, &mut Info);
+    assert_eq!(
+        out,
+        "Format              FAT\n\
+         Index               Offset              Size                Align               Machine\n\
+         0                   0x1000              0x400               0x1000              i386\n\
+         1                   0x2000              0x400               0x1000              x86_64\n"
+    );
+    assert_eq!(
+        run_json(&mut core, "infoj"),
+        json!({
+            "format": "FAT",
+            "arches": [
+                {"offset": 0x1000u64, "size": 0x400u64, "align": 0x1000u64, "machine": "i386"},
+                {"offset": 0x2000u64, "size": 0x400u64, "align": 0x1000u64, "machine": "x86_64"}
+            ]
+        })
+    );
+    Symbols.run(&mut core, &[]);
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Unsupported binary format\nCommand is not available for FAT binaries.\n"
+    );
+
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run(
+        "open",
+        &[format!("fat://x86_64/{}", path.to_string_lossy())],
+    );
+    assert_eq!(core.get_loc(), 0x1300);
+    let out = run(&mut core, &mut Info);
+    assert!(out.starts_with("Format              Mach-O64\n"));
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_load_fat() {
+    operate_on_file(&test_load_fat_cb, &Builder::fat());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_load_proc() {
+    let mut child = Command::new("sleep").arg("60").spawn().unwrap();
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[format!("proc://{}", child.id())]);
+    let stack = core
+        .flags
+        .iter()
+        .find(|(name, _)| name.starts_with("map.stack."))
+        .map(|(_, flag)| flag.addr);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    let stderr = mem::take(&mut core.stderr);
+    assert_eq!(stderr.utf8_string().unwrap(), "");
+    core.stderr = Writer::new_buf();
+    let stack = stack.unwrap();
+    assert!(core.io.vir_to_phy(stack, 1).is_some());
+    core.mode = AddrMode::Phy;
+    core.set_loc(0);
+    Info.run(&mut core, &[]);
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Failed to parse binary\nFile is already mapped into virtual address space.\n"
+    );
+}
+
+fn test_load_elf_core_cb(path: &Path, is64: bool) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    assert_eq!(core.mode, AddrMode::Vir);
+    assert_eq!(core.get_loc(), 0x40_0008);
+    assert_eq!(core.flags.get("pc").unwrap().addr, 0x40_0008);
+    let mapping = core.flags.get("map.libc.so.6.7f000000").unwrap();
+    assert_eq!((mapping.addr, mapping.size), (0x7f00_0000, 0x2000));
+    assert!(core.flags.get("map.crash.400000").is_some());
+    core.run("maps", &[]);
+    assert_eq!(
+        mem::take(&mut core.stdout).utf8_string().unwrap(),
+        "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+         0x400000            0x1000              0x10                r-x     0         LOAD0\n\
+         0x7ffe0000          0x1010              0x20                rw-     0         LOAD1\n"
+    );
+    let mut stack = [0; 0x20];
+    core.read(0x7ffe_0000, &mut stack).unwrap();
+    assert_eq!(stack, [0x41; 0x20]);
+
+    core.mode = AddrMode::Phy;
+    core.set_loc(0);
+    let out = run(&mut core, &mut Threads);
+    let (pc, pc_index, regs) = if is64 {
+        ("rip", 16, 27)
+    } else {
+        ("eip", 12, 17)
+    };
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2 * (regs + 2) + 1);
+    assert_eq!(lines[0], "Pid                 4660");
+    assert_eq!(lines[1], "Signal              11");
+    assert_eq!(lines[2 + pc_index], format!("{pc: <20}0x400008"));
+    assert_eq!(lines[regs + 2], "");
+    assert_eq!(lines[regs + 3], "Pid                 4661");
+    assert_eq!(lines[regs + 4], "Signal              0");
+    assert_eq!(lines[regs + 5 + pc_index], format!("{pc: <20}0x7f000010"));
+    let out = run(&mut core, &mut Mappings);
+    assert_eq!(
+        out,
+        "Start               End                 Offset              Path\n\
+         0x400000            0x401000            0x0                 /bin/crash\n\
+         0x7f000000          0x7f002000          0x1000              /lib/libc.so.6\n"
+    );
+    let out = run(&mut core, &mut Auxv);
+    assert_eq!(
+        out,
+        "Type                Value\n\
+         AT_PAGESZ           0x1000\n\
+         AT_ENTRY            0x400000\n"
+    );
+    assert_eq!(
+        run_json(&mut core, "mappingsj"),
+        json!([
+            {"start": 0x40_0000u64, "end": 0x40_1000u64, "offset": 0u64, "path": "/bin/crash"},
+            {"start": 0x7f00_0000u64, "end": 0x7f00_2000u64, "offset": 0x1000u64, "path": "/lib/libc.so.6"}
+        ])
+    );
+    assert_eq!(
+        run_json(&mut core, "auxvj"),
+        json!([
+            {"type": "AT_PAGESZ", "value": 0x1000u64},
+            {"type": "AT_ENTRY", "value": 0x40_0000u64}
+        ])
+    );
+    core.stdout = Writer::new_buf();
+    core.run("threadsj", &[]);
+    let threads: serde_json::Value =
+        serde_json::from_str(&mem::take(&mut core.stdout).utf8_string().unwrap()).unwrap();
+    assert_eq!(threads[0]["pid"], 0x1234u32);
+    assert_eq!(threads[1]["registers"][pc], 0x7f00_0010u64);
+    assert_eq!(core.stderr.utf8_string().unwrap(), "");
+}
+
+#[test]
+fn test_load_elf_core() {
+    operate_on_file(
+        &|path| test_load_elf_core_cb(path, true),
+        &Builder::elf_core(true),
+    );
+    operate_on_file(
+        &|path| test_load_elf_core_cb(path, false),
+        &Builder::elf_core(false),
+    );
+}
+
+fn test_malformed_core_cb(path: &Path) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    assert_eq!(core.get_loc(), 0x40_0008);
+    assert!(core.flags.get("map.crash.400000").is_none());
+    assert_eq!(
+        mem::take(&mut core.stderr).utf8_string().unwrap(),
+        "Error: Failed to parse core dump\nNT_FILE note has out of range values.\n"
+    );
+    core.stderr = Writer::new_buf();
+    core.mode = AddrMode::Phy;
+    core.set_loc(0);
+    let out = run(&mut core, &mut Mappings);
+    assert_eq!(
+        out,
+        "Start               End                 Offset              Path\n"
+    );
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Failed to parse core dump\nNT_FILE note has out of range values.\n"
+    );
+}
+
+#[test]
+fn test_malformed_core() {
+    let mut elf = Builder {
+        data: Builder::elf_core(true),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    let mut file_note = Builder {
+        data: Vec::new(),
+        off: 0,
+        is64: true,
+        le: true,
+    };
+    file_note.word(2).word(0x1000);
+    let desc = elf.data.windows(0x10).position(|w| w == file_note.data);
+    let desc = desc.unwrap();
+    let core = elf.data.clone();
+    // too many mappings to fit in the address space.
+    elf.seek(desc).word(u64::MAX);
+    operate_on_file(&test_malformed_core_cb, &elf.data);
+    // offset of the second mapping overflows once multiplied by page size.
+    elf.data = core;
+    elf.seek(desc + 0x38).word(u64::MAX);
+    operate_on_file(&test_malformed_core_cb, &elf.data);
+}
+
+fn test_core_cmds_errors_cb(path: &Path) {
+    let mut core = Core::new_no_colors();
+    core.stderr = Writer::new_buf();
+    core.stdout = Writer::new_buf();
+    core.run("open", &[path.to_string_lossy().to_string()]);
+    core.mode = AddrMode::Phy;
+    core.set_loc(0);
+    Threads.run(&mut core, &[]);
+    Auxv.run(&mut core, &["1".to_owned()]);
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Unsupported binary format\nCommand is only available for ELF core dumps.\n\
+         Arguments Error: Expected 0 argument(s), found 1.\n"
+    );
+}
+
+#[test]
+fn test_core_cmds_errors() {
+    operate_on_file(&test_core_cmds_errors_cb, &Builder::elf(true, true));
+}
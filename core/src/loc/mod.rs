@@ -7,11 +7,19 @@ use self::history::History;
 use self::mode::Mode;
 use self::seek::Seek;
 use crate::core::Core;
+use crate::helper::print_json;
 use alloc::sync::Arc;
 use parking_lot::Mutex;
+use serde_json::json;
 
 pub fn register_loc(core: &mut Core) {
     let history = Arc::new(Mutex::new(History::default()));
     core.add_command(Mode::with_history(history.clone()));
     core.add_command(Seek::with_history(history));
 }
+
+/// Print current location and addressing mode, the JSON output of both seek and mode.
+fn print_loc_json(core: &mut Core) {
+    let value = json!({"loc": core.get_loc(), "mode": core.mode.to_string()});
+    print_json(core, &value);
+}
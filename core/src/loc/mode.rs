@@ -1,8 +1,9 @@
 //! commands handling view mode (phy/vir).
 
 use super::history::History;
+use super::print_loc_json;
 use crate::core::Core;
-use crate::helper::{error_msg, expect, AddrMode, MRc};
+use crate::helper::{error_msg, expect, is_json, AddrMode, MRc};
 use crate::Cmd;
 use yansi::Paint;
 #[derive(Default)]
@@ -46,7 +47,10 @@ impl Cmd for Mode {
                 "phy".primary().italic().bold(),
                 &args[0].primary().italic().bold(),
             );
-            error_msg(core, "Invalid Mode", &msg);
+            return error_msg(core, "Invalid Mode", &msg);
+        }
+        if is_json(core) {
+            print_loc_json(core);
         }
     }
     fn commands(&self) -> &'static [&'static str] {
@@ -136,4 +140,27 @@ mod test_mode {
             "Error: Invalid Mode\nExpected vir or phy, but found not_real_arg.\n"
         );
     }
+
+    #[test]
+    fn test_mode_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.map(0x0, 0x5000, 0x10).unwrap();
+        core.run("mj", &["vir".to_owned()]);
+        core.run("mj", &["phy".to_owned()]);
+        core.run("mj", &["not_real_arg".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"loc\":20480,\"mode\":\"Vir\"}\n\
+             {\"loc\":0,\"mode\":\"Phy\"}\n"
+        );
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Invalid Mode\nExpected vir or phy, but found not_real_arg.\n"
+        );
+    }
 }
@@ -1,8 +1,9 @@
 //! seek forward or backward in file.
 
 use super::history::History;
+use super::print_loc_json;
 use crate::core::Core;
-use crate::helper::{error_msg, expect, str_to_num, MRc};
+use crate::helper::{error_msg, expect, is_json, str_to_addr, str_to_num, MRc};
 use crate::Cmd;
 
 #[derive(Default)]
@@ -14,35 +15,41 @@ impl Seek {
     pub(super) fn with_history(history: MRc<History>) -> Self {
         Seek { history }
     }
-    fn backward(&mut self, core: &mut Core) {
-        if let Some((mode, addr)) = self.history.lock().backward(core) {
-            core.mode = mode;
-            core.set_loc(addr);
-        } else {
-            error_msg(core, "Seek Error", "History is empty.");
-        }
+    fn backward(&mut self, core: &mut Core) -> Result<(), String> {
+        let (mode, addr) = self
+            .history
+            .lock()
+            .backward(core)
+            .ok_or("History is empty.")?;
+        core.mode = mode;
+        core.set_loc(addr);
+        Ok(())
     }
-    fn forward(&mut self, core: &mut Core) {
-        if let Some((mode, addr)) = self.history.lock().forward(core) {
-            core.mode = mode;
-            core.set_loc(addr);
-        } else {
-            error_msg(core, "Seek Error", "History is empty.");
-        }
+    fn forward(&mut self, core: &mut Core) -> Result<(), String> {
+        let (mode, addr) = self
+            .history
+            .lock()
+            .forward(core)
+            .ok_or("History is empty.")?;
+        core.mode = mode;
+        core.set_loc(addr);
+        Ok(())
     }
-    fn add_loc(&mut self, core: &mut Core, offset: u64) {
-        if let Some(loc) = core.get_loc().checked_add(offset) {
-            self.set_loc(core, loc);
-        } else {
-            error_msg(core, "Seek Error", "Attempt to add with overflow.");
-        }
+    fn add_loc(&mut self, core: &mut Core, offset: u64) -> Result<(), String> {
+        let loc = core
+            .get_loc()
+            .checked_add(offset)
+            .ok_or("Attempt to add with overflow.")?;
+        self.set_loc(core, loc);
+        Ok(())
     }
-    fn sub_loc(&mut self, core: &mut Core, offset: u64) {
-        if let Some(loc) = core.get_loc().checked_sub(offset) {
-            self.set_loc(core, loc);
-        } else {
-            error_msg(core, "Seek Error", "Attempt to subtract with overflow.");
-        }
+    fn sub_loc(&mut self, core: &mut Core, offset: u64) -> Result<(), String> {
+        let loc = core
+            .get_loc()
+            .checked_sub(offset)
+            .ok_or("Attempt to subtract with overflow.")?;
+        self.set_loc(core, loc);
+        Ok(())
     }
     #[inline]
     fn set_loc(&mut self, core: &mut Core, offset: u64) {
@@ -57,25 +64,27 @@ impl Cmd for Seek {
             expect(core, args.len() as u64, 1);
             return;
         }
-        if args[0] == "-" {
-            self.backward(core);
+        let sought = if args[0] == "-" {
+            self.backward(core)
         } else if args[0] == "+" {
-            self.forward(core);
-        } else if args[0].starts_with('+') {
-            match str_to_num(&args[0][1..]) {
-                Ok(offset) => self.add_loc(core, offset),
-                Err(e) => error_msg(core, "Seek Error", &e.to_string()),
-            }
-        } else if args[0].starts_with('-') {
-            match str_to_num(&args[0][1..]) {
-                Ok(offset) => self.sub_loc(core, offset),
-                Err(e) => error_msg(core, "Seek Error", &e.to_string()),
-            }
+            self.forward(core)
+        } else if let Some(offset) = args[0].strip_prefix('+') {
+            str_to_num(offset)
+                .map_err(|e| e.to_string())
+                .and_then(|offset| self.add_loc(core, offset))
+        } else if let Some(offset) = args[0].strip_prefix('-') {
+            str_to_num(offset)
+                .map_err(|e| e.to_string())
+                .and_then(|offset| self.sub_loc(core, offset))
         } else {
-            match str_to_num(&args[0]) {
-                Ok(offset) => self.set_loc(core, offset),
-                Err(e) => error_msg(core, "Seek Error", &e.to_string()),
-            }
+            str_to_addr(&args[0], core)
+                .map(|offset| self.set_loc(core, offset))
+                .map_err(|e| e.to_string())
+        };
+        match sought {
+            Ok(()) if is_json(core) => print_loc_json(core),
+            Ok(()) => (),
+            Err(e) => error_msg(core, "Seek Error", &e),
         }
     }
 
@@ -291,4 +300,26 @@ mod test_seek {
         core.stderr = Writer::new_buf();
         core.stdout = Writer::new_buf();
     }
+
+    #[test]
+    fn test_seek_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("sj", &["0x10".to_owned()]);
+        core.run("sj", &["+4".to_owned()]);
+        core.run("sj", &["-".to_owned()]);
+        core.run("sj", &["ff".to_owned()]);
+        assert_eq!(core.get_loc(), 0x10);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"loc\":16,\"mode\":\"Phy\"}\n\
+             {\"loc\":20,\"mode\":\"Phy\"}\n\
+             {\"loc\":16,\"mode\":\"Phy\"}\n"
+        );
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Seek Error\ninvalid digit found in string\n"
+        );
+    }
 }
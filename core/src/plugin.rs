@@ -0,0 +1,182 @@
+//! Loading commands out of shared libraries.
+
+use crate::cmd::Cmd;
+use crate::core::Core;
+use crate::helper::MRc;
+use alloc::sync::Arc;
+use libloading::Library;
+use parking_lot::Mutex;
+use rair_io::{
+    load_plugin_library, plugin_files, PluginDeclaration, PluginVersions, RIO_PLUGIN_RUSTC_VERSION,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the interface between [`Core`] and command plugins, it is bumped whenever
+/// [`CmdPluginDeclaration`], [`Cmd`], [`Core`] or any type they use changes.
+pub const CMD_PLUGIN_ABI_VERSION: u32 = 3;
+
+/// Versions every command plugin must be built with.
+pub const CMD_PLUGIN_VERSIONS: PluginVersions = PluginVersions {
+    abi: CMD_PLUGIN_ABI_VERSION,
+    rustc: RIO_PLUGIN_RUSTC_VERSION,
+    crate_version: env!("CARGO_PKG_VERSION"),
+};
+
+/// Name of the symbol every command plugin exports, see [`export_cmd_plugin`].
+pub const CMD_PLUGIN_SYMBOL: &str = "rair_cmd_plugin";
+
+/// Entry point of a command plugin. Commands are handed over to [`Core`] as Rust trait objects so
+/// just like [`rair_io::RIOPluginDeclaration`] this is not a stable ABI, `versions` is checked
+/// against [`CMD_PLUGIN_VERSIONS`] before `commands` is ever called.
+///
+/// Plugins can be unloaded at any time so their commands must not leave anything behind that
+/// points into the plugin such as environment variables with callbacks.
+#[repr(C)]
+pub struct CmdPluginDeclaration {
+    /// [`CMD_PLUGIN_VERSIONS`] the plugin was built with.
+    pub versions: PluginVersions,
+    /// Name of the plugin.
+    pub name: &'static str,
+    /// Version of the plugin.
+    pub version: &'static str,
+    /// Function that creates all commands of the plugin.
+    pub commands: fn() -> Vec<Box<dyn Cmd + Sync + Send>>,
+}
+
+impl PluginDeclaration for CmdPluginDeclaration {
+    const SYMBOL: &'static str = CMD_PLUGIN_SYMBOL;
+    const VERSIONS: PluginVersions = CMD_PLUGIN_VERSIONS;
+    fn versions(&self) -> &PluginVersions {
+        &self.versions
+    }
+}
+
+/// Export commands created by function `$commands` out of a `cdylib` crate so that they can be
+/// loaded using [`Core::load_cmd_plugin`].
+///
+/// ```ignore
+/// fn commands() -> Vec<Box<dyn Cmd + Sync + Send>> {
+///     vec![Box::new(MyCommand)]
+/// }
+///
+/// rair_core::export_cmd_plugin!("my_plugin", "0.1.0", commands);
+/// ```
+#[macro_export]
+macro_rules! export_cmd_plugin {
+    ($name:expr, $version:expr, $commands:path) => {
+        #[allow(non_upper_case_globals)]
+        #[no_mangle]
+        pub static rair_cmd_plugin: $crate::CmdPluginDeclaration = $crate::CmdPluginDeclaration {
+            versions: $crate::CMD_PLUGIN_VERSIONS,
+            name: $name,
+            version: $version,
+            commands: $commands,
+        };
+    };
+}
+
+/// Command plugin loaded by [`Core::load_cmd_plugin`].
+pub struct CmdPlugin {
+    /// Name of the plugin.
+    pub name: String,
+    /// Version of the plugin.
+    pub version: String,
+    /// Shared library the plugin got loaded from.
+    pub path: PathBuf,
+    /// Names of all commands of the plugin.
+    pub commands: Vec<&'static str>,
+    // must be dropped last.
+    _library: Library,
+}
+
+type Loaded = (CmdPlugin, Vec<MRc<dyn Cmd + Sync + Send>>);
+
+fn load(path: &Path) -> Result<Loaded, String> {
+    let (library, (name, version, commands)) =
+        load_plugin_library(path, |declaration: &CmdPluginDeclaration| {
+            let commands = (declaration.commands)()
+                .into_iter()
+                .map(|cmd| Arc::new(Mutex::new(cmd)) as MRc<dyn Cmd + Sync + Send>)
+                .collect();
+            (declaration.name, declaration.version, commands)
+        })?;
+    let plugin = CmdPlugin {
+        name: name.to_owned(),
+        version: version.to_owned(),
+        path: path.to_owned(),
+        commands: Vec::new(),
+        _library: library,
+    };
+    Ok((plugin, commands))
+}
+
+impl Core {
+    /// Load the command plugin exported using [`export_cmd_plugin`] from the shared library at
+    /// `path` and register all of its commands. Returns name of the plugin.
+    pub fn load_cmd_plugin(&mut self, path: &Path) -> Result<String, String> {
+        let path = fs::canonicalize(path).map_err(|e| e.to_string())?;
+        let (plugin, commands) = load(&path)?;
+        let name = plugin.name.clone();
+        self.commands().lock().add_plugin(plugin, commands)?;
+        Ok(name)
+    }
+
+    /// Load every shared library in `dir` using [`Core::load_cmd_plugin`], a missing directory
+    /// is not an error. Returns errors of plugins that failed to load.
+    pub fn load_cmd_plugin_dir(&mut self, dir: &Path) -> Vec<String> {
+        plugin_files(dir)
+            .iter()
+            .filter_map(|path| {
+                let err = self.load_cmd_plugin(path).err()?;
+                Some(format!("{}: {err}", path.display()))
+            })
+            .collect()
+    }
+
+    /// Remove all commands of the plugin named `name` then unload it.
+    pub fn unload_cmd_plugin(&mut self, name: &str) -> Result<(), String> {
+        self.commands().lock().remove_plugin(name)
+    }
+}
+
+#[cfg(test)]
+mod test_plugin {
+    use super::*;
+    use crate::utils::Quit;
+
+    fn commands() -> Vec<Box<dyn Cmd + Sync + Send>> {
+        vec![Box::new(Quit)]
+    }
+
+    #[test]
+    fn test_versions() {
+        let mut declaration = CmdPluginDeclaration {
+            versions: CMD_PLUGIN_VERSIONS,
+            name: "test",
+            version: "0.0.1",
+            commands,
+        };
+        let check = |declaration: &CmdPluginDeclaration| {
+            declaration
+                .versions()
+                .check(&CmdPluginDeclaration::VERSIONS)
+        };
+        assert_eq!(check(&declaration), Ok(()));
+        declaration.versions.crate_version = "0.0.0";
+        assert_eq!(
+            check(&declaration).err().unwrap(),
+            format!(
+                "Plugin was built against version 0.0.0 while rair uses version {}",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        declaration.versions.abi = 0;
+        assert_eq!(
+            check(&declaration).err().unwrap(),
+            format!(
+                "Plugin ABI version 0 doesn't match rair's ABI version {CMD_PLUGIN_ABI_VERSION}"
+            )
+        );
+    }
+}
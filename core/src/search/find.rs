@@ -0,0 +1,340 @@
+//! search command.
+
+use super::pattern::Pattern;
+use crate::core::Core;
+use crate::helper::{error_msg, is_json, print_json, str_to_num, AddrMode};
+use crate::Cmd;
+use core::cmp;
+use rair_io::IoError;
+use serde_json::json;
+use std::io::Write;
+use yansi::Paint;
+
+/// Number of bytes read from the address space in one go.
+const CHUNK_SIZE: u64 = 0x10000;
+
+#[derive(Default)]
+pub struct Search;
+
+/// Contiguous bytes that are yet to be scanned.
+struct Window {
+    start: u64,
+    data: Vec<u8>,
+}
+
+impl Window {
+    fn new(start: u64) -> Self {
+        Window {
+            start,
+            data: Vec::new(),
+        }
+    }
+    fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+    /// Report every complete match in the window, then keep only the tail
+    /// that might be the beginning of a match crossing into the next chunk.
+    fn scan(&mut self, pattern: &Pattern, hits: &mut Vec<u64>) {
+        let len = pattern.len();
+        if self.data.len() >= len {
+            for i in 0..=self.data.len() - len {
+                if pattern.matches(&self.data[i..]) {
+                    hits.push(self.start + i as u64);
+                }
+            }
+            let keep = self.data.len() - len + 1;
+            self.data.drain(..keep);
+            self.start += keep as u64;
+        }
+    }
+}
+
+impl Search {
+    fn parse_pattern(args: &[String]) -> Result<(Pattern, &[String]), String> {
+        let (pattern, rest) = match args.first().map(String::as_str) {
+            Some("hex") if args.len() >= 2 => (Pattern::from_hex(&args[1]), &args[2..]),
+            Some("str") if args.len() >= 2 => (Pattern::from_ascii(&args[1]), &args[2..]),
+            Some("wide") if args.len() >= 2 => (Pattern::from_wide(&args[1]), &args[2..]),
+            Some("int") if args.len() >= 4 => {
+                (Pattern::from_int(&args[1], &args[2], &args[3]), &args[4..])
+            }
+            Some(kind @ ("hex" | "str" | "wide" | "int")) => {
+                return Err(format!("Missing arguments for {} search.", kind.primary()));
+            }
+            Some(kind) => return Err(format!("Unknown search type {}.", kind.primary())),
+            None => return Err("Missing search type.".to_owned()),
+        };
+        Ok((pattern?, rest))
+    }
+
+    /// Ranges to search when no size is given: every opened file in physical
+    /// mode or every map in virtual mode, with adjacent ranges merged.
+    fn address_space(core: &Core) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = match core.mode {
+            AddrMode::Phy => core
+                .io
+                .uri_iter()
+                .map(|desc| (desc.paddr_base(), desc.size()))
+                .collect(),
+            AddrMode::Vir => core
+                .io
+                .map_iter()
+                .map(|map| (map.vaddr, map.size))
+                .collect(),
+        };
+        ranges.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (start, size) in ranges {
+            match merged.last_mut() {
+                Some(last) if last.0 + last.1 == start => last.1 += size,
+                _ => merged.push((start, size)),
+            }
+        }
+        merged
+    }
+
+    fn search_range(
+        core: &mut Core,
+        pattern: &Pattern,
+        start: u64,
+        size: u64,
+        hits: &mut Vec<u64>,
+    ) -> Result<(), IoError> {
+        let end = start.saturating_add(size);
+        let mut window = Window::new(start);
+        let mut addr = start;
+        while addr < end {
+            let chunk = cmp::min(CHUNK_SIZE, end - addr);
+            for (loc, byte) in core.read_sparce(addr, chunk)? {
+                if loc != window.end() {
+                    window.scan(pattern, hits);
+                    window = Window::new(loc);
+                }
+                window.data.push(byte);
+            }
+            window.scan(pattern, hits);
+            addr += chunk;
+        }
+        Ok(())
+    }
+}
+
+impl Cmd for Search {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        let (pattern, rest) = match Self::parse_pattern(args) {
+            Ok(p) => p,
+            Err(e) => return error_msg(core, "Invalid search pattern", &e),
+        };
+        let ranges = match rest {
+            [] => Self::address_space(core),
+            [size] => match str_to_num(size) {
+                Ok(size) => vec![(core.get_loc(), size)],
+                Err(e) => return error_msg(core, "Failed to parse size", &e.to_string()),
+            },
+            _ => {
+                return error_msg(
+                    core,
+                    "Wrong number of arguments",
+                    "Expected optional [size] after the search pattern.",
+                )
+            }
+        };
+        let mut hits = Vec::new();
+        for (start, size) in ranges {
+            if let Err(e) = Self::search_range(core, &pattern, start, size, &mut hits) {
+                return error_msg(core, "Read Failed", &e.to_string());
+            }
+        }
+        if is_json(core) {
+            return print_json(core, &json!(hits));
+        }
+        for hit in hits {
+            writeln!(core.stdout, "0x{hit:x}").unwrap();
+        }
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &["search", "/"]
+    }
+
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            (
+                "hex [hexpairs] <size>",
+                "Search for hex pattern, `?` matches any nibble.",
+            ),
+            ("str [string] <size>", "Search for ASCII string."),
+            ("wide [string] <size>", "Search for UTF-16LE string."),
+            (
+                "int [width] [le|be] [value] <size>",
+                "Search for integer of [width] bytes (1, 2, 4 or 8).",
+            ),
+            (
+                "",
+                "Search [size] bytes from current location, or the whole address space if size is not given.",
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test_search {
+    use super::*;
+    use crate::writer::Writer;
+    use crate::CmdOps;
+    use rair_io::IoMode;
+    use std::path::Path;
+    use test_file::*;
+
+    fn run(core: &mut Core, args: &[&str]) {
+        let args: Vec<String> = args.iter().map(|s| (*s).to_owned()).collect();
+        Search.run(core, &args);
+    }
+
+    #[test]
+    fn test_search_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        Search.help(&mut core);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Commands: [search | /]\n\
+             Usage:\n\
+             / hex [hexpairs] <size>\tSearch for hex pattern, `?` matches any nibble.\n\
+             / str [string] <size>\tSearch for ASCII string.\n\
+             / wide [string] <size>\tSearch for UTF-16LE string.\n\
+             / int [width] [le|be] [value] <size>\tSearch for integer of [width] bytes (1, 2, 4 or 8).\n\
+             /\tSearch [size] bytes from current location, or the whole address space if size is not given.\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    fn test_search_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io.open(&path.to_string_lossy(), IoMode::READ).unwrap();
+        run(&mut core, &["hex", "0?0?"]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "0x0\n0x1\n0x2\n0x3\n0x4\n0x5\n0x6\n"
+        );
+        core.stdout = Writer::new_buf();
+        run(&mut core, &["int", "2", "be", "0x3d18"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "0x11\n");
+        core.stdout = Writer::new_buf();
+        run(&mut core, &["int", "2", "le", "0x3d18"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        core.stdout = Writer::new_buf();
+        core.set_loc(0x20);
+        run(&mut core, &["hex", "?5", "0x20"]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "0x20\n0x2b\n0x35\n0x38\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_search() {
+        operate_on_file(&test_search_cb, DATA);
+    }
+
+    fn test_search_json_cb(path: &Path) {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io.open(&path.to_string_lossy(), IoMode::READ).unwrap();
+        core.run(
+            "searchj",
+            &[
+                "int".to_owned(),
+                "2".to_owned(),
+                "be".to_owned(),
+                "0x3d18".to_owned(),
+            ],
+        );
+        core.run(
+            "searchj",
+            &[
+                "int".to_owned(),
+                "2".to_owned(),
+                "le".to_owned(),
+                "0x3d18".to_owned(),
+            ],
+        );
+        assert_eq!(core.stdout.utf8_string().unwrap(), "[17]\n[]\n");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+    #[test]
+    fn test_search_json() {
+        operate_on_file(&test_search_json_cb, DATA);
+    }
+
+    #[test]
+    fn test_search_strings() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x30000", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        // crosses a chunk boundary.
+        core.write(CHUNK_SIZE - 2, b"rair").unwrap();
+        core.write(0x20010, b"r\0a\0i\0r\0").unwrap();
+        run(&mut core, &["str", "rair"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "0xfffe\n");
+        core.stdout = Writer::new_buf();
+        run(&mut core, &["wide", "rair"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "0x20010\n");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_search_vir() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x100", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.pwrite(0x80, b"rair").unwrap();
+        // match split between two adjacent maps and a match across a gap.
+        core.io.map(0x80, 0x1000, 2).unwrap();
+        core.io.map(0x82, 0x1002, 2).unwrap();
+        core.io.map(0x80, 0x2000, 2).unwrap();
+        core.io.map(0x82, 0x2003, 2).unwrap();
+        core.mode = AddrMode::Vir;
+        run(&mut core, &["str", "rair"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "0x1000\n");
+        core.stdout = Writer::new_buf();
+        core.set_loc(0x2000);
+        run(&mut core, &["str", "ir", "0x10"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "0x2003\n");
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        run(&mut core, &[]);
+        run(&mut core, &["foo", "bar"]);
+        run(&mut core, &["int", "4"]);
+        run(&mut core, &["hex", "0"]);
+        run(&mut core, &["str", "a", "b"]);
+        run(&mut core, &["str", "a", "1", "2"]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Invalid search pattern\nMissing search type.\n\
+             Error: Invalid search pattern\nUnknown search type foo.\n\
+             Error: Invalid search pattern\nMissing arguments for int search.\n\
+             Error: Invalid search pattern\nExpected an even number of hex digits.\n\
+             Error: Failed to parse size\ninvalid digit found in string\n\
+             Error: Wrong number of arguments\nExpected optional [size] after the search pattern.\n"
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! commands for searching the address space.
+
+mod find;
+mod pattern;
+
+use self::find::Search;
+use crate::core::Core;
+
+pub fn register_search(core: &mut Core) {
+    core.add_command(Search);
+}
@@ -0,0 +1,145 @@
+//! Byte patterns used by the search commands.
+
+use crate::helper::str_to_num;
+
+/// A sequence of bytes where each byte is compared only on the bits set in its mask.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Pattern {
+    bytes: Vec<u8>,
+    masks: Vec<u8>,
+}
+
+impl Pattern {
+    fn exact(bytes: Vec<u8>) -> Self {
+        let masks = vec![0xff; bytes.len()];
+        Pattern { bytes, masks }
+    }
+
+    /// Parse hex pairs where `?` stands for any nibble, for example `7f??4c4?`.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let digits: Vec<char> = hex.chars().collect();
+        if digits.is_empty() || !digits.len().is_multiple_of(2) {
+            return Err("Expected an even number of hex digits.".to_owned());
+        }
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        let mut masks = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks(2) {
+            let mut byte: u8 = 0;
+            let mut mask: u8 = 0;
+            for &c in pair {
+                byte <<= 4u8;
+                mask <<= 4u8;
+                if c == '?' {
+                    continue;
+                }
+                let Some(nibble) = c.to_digit(16) else {
+                    return Err(format!("Invalid hex digit `{c}`."));
+                };
+                byte |= nibble as u8;
+                mask |= 0xf;
+            }
+            bytes.push(byte);
+            masks.push(mask);
+        }
+        Ok(Pattern { bytes, masks })
+    }
+
+    /// Pattern matching the raw bytes of `s`.
+    pub fn from_ascii(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("Cannot search for empty string.".to_owned());
+        }
+        Ok(Pattern::exact(s.as_bytes().to_vec()))
+    }
+
+    /// Pattern matching `s` encoded as UTF-16 little endian.
+    pub fn from_wide(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("Cannot search for empty string.".to_owned());
+        }
+        Ok(Pattern::exact(
+            s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        ))
+    }
+
+    /// Pattern matching `value` stored as `width` bytes integer in the given endianness.
+    pub fn from_int(width: &str, endian: &str, value: &str) -> Result<Self, String> {
+        let width = match str_to_num(width) {
+            Ok(w @ (1 | 2 | 4 | 8)) => w as usize,
+            Ok(w) => return Err(format!("Invalid integer width {w}, expected 1, 2, 4 or 8.")),
+            Err(e) => return Err(e.to_string()),
+        };
+        let value = str_to_num(value).map_err(|e| e.to_string())?;
+        if width < 8 && value >> (width * 8) != 0 {
+            return Err(format!("Value 0x{value:x} does not fit in {width} bytes."));
+        }
+        let mut bytes = value.to_be_bytes()[8 - width..].to_vec();
+        match endian {
+            "le" => bytes.reverse(),
+            "be" => (),
+            _ => return Err(format!("Invalid endianness `{endian}`, expected le or be.")),
+        }
+        Ok(Pattern::exact(bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Check if the pattern matches the beginning of `data`.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(&self.masks)
+                .zip(data)
+                .all(|((byte, mask), d)| d & mask == *byte)
+    }
+}
+
+#[cfg(test)]
+mod test_pattern {
+    use super::*;
+
+    #[test]
+    fn test_from_hex() {
+        let pattern = Pattern::from_hex("7f?54?").unwrap();
+        assert_eq!(pattern.len(), 3);
+        assert!(pattern.matches(&[0x7f, 0x05, 0x4c]));
+        assert!(pattern.matches(&[0x7f, 0xf5, 0x40, 0x00]));
+        assert!(!pattern.matches(&[0x7f, 0x06, 0x4c]));
+        assert!(!pattern.matches(&[0x7f, 0x05]));
+        Pattern::from_hex("7f0").unwrap_err();
+        Pattern::from_hex("").unwrap_err();
+        assert_eq!(
+            Pattern::from_hex("7g").unwrap_err(),
+            "Invalid hex digit `g`."
+        );
+    }
+
+    #[test]
+    fn test_from_strings() {
+        let pattern = Pattern::from_ascii("ELF").unwrap();
+        assert!(pattern.matches(b"ELF\x02"));
+        assert!(!pattern.matches(b"elf"));
+        let pattern = Pattern::from_wide("AB").unwrap();
+        assert!(pattern.matches(&[0x41, 0, 0x42, 0]));
+        Pattern::from_ascii("").unwrap_err();
+        Pattern::from_wide("").unwrap_err();
+    }
+
+    #[test]
+    fn test_from_int() {
+        let pattern = Pattern::from_int("4", "le", "0x11223344").unwrap();
+        assert!(pattern.matches(&[0x44, 0x33, 0x22, 0x11]));
+        let pattern = Pattern::from_int("2", "be", "0x1122").unwrap();
+        assert!(pattern.matches(&[0x11, 0x22]));
+        let pattern = Pattern::from_int("8", "be", "1").unwrap();
+        assert!(pattern.matches(&[0, 0, 0, 0, 0, 0, 0, 1]));
+        Pattern::from_int("3", "le", "1").unwrap_err();
+        Pattern::from_int("1", "le", "0x100").unwrap_err();
+        Pattern::from_int("1", "me", "1").unwrap_err();
+        Pattern::from_int("1", "le", "x").unwrap_err();
+    }
+}
@@ -1,17 +1,36 @@
 //! commands for handling environment variables.
 
 use crate::core::Core;
-use crate::helper::{error_msg, expect, expect_range, is_color, str_to_num};
+use crate::helper::{error_msg, expect, expect_range, is_color, is_json, print_json, str_to_num};
 use crate::Cmd;
 use rair_env::EnvData;
+use serde_json::{json, Map, Value};
 use std::io::Write;
 use yansi::Paint;
 #[derive(Default)]
 pub struct Environment;
 
+fn env_to_json(data: &EnvData) -> Value {
+    match *data {
+        EnvData::Bool(b) => json!(b),
+        EnvData::I64(i) => json!(i),
+        EnvData::U64(u) => json!(u),
+        EnvData::Str(s) => json!(s),
+        EnvData::Color(r, g, b) => json!(format!("#{r:02x}{g:02x}{b:02x}")),
+    }
+}
+
 impl Environment {
     fn iterate(core: &mut Core) {
         let env = core.env.clone();
+        if is_json(core) {
+            let vars: Map<String, Value> = env
+                .read()
+                .iter()
+                .map(|(k, v)| (k.to_owned(), env_to_json(&v)))
+                .collect();
+            return print_json(core, &Value::Object(vars));
+        }
         for (k, v) in env.read().iter() {
             match v {
                 EnvData::Bool(b) => writeln!(core.stdout, "{k} = {b}").unwrap(),
@@ -77,12 +96,18 @@ impl Environment {
         }
     }
     fn display(core: &mut Core, key: &str) {
+        let json = is_json(core);
         let env = core.env.read();
         let Some(data) = env.get(key) else {
             drop(env);
             let message = format!("Variable `{key}` doesn't exist.");
             return error_msg(core, "Failed to display variable.", &message);
         };
+        if json {
+            let value = env_to_json(&data);
+            drop(env);
+            return print_json(core, &value);
+        }
         match data {
             EnvData::Bool(b) => writeln!(core.stdout, "{b}").unwrap(),
             EnvData::I64(i) => writeln!(core.stdout, "{i}").unwrap(),
@@ -188,9 +213,15 @@ impl Cmd for EnvironmentHelp {
             expect(core, args.len() as u64, 1);
             return;
         }
+        let json = is_json(core);
         let env = core.env.read();
         let res = env.get_help(&args[0]);
         if let Some(help) = res {
+            if json {
+                let value = json!({"var": args[0], "help": help});
+                drop(env);
+                return print_json(core, &value);
+            }
             let color = env.get_str("environmentHelp.envColor").unwrap();
             let (r, g, b) = env.get_color(color).unwrap();
             writeln!(core.stdout, "{}:\t{}", &args[0].rgb(r, g, b), help).unwrap();
@@ -263,6 +294,25 @@ mod test_env {
             "Error: Failed to display help.\nVariable Not found\n"
         );
     }
+    #[test]
+    fn test_env_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("ej", &["environmentHelp.envColor".to_owned()]);
+        core.run("ej", &["color.6".to_owned()]);
+        core.run("ej", &["cfg.json".to_owned()]);
+        core.run("ehj", &["cfg.json".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "\"color.6\"\n\
+             \"#6c71c4\"\n\
+             true\n\
+             {\"help\":\"Print output of commands as JSON, same as adding `j` suffix to command name\",\"var\":\"cfg.json\"}\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
     #[test]
     fn test_env_reset() {
         let mut core = Core::new_no_colors();
@@ -1,19 +1,27 @@
 //! Utility commands.
 mod env;
+mod plugins;
 mod project;
 mod quit;
+mod source;
 
 use self::env::{Environment, EnvironmentHelp, EnvironmentReset};
+use self::plugins::{ListCmdPlugins, LoadCmdPlugin, UnloadCmdPlugin};
 use self::project::{Load, Save};
 pub use self::quit::Quit;
+pub use self::source::{rair_source, Source};
 use crate::core::Core;
 
 pub fn register_utils(core: &mut Core) {
     core.add_command(Quit);
     core.add_command(Save);
     core.add_command(Load);
+    core.add_command(Source);
     core.add_command(Environment);
     core.add_command(EnvironmentReset);
+    core.add_command(ListCmdPlugins);
+    core.add_command(LoadCmdPlugin);
+    core.add_command(UnloadCmdPlugin);
     let eh = EnvironmentHelp::new(core);
     core.add_command(eh);
 }
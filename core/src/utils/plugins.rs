@@ -0,0 +1,246 @@
+//! commands for listing, loading and unloading command plugins.
+
+use crate::helper::{error_msg, expect, is_json, print_json};
+use crate::{cmd::Cmd, core::Core};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use yansi::Paint;
+
+#[derive(Default)]
+pub struct ListCmdPlugins;
+
+impl Cmd for ListCmdPlugins {
+    fn commands(&self) -> &'static [&'static str] {
+        &["cmdPlugins"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "",
+            "List all loaded command plugins and the commands they provide.",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if !args.is_empty() {
+            expect(core, args.len() as u64, 0);
+            return;
+        }
+        let commands = core.commands();
+        let commands = commands.lock();
+        if is_json(core) {
+            let plugins = commands
+                .plugins()
+                .map(|plugin| {
+                    json!({
+                        "name": plugin.name,
+                        "version": plugin.version,
+                        "path": plugin.path,
+                        "commands": plugin.commands,
+                    })
+                })
+                .collect();
+            return print_json(core, &Value::Array(plugins));
+        }
+        let (r, g, b) = core.env.read().get_color("color.6").unwrap();
+        writeln!(
+            core.stdout,
+            "{: <12}{: <10}{: <24}{}",
+            "Name".rgb(r, g, b),
+            "Version".rgb(r, g, b),
+            "Commands".rgb(r, g, b),
+            "Path".rgb(r, g, b)
+        )
+        .unwrap();
+        for plugin in commands.plugins() {
+            writeln!(
+                core.stdout,
+                "{: <12}{: <10}{: <24}{}",
+                plugin.name,
+                plugin.version,
+                plugin.commands.join(", "),
+                plugin.path.display()
+            )
+            .unwrap();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LoadCmdPlugin;
+
+impl Cmd for LoadCmdPlugin {
+    fn commands(&self) -> &'static [&'static str] {
+        &["cmdPluginLoad"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[path]",
+            "Load command plugin from shared library at [path].",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.load_cmd_plugin(Path::new(&args[0])) {
+            error_msg(core, "Failed to load plugin", &e);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UnloadCmdPlugin;
+
+impl Cmd for UnloadCmdPlugin {
+    fn commands(&self) -> &'static [&'static str] {
+        &["cmdPluginUnload"]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[name]",
+            "Remove all commands of plugin [name] then unload it.",
+        )]
+    }
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        if let Err(e) = core.unload_cmd_plugin(&args[0]) {
+            error_msg(core, "Failed to unload plugin", &e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_plugins {
+    use super::*;
+    use crate::writer::Writer;
+    use rair_io::IoMode;
+    use std::fs;
+    use std::path::PathBuf;
+    use test_file::example_plugin;
+
+    fn adler32_plugin() -> PathBuf {
+        example_plugin("adler32_plugin")
+    }
+
+    #[test]
+    fn test_docs() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.help("cmdPlugins");
+        core.help("cmdPluginLoad");
+        core.help("cmdPluginUnload");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Command: [cmdPlugins]\n\
+             Usage:\n\
+             cmdPlugins\tList all loaded command plugins and the commands they provide.\n\
+             Command: [cmdPluginLoad]\n\
+             Usage:\n\
+             cmdPluginLoad [path]\tLoad command plugin from shared library at [path].\n\
+             Command: [cmdPluginUnload]\n\
+             Usage:\n\
+             cmdPluginUnload [name]\tRemove all commands of plugin [name] then unload it.\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_load_unload() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let path = adler32_plugin();
+        core.run("cmdPluginLoad", &[path.to_string_lossy().to_string()]);
+        core.io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io.pwrite(0, b"Wikipedia").unwrap();
+        core.run("adler32", &["9".to_owned()]);
+        core.help("adler32");
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "0x11e60398\n\
+             Command: [adler32]\n\
+             Usage:\n\
+             adler32 [size]\tPrint Adler-32 checksum of [size] bytes at current location.\n\
+             Plugin: adler32 0.0.1\n"
+        );
+        core.stdout = Writer::new_buf();
+        core.run("cmdPlugins", &[]);
+        let path = fs::canonicalize(path).unwrap();
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            format!(
+                "Name        Version   Commands                Path\n\
+                 adler32     0.0.1     adler32                 {}\n",
+                path.display()
+            )
+        );
+        core.stdout = Writer::new_buf();
+        core.run("cmdPluginsj", &[]);
+        let out: Value = serde_json::from_str(&core.stdout.utf8_string().unwrap()).unwrap();
+        assert_eq!(
+            out,
+            json!([{"name": "adler32", "version": "0.0.1", "path": path, "commands": ["adler32"]}])
+        );
+        core.stdout = Writer::new_buf();
+        core.run("cmdPluginUnload", &["adler32".to_owned()]);
+        core.run("cmdPlugins", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Name        Version   Commands                Path\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        core.stdout = Writer::new_buf();
+        core.stderr = Writer::new_buf();
+        assert!(core.commands().lock().find("adler32").is_none());
+        core.run("cmdPluginLoad", &[path.to_string_lossy().to_string()]);
+        assert!(core.commands().lock().find("adler32").is_some());
+    }
+
+    #[test]
+    fn test_plugin_dir() {
+        let mut core = Core::new_no_colors();
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert!(core.load_cmd_plugin_dir(&missing).is_empty());
+        fs::copy(
+            adler32_plugin(),
+            dir.path().join(adler32_plugin().file_name().unwrap()),
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a plugin").unwrap();
+        assert!(core.load_cmd_plugin_dir(dir.path()).is_empty());
+        assert_eq!(core.commands().lock().plugins().count(), 1);
+        let errors = core.load_cmd_plugin_dir(dir.path());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with(": Plugin adler32 is already loaded."));
+    }
+
+    #[test]
+    fn test_plugins_errors() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.run("cmdPlugins", &["x".to_owned()]);
+        core.run("cmdPluginLoad", &[]);
+        core.run("cmdPluginUnload", &[]);
+        core.run("cmdPluginUnload", &["adler32".to_owned()]);
+        core.run("cmdPluginLoad", &["file_that_doesnt_exist.so".to_owned()]);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        let err = core.stderr.utf8_string().unwrap();
+        assert!(err.starts_with(
+            "Arguments Error: Expected 0 argument(s), found 1.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n\
+             Arguments Error: Expected 1 argument(s), found 0.\n\
+             Error: Failed to unload plugin\n\
+             Plugin adler32 is not loaded.\n\
+             Error: Failed to load plugin\n"
+        ));
+    }
+}
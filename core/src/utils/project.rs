@@ -1,12 +1,13 @@
 //! Commands to save/load projects.
 
 use crate::core::Core;
-use crate::helper::{error_msg, expect};
+use crate::helper::{error_msg, expect, is_json, print_json};
 use crate::Cmd;
 use core::mem;
 use flate2::write::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
 use serde::Deserialize;
+use serde_json::json;
 use std::fs::{self, File};
 use std::io::prelude::*;
 
@@ -31,7 +32,10 @@ impl Cmd for Save {
         compressor.write_all(&data).unwrap();
         let compressed_data = compressor.finish().unwrap();
         if let Err(e) = file.write_all(&compressed_data) {
-            error_msg(core, "Failed to save project", &e.to_string());
+            return error_msg(core, "Failed to save project", &e.to_string());
+        }
+        if is_json(core) {
+            print_json(core, &json!({"path": args[0]}));
         }
     }
     fn commands(&self) -> &'static [&'static str] {
@@ -73,8 +77,15 @@ impl Cmd for Load {
         mem::swap(&mut core.stdout, &mut core2.stdout);
         mem::swap(&mut core.stderr, &mut core2.stderr);
         mem::swap(&mut core.env, &mut core2.env);
+        // the environment is kept, so keep the IO cache as configured
+        let cache = core2.env.read().get_bool("io.cache").unwrap_or(false);
+        core2.io.set_cache(cache);
         core2.set_commands(core.commands());
+        core2.evaluator = core.evaluator;
         *core = core2;
+        if is_json(core) {
+            print_json(core, &json!({"path": args[0]}));
+        }
     }
     fn commands(&self) -> &'static [&'static str] {
         &["load"]
@@ -91,6 +102,7 @@ mod test_project {
     use super::*;
     use crate::{writer::*, CmdOps};
     use rair_io::*;
+    use serde_cbor::{value, Value};
     use std::fs;
     #[test]
     fn test_project_help() {
@@ -126,20 +138,132 @@ mod test_project {
             .open_at("malloc://0x1337", IoMode::READ | IoMode::WRITE, 0x31000)
             .unwrap();
         core.io.map(0x31000, 0xfff31000, 0x337).unwrap();
+        core.flags.add("main", 0xfff31000, 0x20, "*").unwrap();
         save.run(&mut core, &["rair_project".to_owned()]);
         core.io.close_all();
         load.run(&mut core, &["rair_project".to_owned()]);
         core.run("files", &[]);
         core.run("maps", &[]);
+        core.run("flags", &[]);
         assert_eq!(
             core.stdout.utf8_string().unwrap(),
             "Handle\tStart address\tsize\t\tPermissions\tURI\n\
              0\t0x00000000\t0x00000500\tWRITE | READ\tmalloc://0x500\n\
              1\t0x00031000\t0x00001337\tWRITE | READ\tmalloc://0x1337\n\
-             Virtual Address     Physical Address    Size\n\
-             0xfff31000          0x31000             0x337\n"
+             Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0xfff31000          0x31000             0x337               rwx     0         \n\
+             Address             Size                Flagspace           Name\n\
+             0xfff31000          0x20                *                   main\n"
         );
         assert_eq!(core.stderr.utf8_string().unwrap(), "");
         fs::remove_file("rair_project").unwrap();
     }
+    #[test]
+    fn test_project_json() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x500", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run("savej", &["rair_project_json".to_owned()]);
+        core.io.close_all();
+        core.run("loadj", &["rair_project_json".to_owned()]);
+        core.run("loadj", &["rair_project_json_missing".to_owned()]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "{\"path\":\"rair_project_json\"}\n\
+             {\"path\":\"rair_project_json\"}\n"
+        );
+        assert!(core
+            .stderr
+            .utf8_string()
+            .unwrap()
+            .starts_with("Error: Failed to load project\n"));
+        assert_eq!(core.io.uri_iter().count(), 1);
+        fs::remove_file("rair_project_json").unwrap();
+    }
+    #[test]
+    fn test_project_named_maps() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut load = Load;
+        let mut save = Save;
+        core.io
+            .open("malloc://0x500", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.io
+            .map_named(0x100, 0x1000, 0x200, "text", MapPerm::READ | MapPerm::EXEC)
+            .unwrap();
+        save.run(&mut core, &["rair_project_named_maps".to_owned()]);
+        core.io.close_all();
+        load.run(&mut core, &["rair_project_named_maps".to_owned()]);
+        core.run("maps", &[]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Virtual Address     Physical Address    Size                Perm    Priority  Name\n\
+             0x1000              0x100               0x200               r-x     0         text\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        fs::remove_file("rair_project_named_maps").unwrap();
+    }
+    #[test]
+    fn test_project_without_flags() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut load = Load;
+        core.io
+            .open("malloc://0x500", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        // projects saved before flags existed have no flags at all
+        let mut project = value::to_value(&core).unwrap();
+        if let Value::Map(fields) = &mut project {
+            fields.remove(&Value::Text("flags".to_owned()));
+        }
+        let mut compressor = ZlibEncoder::new(Vec::new(), Compression::default());
+        compressor
+            .write_all(&serde_cbor::to_vec(&project).unwrap())
+            .unwrap();
+        fs::write("rair_project_no_flags", compressor.finish().unwrap()).unwrap();
+        core.io.close_all();
+        load.run(&mut core, &["rair_project_no_flags".to_owned()]);
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        assert!(core.flags.is_empty());
+        assert_eq!(core.io.uri_iter().count(), 1);
+        fs::remove_file("rair_project_no_flags").unwrap();
+    }
+    #[test]
+    fn test_project_journal() {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        let mut load = Load;
+        let mut save = Save;
+        core.io
+            .open("malloc://0x500", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core.run("wx", &["1234".to_owned()]);
+        core.run("wx", &["5678".to_owned()]);
+        core.run("wu", &[]);
+        save.run(&mut core, &["rair_project_journal".to_owned()]);
+        core.io.close_all();
+        load.run(&mut core, &["rair_project_journal".to_owned()]);
+        let mut data = [0; 2];
+        core.io.pread(0, &mut data).unwrap();
+        assert_eq!(data, [0x12, 0x34]);
+        core.run("wj", &[]);
+        core.run("wu", &[]);
+        core.io.pread(0, &mut data).unwrap();
+        assert_eq!(data, [0, 0]);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Index     Address             Size      State     Data\n\
+             0         0x0                 0x2       applied   0000 -> 1234\n\
+             1         0x0                 0x2       undone    1234 -> 5678\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+        fs::remove_file("rair_project_journal").unwrap();
+    }
 }
@@ -0,0 +1,120 @@
+//! Running rair scripts line by line.
+
+use crate::core::Core;
+use crate::helper::{error_msg, expect};
+use crate::Cmd;
+use core::cell::RefCell;
+use core::mem;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+thread_local! {
+    // scripts that are currently being sourced, used for detecting include cycles.
+    static SOURCING: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Default)]
+pub struct Source;
+
+impl Cmd for Source {
+    fn run(&mut self, core: &mut Core, args: &[String]) {
+        if args.len() != 1 {
+            expect(core, args.len() as u64, 1);
+            return;
+        }
+        rair_source(core, &args[0]);
+    }
+    fn fork(&self) -> Option<Box<dyn Cmd>> {
+        // scripts can source other scripts.
+        Some(Box::new(Source))
+    }
+    fn commands(&self) -> &'static [&'static str] {
+        &["source", "."]
+    }
+    fn help_messages(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "[file_path]",
+            "Run every line of given script as a command.",
+        )]
+    }
+}
+
+/// Evaluate every line of the script at `path` using [`Core::eval`]. Errors are prefixed with the
+/// script path and line number they originated from.
+pub fn rair_source(core: &mut Core, path: &str) {
+    let script = match fs::read_to_string(path) {
+        Ok(script) => script,
+        Err(e) => return error_msg(core, "Failed to read script", &format!("{path}: {e}.")),
+    };
+    let id = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    if SOURCING.with_borrow(|stack| stack.contains(&id)) {
+        let msg = format!("{path} is already being sourced.");
+        return error_msg(core, "Cyclic script inclusion", &msg);
+    }
+    SOURCING.with_borrow_mut(|stack| stack.push(id));
+    for (i, line) in script.lines().enumerate() {
+        let stderr = mem::take(&mut core.stderr);
+        core.eval(line);
+        let err = mem::replace(&mut core.stderr, stderr).bytes().unwrap();
+        if !err.is_empty() {
+            write!(core.stderr, "{path}:{}: ", i + 1).unwrap();
+            core.stderr.write_all(&err).unwrap();
+        }
+    }
+    SOURCING.with_borrow_mut(Vec::pop);
+}
+
+#[cfg(test)]
+mod test_source {
+    use super::*;
+    use crate::writer::Writer;
+    use crate::CmdOps;
+
+    fn new_core() -> Core {
+        let mut core = Core::new_no_colors();
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.set_evaluator(|core, line| {
+            let mut words = line.split_whitespace().map(str::to_owned);
+            if let Some(command) = words.next() {
+                core.run(&command, &words.collect::<Vec<_>>());
+            }
+        });
+        core
+    }
+
+    #[test]
+    fn test_source_help() {
+        let mut core = new_core();
+        Source.help(&mut core);
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "Commands: [source | .]\n\
+             Usage:\n\
+             . [file_path]\tRun every line of given script as a command.\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_source_registered() {
+        fs::write("core_test_outer.rair", "s 0x10\n. core_test_inner.rair\n").unwrap();
+        fs::write(
+            "core_test_inner.rair",
+            "s +0x20\nsource core_test_outer.rair\n",
+        )
+        .unwrap();
+        let mut core = new_core();
+        core.run("source", &["core_test_outer.rair".to_owned()]);
+        fs::remove_file("core_test_outer.rair").unwrap();
+        fs::remove_file("core_test_inner.rair").unwrap();
+        assert_eq!(core.get_loc(), 0x30);
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "core_test_outer.rair:2: core_test_inner.rair:2: Error: Cyclic script inclusion\n\
+             core_test_outer.rair is already being sourced.\n"
+        );
+    }
+}
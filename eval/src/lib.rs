@@ -1,5 +1,5 @@
 use core::mem;
-use rair_cmd::{Argument, Cmd, ParseTree, RedPipe};
+use rair_cmd::{Argument, Cmd, Loc, ParseTree, RedPipe};
 use rair_core::{Core, Writer};
 use std::{
     fs::{File, OpenOptions},
@@ -32,6 +32,16 @@ fn run_cmd(core: &mut Core, cmd: Cmd) {
             Err(e) => return writeln!(core.stderr, "{e}").unwrap(),
         }
     }
+    // resolve location
+    let loc = match cmd.loc {
+        Some(Loc::Addr(at)) => Some(at),
+        Some(Loc::Flag(name)) => match core.flags.get(&name) {
+            Some(flag) => Some(flag.addr),
+            None => return writeln!(core.stderr, "Flag `{name}` does not exist.").unwrap(),
+        },
+        Some(_) => unreachable!(),
+        None => None,
+    };
     // process redirections or pipes
     let mut stdout: Option<Writer> = None;
     let mut child: Option<Child> = None;
@@ -54,7 +64,7 @@ fn run_cmd(core: &mut Core, cmd: Cmd) {
         RedPipe::None => (),
     }
     // execute
-    match cmd.loc {
+    match loc {
         Some(at) => core.run_at(&cmd.command, &args, at),
         None => core.run(&cmd.command, &args),
     }
@@ -1,4 +1,5 @@
 use rair_core::{Core, Writer};
+use std::fs;
 
 use super::rair_eval;
 
@@ -71,3 +72,80 @@ fn test_good_command_good_arg() {
     assert!(core.stdout.bytes().unwrap().is_empty());
     assert!(core.stderr.bytes().unwrap().is_empty());
 }
+
+#[test]
+fn test_flag_loc() {
+    let mut core = Core::new_no_colors();
+    core.stdout = Writer::new_buf();
+    core.stderr = Writer::new_buf();
+    rair_eval(&mut core, "f main @ 0x1000");
+    rair_eval(&mut core, "s 0x20");
+    rair_eval(&mut core, "f entry @ main");
+    rair_eval(&mut core, "s entry");
+    assert_eq!(core.get_loc(), 0x1000);
+    rair_eval(&mut core, "f foo @ bar");
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Flag `bar` does not exist.\n"
+    );
+    assert_eq!(core.flags.get("foo"), None);
+    assert!(core.stdout.bytes().unwrap().is_empty());
+}
+
+#[test]
+fn test_source() {
+    let script = "eval_test_source.rair";
+    fs::write(script, "# comment\nwx 41\n\nwx 42 @ 1\nbogus\n").unwrap();
+    let mut core = Core::new_no_colors();
+    core.stdout = Writer::new_buf();
+    core.stderr = Writer::new_buf();
+    core.set_evaluator(rair_eval);
+    rair_eval(&mut core, "o rw malloc://0x10");
+    rair_eval(&mut core, ". eval_test_source.rair");
+    fs::remove_file(script).unwrap();
+    let mut data = [0; 2];
+    core.io.pread(0, &mut data).unwrap();
+    assert_eq!(data, *b"AB");
+    assert!(core.stdout.bytes().unwrap().is_empty());
+    assert!(core
+        .stderr
+        .utf8_string()
+        .unwrap()
+        .starts_with("eval_test_source.rair:5: Error: Execution failed\n"));
+}
+
+#[test]
+fn test_source_nested() {
+    fs::write("eval_test_outer.rair", "source eval_test_inner.rair\n").unwrap();
+    fs::write("eval_test_inner.rair", "\n. eval_test_outer.rair\n").unwrap();
+    let mut core = Core::new_no_colors();
+    core.stdout = Writer::new_buf();
+    core.stderr = Writer::new_buf();
+    core.set_evaluator(rair_eval);
+    rair_eval(&mut core, "source eval_test_outer.rair");
+    fs::remove_file("eval_test_outer.rair").unwrap();
+    fs::remove_file("eval_test_inner.rair").unwrap();
+    assert!(core.stdout.bytes().unwrap().is_empty());
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "eval_test_outer.rair:1: eval_test_inner.rair:2: Error: Cyclic script inclusion\n\
+         eval_test_outer.rair is already being sourced.\n"
+    );
+}
+
+#[test]
+fn test_source_errors() {
+    let mut core = Core::new_no_colors();
+    core.stdout = Writer::new_buf();
+    core.stderr = Writer::new_buf();
+    core.set_evaluator(rair_eval);
+    rair_eval(&mut core, ". eval_test_missing.rair");
+    rair_eval(&mut core, ".");
+    assert!(core.stdout.bytes().unwrap().is_empty());
+    assert_eq!(
+        core.stderr.utf8_string().unwrap(),
+        "Error: Failed to read script\n\
+         eval_test_missing.rair: No such file or directory (os error 2).\n\
+         Arguments Error: Expected 1 argument(s), found 0.\n"
+    );
+}
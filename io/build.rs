@@ -0,0 +1,12 @@
+//! Record version of the compiler so that shared library plugins built by another one are refused.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let output = Command::new(rustc).arg("--version").output().unwrap();
+    let version = String::from_utf8_lossy(&output.stdout);
+    println!("cargo:rustc-env=RAIR_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -0,0 +1,89 @@
+//! Example of an IO plugin built as a shared library, it decodes ROT13 text as in
+//! `rot13://path/to/file` and can be layered over any other URI.
+//!
+//! Build it with `cargo build --example rot13_plugin` then load it with `pluginLoad` or copy
+//! it into the rair plugin directory.
+
+use rair_io::{
+    export_rio_plugin, IoError, IoMode, RIOPlugin, RIOPluginDesc, RIOPluginMetadata,
+    RIOPluginOperations,
+};
+
+const METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Rot13",
+    desc: "Example shared library plugin that opens ROT13 encoded files as in rot13://file.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+fn rot13(byte: u8) -> u8 {
+    match byte {
+        b'a'..=b'z' => (byte - b'a' + 13) % 26 + b'a',
+        b'A'..=b'Z' => (byte - b'A' + 13) % 26 + b'A',
+        _ => byte,
+    }
+}
+
+struct Rot13Internal {
+    file: Box<dyn RIOPluginOperations + Sync + Send>,
+}
+
+impl RIOPluginOperations for Rot13Internal {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        self.file.read(raddr, buffer)?;
+        for byte in buffer {
+            *byte = rot13(*byte);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        let encoded: Vec<u8> = buffer.iter().map(|byte| rot13(*byte)).collect();
+        self.file.write(raddr, &encoded)
+    }
+}
+
+struct Rot13Plugin;
+
+impl RIOPlugin for Rot13Plugin {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &METADATA
+    }
+
+    fn open(&mut self, _uri: &str, _flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        // RIO always opens the backing file first then calls open_backed.
+        Err(IoError::Custom("Expected rot13://[uri]".to_owned()))
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.starts_with("rot13://")
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, flags: IoMode) -> Option<(&'a str, IoMode)> {
+        Some((uri.strip_prefix("rot13://")?, flags))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        backing: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        Ok(RIOPluginDesc {
+            name: uri.to_owned(),
+            perm: flags,
+            raddr: 0,
+            size: backing.size,
+            plugin_operations: Box::new(Rot13Internal {
+                file: backing.plugin_operations,
+            }),
+        })
+    }
+}
+
+fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+    Box::new(Rot13Plugin)
+}
+
+export_rio_plugin!(plugin);
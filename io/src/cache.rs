@@ -0,0 +1,73 @@
+//! In memory write cache that lays over the physical address space of [RIO](crate::RIO).
+
+use alloc::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+/// Bytes written to the physical address space while the cache is enabled. They are kept in
+/// memory instead of reaching the descriptors, so even files opened as read only can be
+/// patched. Reads always see cached bytes whether the cache is enabled or not.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Cache {
+    #[serde(skip)]
+    enabled: bool,
+    bytes: BTreeMap<u64, u8>,
+}
+
+impl Cache {
+    /// Returns true if writes go to the cache instead of the descriptors.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    pub(crate) fn write(&mut self, paddr: u64, buf: &[u8]) {
+        for (addr, byte) in (paddr..).zip(buf) {
+            self.bytes.insert(addr, *byte);
+        }
+    }
+    /// Replace bytes of `buf` read from `paddr` with cached bytes.
+    pub(crate) fn read(&self, paddr: u64, buf: &mut [u8]) {
+        let end = paddr.saturating_add(buf.len() as u64);
+        for (addr, byte) in self.bytes.range(paddr..end) {
+            buf[(addr - paddr) as usize] = *byte;
+        }
+    }
+    /// Same as [`Cache::read`] for data stored in sparce vector.
+    pub(crate) fn read_sparce(&self, paddr: u64, size: u64, data: &mut BTreeMap<u64, u8>) {
+        let end = paddr.saturating_add(size);
+        data.extend(self.bytes.range(paddr..end));
+    }
+    /// Remove cached bytes in physical range `paddr..paddr + size` and return them as
+    /// contiguous ranges.
+    pub(crate) fn take(&mut self, paddr: u64, size: u64) -> Vec<(u64, Vec<u8>)> {
+        let ranges = self.ranges_in(paddr, size);
+        for (addr, data) in &ranges {
+            for addr in *addr..addr + data.len() as u64 {
+                self.bytes.remove(&addr);
+            }
+        }
+        ranges
+    }
+    fn ranges_in(&self, paddr: u64, size: u64) -> Vec<(u64, Vec<u8>)> {
+        let end = paddr.saturating_add(size);
+        let mut ranges: Vec<(u64, Vec<u8>)> = Vec::new();
+        for (addr, byte) in self.bytes.range(paddr..end) {
+            match ranges.last_mut() {
+                Some((start, data)) if *start + data.len() as u64 == *addr => data.push(*byte),
+                _ => ranges.push((*addr, vec![*byte])),
+            }
+        }
+        ranges
+    }
+    /// Contiguous ranges of cached bytes sorted by physical address.
+    #[must_use]
+    pub fn ranges(&self) -> Vec<(u64, Vec<u8>)> {
+        self.ranges_in(0, u64::MAX)
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
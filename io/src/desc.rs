@@ -1,8 +1,10 @@
 //! file descriptor data structure and needed tools to operate on single file.
 
-use crate::plugin::{RIOPlugin, RIOPluginOperations};
+use crate::plugin::{RIOPlugin, RIOPluginOperations, RIORegion};
 use crate::utils::{IoError, IoMode};
+use core::ops::Range;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// This struct represents a file that is opened in [RIO]
 #[derive(Serialize, Deserialize)]
@@ -46,16 +48,91 @@ impl RIODesc {
         let plugin_desc = plugin.open(&self.name, self.perm)?;
         self.plugin_operations = plugin_desc.plugin_operations;
         self.raddr = plugin_desc.raddr;
+        // Volatile files are only resized in memory, so they must be resized again.
+        if self.is_volatile() && plugin_desc.size != self.size {
+            self.plugin_operations.resize(self.raddr + self.size)?;
+        }
+        Ok(())
+    }
+    // Returns true if writes done to this file are lost once it is closed.
+    pub(crate) fn is_volatile(&self) -> bool {
+        self.perm.contains(IoMode::COW) || self.plugin_operations.is_volatile()
+    }
+    pub(crate) fn resize(&mut self, size: u64) -> Result<(), IoError> {
+        self.plugin_operations.resize(self.raddr + size)?;
+        self.size = size;
+        Ok(())
+    }
+    pub(crate) fn insert(&mut self, paddr: u64, data: &[u8]) -> Result<(), IoError> {
+        let raddr = paddr - self.paddr + self.raddr;
+        let len = data.len() as u64;
+        if !self.plugin_operations.insert(raddr as usize, data)? {
+            let mut tail = vec![0; (self.paddr + self.size - paddr) as usize];
+            self.read(paddr as usize, &mut tail)?;
+            self.plugin_operations
+                .resize(self.raddr + self.size + len)?;
+            self.write((paddr + len) as usize, &tail)?;
+            self.write(paddr as usize, data)?;
+        }
+        self.size += len;
+        Ok(())
+    }
+    pub(crate) fn remove(&mut self, paddr: u64, size: u64) -> Result<(), IoError> {
+        let raddr = paddr - self.paddr + self.raddr;
+        if !self
+            .plugin_operations
+            .remove(raddr as usize, size as usize)?
+        {
+            let mut old = vec![0; (self.paddr + self.size - paddr) as usize];
+            self.read(paddr as usize, &mut old)?;
+            self.write(paddr as usize, &old[size as usize..])?;
+            // Put the moved bytes back if the file can't shrink.
+            if let Err(e) = self.plugin_operations.resize(self.raddr + self.size - size) {
+                self.write(paddr as usize, &old)?;
+                return Err(e);
+            }
+        }
+        self.size -= size;
         Ok(())
     }
     pub(crate) fn read(&mut self, paddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
         self.plugin_operations
             .read(paddr - self.paddr as usize + self.raddr as usize, buffer)
     }
+    pub(crate) fn read_sparce(
+        &mut self,
+        paddr: usize,
+        buffer: &mut [u8],
+    ) -> Result<Vec<Range<usize>>, IoError> {
+        self.plugin_operations
+            .read_sparce(paddr - self.paddr as usize + self.raddr as usize, buffer)
+    }
     pub(crate) fn write(&mut self, paddr: usize, buffer: &[u8]) -> Result<(), IoError> {
         self.plugin_operations
             .write(paddr - self.paddr as usize + self.raddr as usize, buffer)
     }
+    pub(crate) fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        self.plugin_operations.commit(path)
+    }
+    /// Returns regions of this file that are mapped to fixed virtual addresses, with their
+    /// `raddr` translated to physical addresses.
+    #[must_use]
+    pub fn regions(&self) -> Vec<RIORegion> {
+        let mut regions = self.plugin_operations.regions();
+        for region in &mut regions {
+            region.raddr = region.raddr - self.raddr + self.paddr;
+        }
+        regions
+    }
+    /// Returns ranges of this file that hold no data, translated to physical addresses.
+    #[must_use]
+    pub fn holes(&self) -> Vec<Range<u64>> {
+        self.plugin_operations
+            .holes()
+            .into_iter()
+            .map(|hole| hole.start - self.raddr + self.paddr..hole.end - self.raddr + self.paddr)
+            .collect()
+    }
     /// Returns URI of current file descriptor.
     #[must_use]
     pub fn name(&self) -> &str {
@@ -121,6 +121,77 @@ impl RIODescQuery {
         self.paddr_to_hndls.insert(lo, hi, hndl);
         Ok(hndl)
     }
+    // Make sure that file with given `hndl` can become `size` bytes without overlapping other
+    // files.
+    fn check_size(&self, hndl: u64, size: u64) -> Result<(), IoError> {
+        if size == 0 {
+            return Err(IoError::Custom("Can not resize file to 0 bytes".to_owned()));
+        }
+        let desc = self.hndl_to_desc(hndl).ok_or(IoError::HndlNotFoundError)?;
+        let hi = desc
+            .paddr
+            .checked_add(size - 1)
+            .ok_or(IoError::AddressesOverlapError)?;
+        if self
+            .paddr_to_hndls
+            .overlap(desc.paddr, hi)
+            .into_iter()
+            .any(|other| *other != hndl)
+        {
+            return Err(IoError::AddressesOverlapError);
+        }
+        Ok(())
+    }
+    // Update physical address range of file with given `hndl` after its size changed.
+    fn reindex(&mut self, hndl: u64, old_size: u64) {
+        let desc = self.hndl_to_desc(hndl).unwrap();
+        let lo = desc.paddr;
+        let hi = desc.paddr + desc.size - 1;
+        self.paddr_to_hndls.delete_envelop(lo, lo + old_size - 1);
+        self.paddr_to_hndls.insert(lo, hi, hndl);
+    }
+    pub(crate) fn resize(&mut self, hndl: u64, size: u64) -> Result<(), IoError> {
+        self.check_size(hndl, size)?;
+        let desc = self.hndl_to_mut_desc(hndl).unwrap();
+        let old_size = desc.size;
+        desc.resize(size)?;
+        self.reindex(hndl, old_size);
+        Ok(())
+    }
+    // Returns the end of the file `data` got inserted into before inserting it, `paddr` can be
+    // the end of a file to append data to it.
+    pub(crate) fn insert(&mut self, paddr: u64, data: &[u8]) -> Result<u64, IoError> {
+        let mut hndls = self.paddr_to_hndls.overlap(paddr, paddr);
+        if hndls.is_empty() && paddr != 0 {
+            hndls = self.paddr_to_hndls.overlap(paddr - 1, paddr - 1);
+        }
+        let hndl = **hndls.first().ok_or(IoError::AddressNotFound)?;
+        let old_size = self.hndl_to_desc(hndl).unwrap().size;
+        self.check_size(hndl, old_size + data.len() as u64)?;
+        let desc = self.hndl_to_mut_desc(hndl).unwrap();
+        let end = desc.paddr + old_size;
+        desc.insert(paddr, data)?;
+        self.reindex(hndl, old_size);
+        Ok(end)
+    }
+    // Returns the end of the file bytes got removed from before removing them.
+    pub(crate) fn remove(&mut self, paddr: u64, size: u64) -> Result<u64, IoError> {
+        let hndl = **self
+            .paddr_to_hndls
+            .overlap(paddr, paddr)
+            .first()
+            .ok_or(IoError::AddressNotFound)?;
+        let desc = self.hndl_to_desc(hndl).unwrap();
+        let old_size = desc.size;
+        let end = desc.paddr + old_size;
+        if paddr.checked_add(size).is_none_or(|hi| hi > end) {
+            return Err(IoError::AddressNotFound);
+        }
+        self.check_size(hndl, old_size - size)?;
+        self.hndl_to_mut_desc(hndl).unwrap().remove(paddr, size)?;
+        self.reindex(hndl, old_size);
+        Ok(end)
+    }
     pub(crate) fn hndl_to_desc(&self, hndl: u64) -> Option<&RIODesc> {
         if hndl >= self.hndl_to_descs.len() as u64 {
             return None;
@@ -512,4 +583,40 @@ mod desc_query_tests {
     fn test_paddr_sparce_range_to_hndl() {
         operate_on_files(&paddr_sparce_range_to_hndl_cb, &[DATA, DATA, DATA, DATA]);
     }
+
+    fn resize_cb(paths: &[&Path]) {
+        let mut p = plugin();
+        let mut descs = RIODescQuery::new();
+        let flags = IoMode::COW;
+        let len = DATA.len() as u64;
+        descs
+            .register_open_at(&mut *p, &paths[0].to_string_lossy(), flags, 0)
+            .unwrap();
+        descs
+            .register_open_at(&mut *p, &paths[1].to_string_lossy(), flags, len + 0x10)
+            .unwrap();
+        assert_eq!(
+            descs.resize(0, 0).err().unwrap(),
+            IoError::Custom("Can not resize file to 0 bytes".to_owned())
+        );
+        assert_eq!(
+            descs.resize(2, 0x10).err().unwrap(),
+            IoError::HndlNotFoundError
+        );
+        assert_eq!(
+            descs.resize(0, len + 0x11).err().unwrap(),
+            IoError::AddressesOverlapError
+        );
+        descs.resize(0, len + 0x10).unwrap();
+        assert_eq!(descs.hndl_to_desc(0).unwrap().size(), len + 0x10);
+        assert_eq!(descs.paddr_range_to_hndl(len, 0x20).unwrap().len(), 2);
+        descs.resize(1, 0x10).unwrap();
+        assert_eq!(descs.hndl_to_desc(1).unwrap().size(), 0x10);
+        assert!(descs.paddr_range_to_hndl(len + 0x20, 1).is_none());
+        assert_eq!(descs.paddr_to_hndls.size(), 2);
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_files(&resize_cb, &[DATA, DATA]);
+    }
 }
@@ -0,0 +1,169 @@
+//! Loading plugins out of shared libraries.
+
+use crate::plugin::{RIOPlugin, RIOPluginDeclaration};
+use crate::utils::IoError;
+use libloading::{Library, Symbol};
+use std::env::consts::DLL_EXTENSION;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Versions a shared library plugin was built with. Plugins hand Rust trait objects over to rair
+/// and Rust has no stable ABI, so a plugin is only accepted if it was built by the very same
+/// compiler against the very same version of the crate that loads it.
+///
+/// This is the first field of every plugin declaration so that it can be checked before anything
+/// else of the declaration is touched.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PluginVersions {
+    /// Version of the interface between the plugin and rair, bumped whenever the declaration or
+    /// any trait it uses changes.
+    pub abi: u32,
+    /// Output of `rustc --version` for the compiler that built the plugin.
+    pub rustc: &'static str,
+    /// Version of the crate the plugin was built against.
+    pub crate_version: &'static str,
+}
+
+impl PluginVersions {
+    /// Refuse plugins that were not built with the versions of `expected`.
+    pub fn check(&self, expected: &PluginVersions) -> Result<(), String> {
+        if self.abi != expected.abi {
+            return Err(format!(
+                "Plugin ABI version {} doesn't match rair's ABI version {}",
+                self.abi, expected.abi
+            ));
+        }
+        if self.rustc != expected.rustc {
+            return Err(format!(
+                "Plugin was built by {} while rair was built by {}",
+                self.rustc, expected.rustc
+            ));
+        }
+        if self.crate_version != expected.crate_version {
+            return Err(format!(
+                "Plugin was built against version {} while rair uses version {}",
+                self.crate_version, expected.crate_version
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Static exported by shared library plugins.
+pub trait PluginDeclaration {
+    /// Name of the exported static.
+    const SYMBOL: &'static str;
+    /// Versions the plugin must be built with.
+    const VERSIONS: PluginVersions;
+    /// Versions the plugin was built with.
+    fn versions(&self) -> &PluginVersions;
+}
+
+/// Load the shared library at `path` and hand the declaration `T` it exports over to `f` once its
+/// versions are checked. The library must outlive whatever `f` creates.
+pub fn load_plugin_library<T, R, F>(path: &Path, f: F) -> Result<(Library, R), String>
+where
+    T: PluginDeclaration,
+    F: FnOnce(&T) -> R,
+{
+    let library = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+    let loaded = {
+        let symbol: Symbol<*const T> =
+            unsafe { library.get(T::SYMBOL.as_bytes()) }.map_err(|e| e.to_string())?;
+        let declaration = unsafe { &**symbol };
+        declaration.versions().check(&T::VERSIONS)?;
+        f(declaration)
+    };
+    Ok((library, loaded))
+}
+
+/// Sorted paths of all shared libraries in `dir`, a missing directory has none.
+#[must_use]
+pub fn plugin_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == DLL_EXTENSION))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Load shared library at `path` and create the RIO plugin it exports.
+pub(crate) fn load(path: &Path) -> Result<(Library, Box<dyn RIOPlugin + Sync + Send>), IoError> {
+    load_plugin_library(path, |declaration: &RIOPluginDeclaration| {
+        (declaration.plugin)()
+    })
+    .map_err(|e| IoError::Custom(format!("Failed to load plugin {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod test_dylib {
+    use super::*;
+    use crate::plugin::RIO_PLUGIN_VERSIONS;
+
+    #[test]
+    fn test_check() {
+        let mut versions = RIO_PLUGIN_VERSIONS;
+        assert_eq!(versions.check(&RIO_PLUGIN_VERSIONS), Ok(()));
+        versions.crate_version = "0.0.0";
+        assert_eq!(
+            versions.check(&RIO_PLUGIN_VERSIONS).err().unwrap(),
+            format!(
+                "Plugin was built against version 0.0.0 while rair uses version {}",
+                RIO_PLUGIN_VERSIONS.crate_version
+            )
+        );
+        versions.rustc = "rustc 0.0.0";
+        assert_eq!(
+            versions.check(&RIO_PLUGIN_VERSIONS).err().unwrap(),
+            format!(
+                "Plugin was built by rustc 0.0.0 while rair was built by {}",
+                RIO_PLUGIN_VERSIONS.rustc
+            )
+        );
+        versions.abi = 0;
+        assert_eq!(
+            versions.check(&RIO_PLUGIN_VERSIONS).err().unwrap(),
+            format!(
+                "Plugin ABI version 0 doesn't match rair's ABI version {}",
+                RIO_PLUGIN_VERSIONS.abi
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let path = Path::new("file_that_doesnt_exist.so");
+        assert!(load(path)
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("Failed to load plugin file_that_doesnt_exist.so: "));
+        #[cfg(target_os = "linux")]
+        assert!(load(Path::new("libc.so.6"))
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("undefined symbol: rair_io_plugin"));
+    }
+
+    #[test]
+    fn test_plugin_files() {
+        assert!(plugin_files(Path::new("dir_that_doesnt_exist")).is_empty());
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b", "a", "c"] {
+            fs::write(dir.path().join(format!("{name}.{DLL_EXTENSION}")), "").unwrap();
+        }
+        fs::write(dir.path().join("readme.txt"), "").unwrap();
+        let names: Vec<_> = plugin_files(dir.path())
+            .iter()
+            .map(|path| path.file_stem().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+}
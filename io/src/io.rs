@@ -1,13 +1,20 @@
 //! RIO main implementation.
 
+use crate::cache::Cache;
 use crate::desc::RIODesc;
 use crate::descquery::RIODescQuery;
+use crate::dylib;
+use crate::journal::{Journal, Patch};
 use crate::mapsquery::{RIOMap, RIOMapQuery};
-use crate::plugin::RIOPlugin;
-use crate::plugins;
-use crate::utils::{IoError, IoMode};
+use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations};
+use crate::plugins::{self, archive, archive::ArchiveMember};
+use crate::utils::{IoError, IoMode, MapPerm, Shift};
 use alloc::{collections::BTreeMap, sync::Arc};
+use core::mem;
+use libloading::Library;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 // Credits goes to @Talchas#7429 for the idea of using remote
 // to create something that behaves as finalize_hook() for
@@ -24,13 +31,137 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 /// [RIO] is abstraction over IO, It allows you to open (more than one) file each with its own
 /// encoding in one address space, which you can read / write or map parts of without knowing
 /// which file is really being accessed
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(remote = "RIO")]
 pub struct RIO {
     descs: RIODescQuery,
+    // maps of the current bank.
     maps: RIOMapQuery,
+    #[serde(default = "default_bank")]
+    bank: String,
+    // maps of every other bank.
+    #[serde(default)]
+    banks: BTreeMap<String, RIOMapQuery>,
+    #[serde(default)]
+    journal: Journal,
+    #[serde(default)]
+    cache: Cache,
+    // shared libraries plugins got loaded from, so that projects can load them again.
+    #[serde(default)]
+    plugin_files: Vec<PathBuf>,
     #[serde(skip)]
     plugins: Vec<Box<dyn RIOPlugin + Sync + Send>>,
+    // must be dropped after every plugin and descriptor that came out of them.
+    #[serde(skip)]
+    libraries: Vec<Library>,
+}
+
+fn default_bank() -> String {
+    "default".to_owned()
+}
+
+impl Default for RIO {
+    fn default() -> Self {
+        RIO {
+            descs: RIODescQuery::default(),
+            maps: RIOMapQuery::default(),
+            bank: default_bank(),
+            banks: BTreeMap::new(),
+            journal: Journal::default(),
+            cache: Cache::default(),
+            plugin_files: Vec::new(),
+            plugins: Vec::new(),
+            libraries: Vec::new(),
+        }
+    }
+}
+
+const LAYERS_METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Layers",
+    desc: "Opens URIs with the first plugin that accepts them, URIs layered over other URIs \
+           get their backing data opened through the same plugins first.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+/// Backing data that does not start at real address 0, it is shifted so that layered plugins
+/// always see their backing data starting at 0.
+struct Shifted {
+    raddr: usize,
+    operations: Box<dyn RIOPluginOperations + Sync + Send>,
+}
+
+impl RIOPluginOperations for Shifted {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        self.operations.read(raddr + self.raddr, buffer)
+    }
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        self.operations.write(raddr + self.raddr, buffer)
+    }
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        self.operations.commit(path)
+    }
+    fn commit_data(&mut self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        self.operations.commit_data(path, data)
+    }
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        self.operations.resize(end + self.raddr as u64)
+    }
+    fn insert(&mut self, raddr: usize, data: &[u8]) -> Result<bool, IoError> {
+        self.operations.insert(raddr + self.raddr, data)
+    }
+    fn remove(&mut self, raddr: usize, size: usize) -> Result<bool, IoError> {
+        self.operations.remove(raddr + self.raddr, size)
+    }
+}
+
+/// All loaded plugins acting as a single plugin that can open nested URIs such as
+/// `b64://gzip://file`.
+struct Layers<'a>(&'a mut [Box<dyn RIOPlugin + Sync + Send>]);
+
+impl Layers<'_> {
+    fn find(&mut self, uri: &str) -> Result<&mut Box<dyn RIOPlugin + Sync + Send>, IoError> {
+        self.0
+            .iter_mut()
+            .find(|plugin| plugin.accept_uri(uri))
+            .ok_or(IoError::IoPluginNotFoundError)
+    }
+    /// Path of the file on disk at the bottom of all layers of `uri`.
+    fn path(&mut self, uri: &str) -> Result<String, IoError> {
+        let plugin = self.find(uri)?;
+        if let Some((backing, _)) = plugin.backing_uri(uri, IoMode::READ) {
+            return self.path(backing);
+        }
+        Ok(uri
+            .split_once("://")
+            .map_or(uri, |(_, path)| path)
+            .to_owned())
+    }
+}
+
+impl RIOPlugin for Layers<'_> {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &LAYERS_METADATA
+    }
+    fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        let plugin = self.find(uri)?;
+        let Some((backing_uri, backing_flags)) = plugin.backing_uri(uri, flags) else {
+            return plugin.open(uri, flags);
+        };
+        let mut backing = self.open(backing_uri, backing_flags)?;
+        if backing.raddr != 0 {
+            backing.plugin_operations = Box::new(Shifted {
+                raddr: backing.raddr as usize,
+                operations: backing.plugin_operations,
+            });
+            backing.raddr = 0;
+        }
+        self.find(uri)?.open_backed(uri, flags, backing)
+    }
+    fn accept_uri(&self, uri: &str) -> bool {
+        self.0.iter().any(|plugin| plugin.accept_uri(uri))
+    }
 }
 
 impl Serialize for RIO {
@@ -49,19 +180,14 @@ impl<'de> Deserialize<'de> for RIO {
     {
         let mut io = RIO::deserialize(deserializer)?;
         plugins::load_plugins(&mut io);
+        for path in mem::take(&mut io.plugin_files) {
+            io.load_plugin_file(&path).map_err(de::Error::custom)?;
+        }
+        let mut layers = Layers(&mut io.plugins);
         for desc in &mut io.descs {
-            let mut found = false;
-            for plugin in &mut io.plugins {
-                if plugin.accept_uri(&desc.name) {
-                    desc.reopen(&mut **plugin).map_err(de::Error::custom)?;
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
-                return Err(de::Error::custom(IoError::IoPluginNotFoundError));
-            }
+            desc.reopen(&mut layers).map_err(de::Error::custom)?;
         }
+        io.replay_journal().map_err(de::Error::custom)?;
         Ok(io)
     }
 }
@@ -81,11 +207,57 @@ impl RIO {
         io
     }
 
-    /// THIS FUNCTION IS NOT SUPPOSED TO BE THAT TRIVIAL
-    /// I WANT IT TO LITERALLY OPEN A PLUGIN FILE
+    /// Register a plugin that is already loaded in memory, see [`RIO::load_plugin_file`] for
+    /// loading plugins out of shared libraries.
     pub fn load_plugin(&mut self, plugin: Box<dyn RIOPlugin + Sync + Send>) {
         self.plugins.push(plugin);
     }
+
+    /// Load the plugin exported using [`export_rio_plugin`] from the shared library at `path`.
+    /// Projects remember which files plugins came from and load them again.
+    ///
+    /// [`export_rio_plugin`]: crate::export_rio_plugin
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rair_io::{RIO, IoError};
+    /// use std::path::Path;
+    /// fn main() -> Result<(), IoError> {
+    ///     let mut io = RIO::new();
+    ///     io.load_plugin_file(Path::new("libmyplugin.so"))?;
+    ///     return Ok(());
+    /// }
+    /// ```
+    pub fn load_plugin_file(&mut self, path: &Path) -> Result<&'static RIOPluginMetadata, IoError> {
+        let path = fs::canonicalize(path)?;
+        if self.plugin_files.contains(&path) {
+            return Err(IoError::Custom(format!(
+                "Plugin {} is already loaded",
+                path.display()
+            )));
+        }
+        let (library, plugin) = dylib::load(&path)?;
+        let metadata = plugin.get_metadata();
+        self.plugins.push(plugin);
+        self.libraries.push(library);
+        self.plugin_files.push(path);
+        Ok(metadata)
+    }
+
+    /// Load every shared library in `dir` using [`RIO::load_plugin_file`], a missing directory
+    /// is not an error. Returns errors of plugins that failed to load.
+    pub fn load_plugin_dir(&mut self, dir: &Path) -> Vec<IoError> {
+        dylib::plugin_files(dir)
+            .iter()
+            .filter_map(|path| self.load_plugin_file(path).err())
+            .collect()
+    }
+
+    /// Metadata of all loaded plugins in the order they get asked to open URIs.
+    pub fn plugins(&self) -> impl Iterator<Item = &'static RIOPluginMetadata> + '_ {
+        self.plugins.iter().map(|plugin| plugin.get_metadata())
+    }
     /// Allows us to open file and have it accessable from out physical address space,
     /// *open* will automatically load the file in the smallest available physical address while
     /// [`RIO::open_at`] will allow user to determine what physical address to use. `uri` is
@@ -105,15 +277,29 @@ impl RIO {
     /// io.open("hello.txt", IoMode::READ);
     /// ```
     pub fn open(&mut self, uri: &str, flags: IoMode) -> Result<u64, IoError> {
-        for plugin in &mut self.plugins {
-            if plugin.accept_uri(uri) {
-                if let Ok(hndl) = self.descs.register_open_default(&mut **plugin, uri, flags) {
-                    return Ok(hndl);
-                }
-                return self.descs.register_open(&mut **plugin, uri, flags);
+        let mut layers = Layers(&mut self.plugins);
+        if !layers.accept_uri(uri) {
+            return Err(IoError::IoPluginNotFoundError);
+        }
+        let hndl = match self.descs.register_open_default(&mut layers, uri, flags) {
+            Ok(hndl) => hndl,
+            Err(_) => self.descs.register_open(&mut layers, uri, flags)?,
+        };
+        self.map_regions(hndl)
+    }
+
+    /// Map all regions that the file opened as `hndl` reports into virtual address space.
+    /// If any of them fails to be mapped the file gets closed again.
+    fn map_regions(&mut self, hndl: u64) -> Result<u64, IoError> {
+        let regions = self.descs.hndl_to_desc(hndl).unwrap().regions();
+        for region in regions {
+            let (paddr, vaddr, size) = (region.raddr, region.vaddr, region.size);
+            if let Err(e) = self.map_named(paddr, vaddr, size, &region.name, MapPerm::all()) {
+                self.close(hndl)?;
+                return Err(e);
             }
         }
-        Err(IoError::IoPluginNotFoundError)
+        Ok(hndl)
     }
 
     /// Allows us to open file and have it accessable from out physical address space
@@ -136,12 +322,36 @@ impl RIO {
     /// }
     /// ```
     pub fn open_at(&mut self, uri: &str, flags: IoMode, at: u64) -> Result<u64, IoError> {
-        for plugin in &mut self.plugins {
-            if plugin.accept_uri(uri) {
-                return self.descs.register_open_at(&mut **plugin, uri, flags, at);
-            }
+        let mut layers = Layers(&mut self.plugins);
+        if !layers.accept_uri(uri) {
+            return Err(IoError::IoPluginNotFoundError);
         }
-        Err(IoError::IoPluginNotFoundError)
+        let hndl = self.descs.register_open_at(&mut layers, uri, flags, at)?;
+        self.map_regions(hndl)
+    }
+
+    /// List members of the zip or tar archive at `uri` without opening it, every member
+    /// can later be opened using [`RIO::open`] or [`RIO::open_at`] with its
+    /// [`ArchiveMember::uri`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rair_io::{RIO, IoMode, IoError};
+    /// fn main() -> Result<(), IoError> {
+    ///     let mut io = RIO::new();
+    ///     for member in io.archive_members("firmware.zip")? {
+    ///         io.open(&member.uri, IoMode::READ)?;
+    ///     }
+    ///     return Ok(());
+    /// }
+    /// ```
+    pub fn archive_members(&mut self, uri: &str) -> Result<Vec<ArchiveMember>, IoError> {
+        let mut desc = Layers(&mut self.plugins).open(uri, IoMode::READ)?;
+        let mut data = vec![0; desc.size as usize];
+        desc.plugin_operations
+            .read(desc.raddr as usize, &mut data)?;
+        archive::members(uri, data)
     }
 
     /// Close an opened file, delete its physical and virtual address space.
@@ -162,11 +372,129 @@ impl RIO {
     /// ```
 
     pub fn close(&mut self, hndl: u64) -> Result<(), IoError> {
+        let range = self
+            .descs
+            .hndl_to_desc(hndl)
+            .map(|desc| (desc.paddr_base(), desc.size()));
         // delete all memory mappings related to the closed handle
         self.descs.close(hndl)?;
+        if let Some((paddr, size)) = range {
+            self.journal.forget(paddr, size);
+            self.cache.take(paddr, size);
+        }
+        Ok(())
+    }
+
+    /// Resize an opened file to `size` bytes, a file can only grow into free physical address
+    /// space. Copy-On-Write files are resized in memory only. Undo history and cached writes
+    /// of truncated bytes are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rair_io::RIO;
+    /// use rair_io::IoMode;
+    /// use rair_io::IoError;
+    /// fn main() -> Result<(), IoError> {
+    ///     let mut io = RIO::new();
+    ///     let hndl = io.open("hello.txt", IoMode::READ | IoMode::WRITE)?;
+    ///     io.resize(hndl, 0x100)?;
+    ///     return Ok(());
+    /// }
+    /// ```
+    pub fn resize(&mut self, hndl: u64, size: u64) -> Result<(), IoError> {
+        let desc = self
+            .descs
+            .hndl_to_desc(hndl)
+            .ok_or(IoError::HndlNotFoundError)?;
+        let paddr = desc.paddr_base();
+        let old_size = desc.size();
+        self.descs.resize(hndl, size)?;
+        if size < old_size {
+            self.journal.forget(paddr + size, old_size - size);
+            self.cache.take(paddr + size, old_size - size);
+        }
         Ok(())
     }
 
+    /// Insert `data` at `paddr` moving the rest of the file forward, `paddr` can also be the
+    /// end of a file to append `data` to it. The file grows so it must not run into the file
+    /// after it. If `shift_maps` is set, maps pointing at moved bytes follow them in all banks.
+    /// Returns how physical addresses moved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rair_io::RIO;
+    /// use rair_io::IoMode;
+    /// use rair_io::IoError;
+    /// fn main() -> Result<(), IoError> {
+    ///     let mut io = RIO::new();
+    ///     io.open("hello.txt", IoMode::READ | IoMode::WRITE)?;
+    ///     io.insert(0x10, &[0xde, 0xad], true)?;
+    ///     return Ok(());
+    /// }
+    /// ```
+    pub fn insert(&mut self, paddr: u64, data: &[u8], shift_maps: bool) -> Result<Shift, IoError> {
+        let end = self.descs.insert(paddr, data)?;
+        let shift = Shift {
+            paddr,
+            end,
+            removed: 0,
+            inserted: data.len() as u64,
+        };
+        self.shift(shift, shift_maps);
+        Ok(shift)
+    }
+
+    /// Remove `size` bytes at `paddr` moving the rest of the file backward, the removed bytes
+    /// must all belong to the same file. If `shift_maps` is set, maps pointing at moved bytes
+    /// follow them in all banks. Returns how physical addresses moved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rair_io::RIO;
+    /// use rair_io::IoMode;
+    /// use rair_io::IoError;
+    /// fn main() -> Result<(), IoError> {
+    ///     let mut io = RIO::new();
+    ///     io.open("hello.txt", IoMode::READ | IoMode::WRITE)?;
+    ///     io.remove(0x10, 2, true)?;
+    ///     return Ok(());
+    /// }
+    /// ```
+    pub fn remove(&mut self, paddr: u64, size: u64, shift_maps: bool) -> Result<Shift, IoError> {
+        let end = self.descs.remove(paddr, size)?;
+        let shift = Shift {
+            paddr,
+            end,
+            removed: size,
+            inserted: 0,
+        };
+        self.shift(shift, shift_maps);
+        Ok(shift)
+    }
+
+    // Move undo history and cached writes after bytes of a file moved, undo history of moved
+    // bytes is dropped while cached bytes move along.
+    fn shift(&mut self, shift: Shift, shift_maps: bool) {
+        let new_end = shift.end - shift.removed + shift.inserted;
+        self.journal
+            .forget(shift.paddr, shift.end.max(new_end) - shift.paddr);
+        self.cache.take(shift.paddr, shift.removed);
+        let tail = shift.paddr + shift.removed;
+        for (paddr, data) in self.cache.take(tail, shift.end - tail) {
+            self.cache.write(shift.apply(paddr), &data);
+        }
+        if shift_maps {
+            self.maps.move_paddrs(|paddr| shift.apply(paddr));
+            for maps in self.banks.values_mut() {
+                maps.move_paddrs(|paddr| shift.apply(paddr));
+            }
+        }
+    }
+
     /// Close all open files, and reset all virtual and physical address spaces.
     ///
     /// # Example
@@ -186,7 +514,11 @@ impl RIO {
 
     pub fn close_all(&mut self) {
         self.maps = RIOMapQuery::new();
+        self.bank = default_bank();
+        self.banks = BTreeMap::new();
         self.descs = RIODescQuery::new();
+        self.journal = Journal::default();
+        self.cache.take(0, u64::MAX);
     }
 
     /// Read from the physical address space of current [RIO] object. If there is no enough
@@ -214,6 +546,7 @@ impl RIO {
                 )?;
                 start += size;
             }
+            self.cache.read(paddr, buf);
             Ok(())
         } else {
             Err(IoError::AddressNotFound)
@@ -237,11 +570,13 @@ impl RIO {
         for (hndl, paddr, size) in ranges {
             let desc = self.descs.hndl_to_mut_desc(hndl).unwrap();
             let mut buffer = vec![0; size as usize];
-            desc.read(paddr as usize, &mut buffer)?;
-            for (i, v) in buffer.iter().enumerate() {
-                result.insert(paddr + i as u64, *v);
+            for range in desc.read_sparce(paddr as usize, &mut buffer)? {
+                for i in range {
+                    result.insert(paddr + i as u64, buffer[i]);
+                }
             }
         }
+        self.cache.read_sparce(paddr, size, &mut result);
         Ok(result)
     }
     /// Write into the physical address space of current [RIO] object. If there is no enough
@@ -258,6 +593,67 @@ impl RIO {
     /// io.pwrite(0x20, &fillme);
     /// ```
     pub fn pwrite(&mut self, paddr: u64, buf: &[u8]) -> Result<(), IoError> {
+        let patch = self.patch(paddr, buf)?;
+        self.journal.record(vec![patch]);
+        Ok(())
+    }
+    /// Write `buf` at `paddr` and return the overwritten bytes.
+    fn patch(&mut self, paddr: u64, buf: &[u8]) -> Result<Patch, IoError> {
+        // Writes reaching the descriptors replace cached bytes, so the overwritten bytes are
+        // the ones from the descriptors.
+        let stale = if self.cache.is_enabled() {
+            Vec::new()
+        } else {
+            self.cache.take(paddr, buf.len() as u64)
+        };
+        let mut old = vec![0; buf.len()];
+        let result = self
+            .pread(paddr, &mut old)
+            .and_then(|()| self.raw_pwrite(paddr, buf));
+        if let Err(e) = result {
+            for (paddr, data) in stale {
+                self.cache.write(paddr, &data);
+            }
+            return Err(e);
+        }
+        Ok(Patch {
+            paddr,
+            old,
+            new: buf.to_vec(),
+        })
+    }
+    /// Same as [`RIO::pwrite`] without recording the write in the journal.
+    fn raw_pwrite(&mut self, paddr: u64, buf: &[u8]) -> Result<(), IoError> {
+        let result = self.descs.paddr_range_to_hndl(paddr, buf.len() as u64);
+        if result.is_some() && self.cache.is_enabled() {
+            self.cache.write(paddr, buf);
+            return Ok(());
+        }
+        self.desc_pwrite(paddr, buf)?;
+        self.cache.take(paddr, buf.len() as u64);
+        Ok(())
+    }
+    // Volatile files are reopened with their original content, so writes that are applied
+    // according to the journal are done again.
+    fn replay_journal(&mut self) -> Result<(), IoError> {
+        let patches: Vec<Patch> = self.journal.applied().flatten().cloned().collect();
+        for patch in patches {
+            let size = patch.new.len() as u64;
+            let Some(operations) = self.descs.paddr_range_to_hndl(patch.paddr, size) else {
+                continue;
+            };
+            for (hndl, paddr, size) in operations {
+                let desc = self.descs.hndl_to_mut_desc(hndl).unwrap();
+                if desc.is_volatile() {
+                    let start = (paddr - patch.paddr) as usize;
+                    desc.write(paddr as usize, &patch.new[start..start + size as usize])?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Write `buf` directly into the descriptors, bypassing the cache.
+    fn desc_pwrite(&mut self, paddr: u64, buf: &[u8]) -> Result<(), IoError> {
         let result = self.descs.paddr_range_to_hndl(paddr, buf.len() as u64);
         if let Some(operations) = result {
             let mut start = 0;
@@ -282,11 +678,156 @@ impl RIO {
         self.maps.map(paddr, vaddr, size)
     }
 
-    /// unmap already mapped regions
+    /// Same as [`RIO::map`] except that the map is given a `name` and permissions. Writes to
+    /// maps without [`MapPerm::WRITE`] through [`RIO::vwrite`] are refused.
+    pub fn map_named(
+        &mut self,
+        paddr: u64,
+        vaddr: u64,
+        size: u64,
+        name: &str,
+        perm: MapPerm,
+    ) -> Result<(), IoError> {
+        self.add_map(RIOMap {
+            paddr,
+            vaddr,
+            size,
+            name: name.to_owned(),
+            perm,
+            priority: 0,
+        })
+    }
+
+    /// Add `map` to the current bank. Maps can overlap other maps only if they have different
+    /// [`RIOMap::priority`], in which case the map with higher priority hides the others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rair_io::{IoMode, RIOMap, RIO};
+    /// let mut io = RIO::new();
+    /// io.open("malloc://0x200", IoMode::READ | IoMode::WRITE).unwrap();
+    /// io.pwrite(0x100, &[0xff; 0x100]).unwrap();
+    /// io.map(0x0, 0x1000, 0x100).unwrap();
+    /// let overlay = RIOMap {
+    ///     paddr: 0x100,
+    ///     vaddr: 0x1080,
+    ///     size: 0x10,
+    ///     priority: 1,
+    ///     ..Default::default()
+    /// };
+    /// io.add_map(overlay).unwrap();
+    /// let mut data = [0; 4];
+    /// io.vread(0x107e, &mut data).unwrap();
+    /// assert_eq!(data, [0, 0, 0xff, 0xff]);
+    /// ```
+    pub fn add_map(&mut self, map: RIOMap) -> Result<(), IoError> {
+        if self
+            .descs
+            .paddr_range_to_hndl(map.paddr, map.size)
+            .is_none()
+        {
+            return Err(IoError::AddressNotFound);
+        }
+        self.maps.add_map(map)
+    }
+
+    /// Remove `map` from the current bank as a whole, even if it is hidden under maps of higher
+    /// priority.
+    pub fn remove_map(&mut self, map: &RIOMap) -> Result<(), IoError> {
+        self.maps.remove_map(map)
+    }
+
+    /// unmap already mapped regions, maps hidden under the unmapped regions become visible.
     pub fn unmap(&mut self, vaddr: u64, size: u64) -> Result<(), IoError> {
         self.maps.unmap(vaddr, size)
     }
 
+    /// Name of the current bank. Each bank is a separate virtual address space, maps are
+    /// added to and looked up in the current bank only.
+    #[must_use]
+    pub fn bank(&self) -> &str {
+        &self.bank
+    }
+
+    /// Names of all banks sorted.
+    #[must_use]
+    pub fn banks(&self) -> Vec<&str> {
+        let mut banks: Vec<&str> = self.banks.keys().map(String::as_str).collect();
+        banks.push(&self.bank);
+        banks.sort_unstable();
+        banks
+    }
+
+    /// Create a new bank with no maps.
+    pub fn add_bank(&mut self, name: &str) -> Result<(), IoError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(IoError::Custom(format!("Invalid bank name `{name}`")));
+        }
+        if name == self.bank || self.banks.contains_key(name) {
+            return Err(IoError::Custom(format!("Bank {name} already exists")));
+        }
+        self.banks.insert(name.to_owned(), RIOMapQuery::new());
+        Ok(())
+    }
+
+    /// Remove bank `name` along with all of its maps. The current bank can't be removed.
+    pub fn remove_bank(&mut self, name: &str) -> Result<(), IoError> {
+        if name == self.bank {
+            return Err(IoError::Custom(format!(
+                "Can not remove current bank {name}"
+            )));
+        }
+        self.banks
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| IoError::Custom(format!("Bank {name} does not exist")))
+    }
+
+    /// Make `name` the current bank.
+    pub fn switch_bank(&mut self, name: &str) -> Result<(), IoError> {
+        if name == self.bank {
+            return Ok(());
+        }
+        let Some(maps) = self.banks.remove(name) else {
+            return Err(IoError::Custom(format!("Bank {name} does not exist")));
+        };
+        let old = mem::replace(&mut self.maps, maps);
+        let old_name = mem::replace(&mut self.bank, name.to_owned());
+        self.banks.insert(old_name, old);
+        Ok(())
+    }
+
+    /// Run `f` with `bank` as the current bank, then switch back. This allows addressing
+    /// banks explicitly without switching to them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rair_io::{IoMode, RIO};
+    /// let mut io = RIO::new();
+    /// io.open("malloc://0x200", IoMode::READ | IoMode::WRITE).unwrap();
+    /// io.pwrite(0x100, &[0xff; 0x100]).unwrap();
+    /// io.map(0x0, 0x1000, 0x100).unwrap();
+    /// io.add_bank("data").unwrap();
+    /// io.with_bank("data", |io| io.map(0x100, 0x1000, 0x100)).unwrap();
+    /// let mut data = [0; 2];
+    /// io.with_bank("data", |io| io.vread(0x1000, &mut data)).unwrap();
+    /// assert_eq!(data, [0xff, 0xff]);
+    /// io.vread(0x1000, &mut data).unwrap();
+    /// assert_eq!(data, [0, 0]);
+    /// ```
+    pub fn with_bank<T, F>(&mut self, bank: &str, f: F) -> Result<T, IoError>
+    where
+        F: FnOnce(&mut RIO) -> Result<T, IoError>,
+    {
+        let current = self.bank.clone();
+        self.switch_bank(bank)?;
+        let result = f(self);
+        self.switch_bank(&current)?;
+        result
+    }
+
     /// read memory from virtual address space. If there is no enough
     /// data to fill *buf* an error is returned.
     pub fn vread(&mut self, vaddr: u64, buf: &mut [u8]) -> Result<(), IoError> {
@@ -312,10 +853,8 @@ impl RIO {
         let mut result = BTreeMap::new();
         let maps = self.maps.split_vaddr_sparce_range(vaddr, size);
         for map in maps {
-            let mut buf = vec![0; map.size as usize];
-            self.pread(map.paddr, &mut buf)?;
-            for (i, v) in buf.iter().enumerate() {
-                result.insert(map.vaddr + i as u64, *v);
+            for (paddr, v) in self.pread_sparce(map.paddr, map.size)? {
+                result.insert(paddr - map.paddr + map.vaddr, v);
             }
         }
         Ok(result)
@@ -324,17 +863,124 @@ impl RIO {
     pub fn vwrite(&mut self, vaddr: u64, buf: &[u8]) -> Result<(), IoError> {
         let result = self.maps.split_vaddr_range(vaddr, buf.len() as u64);
         if let Some(maps) = result {
+            if let Some(map) = maps.iter().find(|map| !map.perm.contains(MapPerm::WRITE)) {
+                return Err(IoError::MapNotWritableError(map.vaddr));
+            }
             let mut start = 0;
+            let mut entry = Vec::new();
             for map in maps {
-                self.pwrite(map.paddr, &buf[start as usize..(start + map.size) as usize])?;
+                let data = &buf[start as usize..(start + map.size) as usize];
+                match self.patch(map.paddr, data) {
+                    Ok(patch) => entry.push(patch),
+                    Err(e) => {
+                        self.journal.record(entry);
+                        return Err(e);
+                    }
+                }
                 start += map.size;
             }
+            self.journal.record(entry);
             Ok(())
         } else {
             Err(IoError::AddressNotFound)
         }
     }
 
+    /// Revert the most recent write recorded in the journal. `false` is
+    /// returned if there is nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, IoError> {
+        let Some(entry) = self.journal.pop_undo() else {
+            return Ok(false);
+        };
+        let result = entry
+            .iter()
+            .rev()
+            .try_for_each(|patch| self.raw_pwrite(patch.paddr, &patch.old));
+        if let Err(e) = result {
+            self.journal.push_undo(entry);
+            return Err(e);
+        }
+        self.journal.push_redo(entry);
+        Ok(true)
+    }
+
+    /// Reapply the most recently undone write. `false` is returned if there
+    /// is nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, IoError> {
+        let Some(entry) = self.journal.pop_redo() else {
+            return Ok(false);
+        };
+        let result = entry
+            .iter()
+            .try_for_each(|patch| self.raw_pwrite(patch.paddr, &patch.new));
+        if let Err(e) = result {
+            self.journal.push_redo(entry);
+            return Err(e);
+        }
+        self.journal.push_undo(entry);
+        Ok(true)
+    }
+
+    /// Enable or disable the write cache. While enabled, writes to the physical address space
+    /// are kept in memory instead of being written into the descriptors, this allows writing
+    /// to files opened as read only. Cached bytes are still visible after disabling the cache
+    /// until they are discarded, committed or overwritten by writes that reach the descriptors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rair_io::{IoMode, RIO};
+    /// let mut io = RIO::new();
+    /// io.open("malloc://0x10", IoMode::READ | IoMode::WRITE).unwrap();
+    /// io.set_cache(true);
+    /// io.pwrite(0x0, &[1, 2]).unwrap();
+    /// let mut data = [0; 3];
+    /// io.pread(0x0, &mut data).unwrap();
+    /// assert_eq!(data, [1, 2, 0]);
+    /// io.discard_cache(0x0, 0x10);
+    /// io.pread(0x0, &mut data).unwrap();
+    /// assert_eq!(data, [0, 0, 0]);
+    /// ```
+    pub fn set_cache(&mut self, enabled: bool) {
+        self.cache.set_enabled(enabled);
+    }
+
+    /// Write cache laid over the physical address space, see [`RIO::set_cache`].
+    #[must_use]
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// Drop cached bytes in physical range `paddr..paddr + size`, writes recorded in the
+    /// journal that touch dropped bytes are forgotten.
+    pub fn discard_cache(&mut self, paddr: u64, size: u64) {
+        for (paddr, data) in self.cache.take(paddr, size) {
+            self.journal.forget(paddr, data.len() as u64);
+        }
+    }
+
+    /// Write cached bytes in physical range `paddr..paddr + size` into the descriptors and drop
+    /// them from the cache. Bytes that fail to be written stay cached.
+    pub fn commit_cache(&mut self, paddr: u64, size: u64) -> Result<(), IoError> {
+        let mut ranges = self.cache.take(paddr, size).into_iter();
+        while let Some((paddr, data)) = ranges.next() {
+            if let Err(e) = self.desc_pwrite(paddr, &data) {
+                self.cache.write(paddr, &data);
+                for (paddr, data) in ranges {
+                    self.cache.write(paddr, &data);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// History of writes that can be undone or redone.
+    #[must_use]
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
     /// convert virtual address to physical address
     #[must_use]
     pub fn vir_to_phy(&self, vaddr: u64, size: u64) -> Option<Vec<RIOMap>> {
@@ -364,13 +1010,39 @@ impl RIO {
     pub fn hndl_to_desc(&self, hndl: u64) -> Option<&RIODesc> {
         self.descs.hndl_to_desc(hndl)
     }
+
+    /// Persist changes done to file opened as [`IoMode::COW`] with handle `hndl`. Data is
+    /// written to `path` using the encoding of the plugin that opened the file, if `path`
+    /// is `None` the original file is overwritten instead.
+    pub fn commit(&mut self, hndl: u64, path: Option<&Path>) -> Result<(), IoError> {
+        let Some(desc) = self.descs.hndl_to_mut_desc(hndl) else {
+            return Err(IoError::HndlNotFoundError);
+        };
+        if !desc.perm.contains(IoMode::COW) {
+            return Err(IoError::Custom(
+                "File is not opened in Copy-On-Write mode".to_owned(),
+            ));
+        }
+        if let Some(path) = path {
+            return desc.commit(path);
+        }
+        let name = desc.name.clone();
+        let path = Layers(&mut self.plugins).path(&name)?;
+        let desc = self.descs.hndl_to_mut_desc(hndl).unwrap();
+        desc.commit(Path::new(&path))
+    }
 }
 
 #[cfg(test)]
 mod rio_tests {
 
     use super::*;
-    use std::io;
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::env::consts::DLL_EXTENSION;
+    use std::fs;
+    use std::io::{self, Read, Write};
     use std::path::Path;
     use test_file::*;
     fn test_failing_open_cb(path: &[&Path]) {
@@ -530,16 +1202,19 @@ mod rio_tests {
                 paddr: 0x1000,
                 vaddr: 0x400,
                 size: DATA.len() as u64,
+                ..Default::default()
             },
             RIOMap {
                 paddr: 0x2000,
                 vaddr: 0x400 + DATA.len() as u64,
                 size: DATA.len() as u64,
+                ..Default::default()
             },
             RIOMap {
                 paddr: 0x3000,
                 vaddr: 0x400 + DATA.len() as u64 * 2,
                 size: DATA.len() as u64,
+                ..Default::default()
             },
         ];
         assert_eq!(io.vir_to_phy(0x400, DATA.len() as u64 * 3).unwrap(), maps);
@@ -550,6 +1225,7 @@ mod rio_tests {
             paddr: 0x1000,
             vaddr: 0x400,
             size: DATA.len() as u64,
+            ..Default::default()
         }];
         assert_eq!(io.vir_to_phy(0x400, DATA.len() as u64).unwrap(), maps);
         maps = vec![
@@ -557,11 +1233,13 @@ mod rio_tests {
                 paddr: 0x2000 + DATA.len() as u64 / 2,
                 vaddr: 0x400 + DATA.len() as u64 * 3 / 2,
                 size: DATA.len() as u64 - DATA.len() as u64 / 2,
+                ..Default::default()
             },
             RIOMap {
                 paddr: 0x3000,
                 vaddr: 0x400 + DATA.len() as u64 * 2,
                 size: DATA.len() as u64,
+                ..Default::default()
             },
         ];
         assert_eq!(
@@ -716,7 +1394,8 @@ mod rio_tests {
             RIOMap {
                 paddr: 0x200,
                 vaddr: 0x2000,
-                size
+                size,
+                ..Default::default()
             }
         );
         assert_eq!(
@@ -724,7 +1403,8 @@ mod rio_tests {
             RIOMap {
                 paddr: 0x300,
                 vaddr: 0x3000,
-                size
+                size,
+                ..Default::default()
             }
         );
         assert_eq!(
@@ -732,7 +1412,8 @@ mod rio_tests {
             RIOMap {
                 paddr: 0,
                 vaddr: 0x4000,
-                size
+                size,
+                ..Default::default()
             }
         );
         assert_eq!(
@@ -740,7 +1421,8 @@ mod rio_tests {
             RIOMap {
                 paddr: 0x100,
                 vaddr: 0x5000,
-                size
+                size,
+                ..Default::default()
             }
         );
         assert_eq!(iter.next(), None);
@@ -877,4 +1559,561 @@ mod rio_tests {
     fn test_serde() {
         operate_on_files(&serde_cb, &[DATA, DATA, DATA]);
     }
+    #[test]
+    fn test_map_perm() {
+        let mut io = RIO::new();
+        io.open("malloc://0x20", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        let rx = "rx".parse().unwrap();
+        io.map_named(0x0, 0x1000, 0x10, ".text", rx).unwrap();
+        io.map_named(0x10, 0x1010, 0x10, ".data", MapPerm::READ | MapPerm::WRITE)
+            .unwrap();
+        assert_eq!(
+            io.vwrite(0x1008, &[0xff; 0x10]).err().unwrap(),
+            IoError::MapNotWritableError(0x1008)
+        );
+        assert!(io.journal().applied().next().is_none());
+        assert_eq!(
+            io.vwrite(0x1000, &[0xff]).err().unwrap().to_string(),
+            "Virtual address 0x1000 is not writable."
+        );
+        io.vwrite(0x1010, &[0xff; 0x10]).unwrap();
+        io.pwrite(0x0, &[0xff]).unwrap();
+        io.unmap(0x1004, 0x4).unwrap();
+        let maps: Vec<_> = io
+            .map_iter()
+            .map(|map| (map.vaddr, map.name.clone(), map.perm.to_string()))
+            .collect();
+        assert_eq!(
+            maps,
+            [
+                (0x1000, ".text".to_owned(), "r-x".to_owned()),
+                (0x1008, ".text".to_owned(), "r-x".to_owned()),
+                (0x1010, ".data".to_owned(), "rw-".to_owned()),
+            ]
+        );
+        let serialized = serde_json::to_string(&io).unwrap();
+        drop(io);
+        io = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            io.vwrite(0x1008, &[0xff]).err().unwrap(),
+            IoError::MapNotWritableError(0x1008)
+        );
+        let map = io.map_iter().last().unwrap();
+        assert_eq!(
+            (&*map.name, map.perm),
+            (".data", MapPerm::READ | MapPerm::WRITE)
+        );
+        assert_eq!(
+            "rwz".parse::<MapPerm>().err().unwrap(),
+            IoError::Custom("Invalid permissions rwz".to_owned())
+        );
+        assert_eq!("---".parse::<MapPerm>().unwrap(), MapPerm::empty());
+    }
+    #[test]
+    fn test_banks() {
+        let mut io = RIO::new();
+        io.open("malloc://0x20", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.pwrite(0x10, &[0xff; 0x10]).unwrap();
+        io.map(0x0, 0x1000, 0x10).unwrap();
+        assert_eq!(io.bank(), "default");
+        io.add_bank("data").unwrap();
+        assert_eq!(
+            io.add_bank("data").err().unwrap(),
+            IoError::Custom("Bank data already exists".to_owned())
+        );
+        assert_eq!(
+            io.add_bank("a b").err().unwrap(),
+            IoError::Custom("Invalid bank name `a b`".to_owned())
+        );
+        assert_eq!(
+            io.switch_bank("code").err().unwrap(),
+            IoError::Custom("Bank code does not exist".to_owned())
+        );
+        io.switch_bank("data").unwrap();
+        assert_eq!(io.bank(), "data");
+        assert_eq!(io.map_iter().count(), 0);
+        io.map(0x10, 0x1000, 0x10).unwrap();
+        let mut data = [0; 2];
+        io.vread(0x1000, &mut data).unwrap();
+        assert_eq!(data, [0xff, 0xff]);
+        assert_eq!(
+            io.remove_bank("data").err().unwrap(),
+            IoError::Custom("Can not remove current bank data".to_owned())
+        );
+        io.with_bank("default", |io| io.vwrite(0x1000, &[1, 2]))
+            .unwrap();
+        assert_eq!(io.bank(), "data");
+        assert!(io
+            .with_bank("code", |io| io.vwrite(0x1000, &[1, 2]))
+            .is_err());
+        let mut data = [0; 2];
+        io.pread(0x0, &mut data).unwrap();
+        assert_eq!(data, [1, 2]);
+        assert_eq!(io.banks(), ["data", "default"]);
+        let serialized = serde_json::to_string(&io).unwrap();
+        drop(io);
+        io = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(io.bank(), "data");
+        assert_eq!(io.vir_to_phy(0x1000, 1).unwrap()[0].paddr, 0x10);
+        io.switch_bank("default").unwrap();
+        assert_eq!(io.vir_to_phy(0x1000, 1).unwrap()[0].paddr, 0x0);
+        io.remove_bank("data").unwrap();
+        assert_eq!(io.banks(), ["default"]);
+        io.add_bank("data").unwrap();
+        io.close_all();
+        assert_eq!(io.banks(), ["default"]);
+    }
+    #[test]
+    fn test_undo_redo() {
+        let mut io = RIO::new();
+        io.open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.open_at("malloc://0x10", IoMode::READ | IoMode::WRITE, 0x100)
+            .unwrap();
+        io.map(0x0, 0x1000, 0x10).unwrap();
+        io.map(0x100, 0x1010, 0x10).unwrap();
+        assert!(!io.undo().unwrap());
+        assert!(!io.redo().unwrap());
+        io.pwrite(0x4, &[1, 2]).unwrap();
+        io.vwrite(0x100e, &[3, 4, 5, 6]).unwrap();
+        let mut fillme = [0; 4];
+        io.pread(0xe, &mut fillme[..2]).unwrap();
+        io.pread(0x100, &mut fillme[2..]).unwrap();
+        assert_eq!(fillme, [3, 4, 5, 6]);
+        let applied: Vec<_> = io.journal().applied().collect();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[1].len(), 2);
+        assert_eq!(applied[1][1].paddr, 0x100);
+        assert_eq!(applied[1][1].old, [0, 0]);
+        assert_eq!(applied[1][1].new, [5, 6]);
+
+        // undoing the vwrite reverts both halves at once
+        assert!(io.undo().unwrap());
+        io.vread(0x100e, &mut fillme).unwrap();
+        assert_eq!(fillme, [0; 4]);
+        assert!(io.undo().unwrap());
+        io.pread(0x4, &mut fillme[..2]).unwrap();
+        assert_eq!(fillme[..2], [0, 0]);
+        assert!(!io.undo().unwrap());
+        assert_eq!(io.journal().undone().count(), 2);
+
+        assert!(io.redo().unwrap());
+        io.pread(0x4, &mut fillme[..2]).unwrap();
+        assert_eq!(fillme[..2], [1, 2]);
+        // new writes drop whatever could have been redone
+        io.pwrite(0x0, &[9]).unwrap();
+        assert!(!io.redo().unwrap());
+        assert_eq!(io.journal().applied().count(), 2);
+
+        // closing a file forgets every entry touching it
+        io.pwrite(0x100, &[7]).unwrap();
+        io.close(1).unwrap();
+        assert_eq!(io.journal().applied().count(), 2);
+        io.close_all();
+        assert!(io.journal().is_empty());
+    }
+    fn test_cache_cb(path: &Path) {
+        let mut io = RIO::new();
+        io.open(&path.to_string_lossy(), IoMode::READ).unwrap();
+        io.open_at("malloc://0x10", IoMode::READ | IoMode::WRITE, 0x10)
+            .unwrap();
+        io.map(0x0, 0x1000, 0x20).unwrap();
+        io.set_cache(true);
+        assert!(io.cache().is_enabled());
+        io.vwrite(0xe, &[1]).err().unwrap();
+        io.vwrite(0x100e, &[1, 2, 3, 4]).unwrap();
+        io.pwrite(0x2, &[5]).unwrap();
+        assert_eq!(
+            io.cache().ranges(),
+            [(0x2, vec![5]), (0xe, vec![1, 2, 3, 4])]
+        );
+        let mut data = [0; 4];
+        io.vread(0x100e, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4]);
+        let sparce: Vec<_> = io.pread_sparce(0x0, 0x4).unwrap().into_iter().collect();
+        assert_eq!(sparce, [(0, 0), (1, 0), (2, 5), (3, 0)]);
+        // the cache is still visible once disabled
+        io.set_cache(false);
+        io.pread(0xe, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4]);
+        assert!(io.pwrite(0x2, &[6]).is_err());
+        // first file is read only, so bytes that fail to be committed stay in the cache
+        assert!(io.commit_cache(0x0, 0x20).is_err());
+        assert_eq!(
+            io.cache().ranges(),
+            [(0x2, vec![5]), (0xe, vec![1, 2, 3, 4])]
+        );
+        io.commit_cache(0x10, 0x10).unwrap();
+        assert_eq!(io.cache().ranges(), [(0x2, vec![5]), (0xe, vec![1, 2])]);
+        io.discard_cache(0x0, 0x10);
+        assert!(io.cache().is_empty());
+        assert_eq!(io.journal().applied().count(), 0);
+        io.pread(0xe, &mut data).unwrap();
+        assert_eq!(data, [0, 0, 3, 4]);
+        io.set_cache(true);
+        io.pwrite(0x0, &[1]).unwrap();
+        let serialized = serde_json::to_string(&io).unwrap();
+        io.close(0).unwrap();
+        assert!(io.cache().is_empty());
+        io = serde_json::from_str(&serialized).unwrap();
+        assert!(!io.cache().is_enabled());
+        assert_eq!(io.cache().ranges(), [(0x0, vec![1])]);
+    }
+    #[test]
+    fn test_cache() {
+        operate_on_file(&test_cache_cb, &[0; 0x10]);
+    }
+    #[test]
+    fn test_cache_write_through() {
+        let mut io = RIO::new();
+        io.open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.set_cache(true);
+        io.pwrite(0x4, &[1, 2, 3]).unwrap();
+        io.set_cache(false);
+        io.pwrite(0x5, &[4]).unwrap();
+        let mut data = [0; 3];
+        io.pread(0x4, &mut data).unwrap();
+        assert_eq!(data, [1, 4, 3]);
+        assert_eq!(io.cache().ranges(), [(0x4, vec![1]), (0x6, vec![3])]);
+        let last = io.journal().applied().last().unwrap();
+        assert_eq!(last[0].old, [0]);
+        io.undo().unwrap();
+        io.pread(0x4, &mut data).unwrap();
+        assert_eq!(data, [1, 0, 3]);
+        io.redo().unwrap();
+        io.discard_cache(0x0, 0x10);
+        io.pread(0x4, &mut data).unwrap();
+        assert_eq!(data, [0, 4, 0]);
+    }
+    fn resize_cb(path: &Path) {
+        let mut io = RIO::new();
+        let hndl = io
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.pwrite(0xc, &[1]).unwrap();
+        io.set_cache(true);
+        io.pwrite(0x4, &[2]).unwrap();
+        io.pwrite(0xe, &[3]).unwrap();
+        io.resize(hndl, 0x8).unwrap();
+        assert_eq!(io.hndl_to_desc(hndl).unwrap().size(), 0x8);
+        assert_eq!(io.journal().applied().count(), 1);
+        assert_eq!(io.cache().ranges(), [(0x4, vec![2])]);
+        assert!(io.pread(0x8, &mut [0]).is_err());
+        io.resize(hndl, 0x20).unwrap();
+        let mut fillme = [0xff; 0x20];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(
+            fillme[..0x10],
+            [0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        let cow = io
+            .open_at(&path.to_string_lossy(), IoMode::COW, 0x100)
+            .unwrap();
+        assert_eq!(
+            io.resize(hndl, 0x101).err().unwrap(),
+            IoError::AddressesOverlapError
+        );
+        assert_eq!(
+            io.resize(3, 0x10).err().unwrap(),
+            IoError::HndlNotFoundError
+        );
+        io.resize(cow, 0x4).unwrap();
+        io.close(hndl).unwrap();
+        let serialized = serde_json::to_string(&io).unwrap();
+        let mut io: RIO = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(io.hndl_to_desc(cow).unwrap().size(), 0x4);
+        let mut fillme = [0xff; 0x4];
+        io.pread(0x100, &mut fillme).unwrap();
+        assert_eq!(fillme, [0x00, 0x01, 0x01, 0x02]);
+        assert!(io.pread(0x104, &mut [0]).is_err());
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_file(&resize_cb, DATA);
+    }
+    fn insert_remove_cb(path: &Path) {
+        let mut io = RIO::new();
+        io.open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.open_at("malloc://0x10", IoMode::READ | IoMode::WRITE, 0x20)
+            .unwrap();
+        let data: Vec<u8> = (0..0x10).collect();
+        io.pwrite(0, &data).unwrap();
+        io.set_cache(true);
+        io.pwrite(0xc, &[0xcc]).unwrap();
+        io.set_cache(false);
+        io.map(0x0, 0x1000, 0x8).unwrap();
+        io.map(0x8, 0x2000, 0x8).unwrap();
+        io.add_bank("other").unwrap();
+        io.with_bank("other", |io| io.map(0xc, 0x3000, 0x4))
+            .unwrap();
+        let shift = io.insert(0x4, &[0xaa, 0xbb], true).unwrap();
+        assert_eq!(
+            shift,
+            Shift {
+                paddr: 0x4,
+                end: 0x10,
+                removed: 0,
+                inserted: 2
+            }
+        );
+        io.insert(0x12, &[0xee], true).unwrap();
+        assert_eq!(io.hndl_to_desc(0).unwrap().size(), 0x13);
+        let mut fillme = [0; 0x13];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(
+            fillme,
+            [0, 1, 2, 3, 0xaa, 0xbb, 4, 5, 6, 7, 8, 9, 10, 11, 0xcc, 13, 14, 15, 0xee]
+        );
+        assert_eq!(io.cache().ranges(), [(0xe, vec![0xcc])]);
+        assert_eq!(io.journal().applied().count(), 0);
+        let mut fillme = [0; 2];
+        io.vread(0x2000, &mut fillme).unwrap();
+        assert_eq!(fillme, [8, 9]);
+        io.with_bank("other", |io| io.vread(0x3000, &mut fillme))
+            .unwrap();
+        assert_eq!(fillme, [0xcc, 13]);
+        assert_eq!(
+            io.insert(0, &[0; 0x10], false).err().unwrap(),
+            IoError::AddressesOverlapError
+        );
+        assert_eq!(
+            io.insert(0x14, &[0], false).err().unwrap(),
+            IoError::AddressNotFound
+        );
+        assert_eq!(
+            io.remove(0x10, 0x10, false).err().unwrap(),
+            IoError::AddressNotFound
+        );
+        assert_eq!(
+            io.remove(0, 0x13, false).err().unwrap(),
+            IoError::Custom("Can not resize file to 0 bytes".to_owned())
+        );
+        io.remove(0x2, 4, false).unwrap();
+        let mut fillme = [0; 0xf];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(
+            fillme,
+            [0, 1, 4, 5, 6, 7, 8, 9, 10, 11, 0xcc, 13, 14, 15, 0xee]
+        );
+        let mut fillme = [0; 1];
+        io.vread(0x2000, &mut fillme).unwrap();
+        assert_eq!(fillme, [0xcc]);
+        // files that are not in memory are resized
+        let hndl = io
+            .open_at(&path.to_string_lossy(), IoMode::READ | IoMode::WRITE, 0x100)
+            .unwrap();
+        io.insert(0x101, &[0xff], false).unwrap();
+        io.remove(0x104, 0x60, false).unwrap();
+        io.close(hndl).unwrap();
+        assert_eq!(
+            fs::read(path).unwrap(),
+            [0x00, 0xff, 0x01, 0x01, 0x02, 0xc3, 0xc5, 0x88, 0x4d, 0xd5]
+        );
+    }
+    #[test]
+    fn test_insert_remove() {
+        operate_on_file(&insert_remove_cb, DATA);
+    }
+    #[test]
+    fn test_journal_serde() {
+        let mut io = RIO::new();
+        io.open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        io.pwrite(0x4, &[1, 2]).unwrap();
+        io.pwrite(0x8, &[3]).unwrap();
+        io.undo().unwrap();
+        let serialized = serde_json::to_string(&io).unwrap();
+        let mut io: RIO = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(io.journal().applied().count(), 1);
+        assert_eq!(io.journal().undone().count(), 1);
+        let mut fillme = [0; 0x10];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(fillme[4..9], [1, 2, 0, 0, 0]);
+        assert!(io.undo().unwrap());
+        assert!(io.redo().unwrap());
+        assert!(io.redo().unwrap());
+        let mut fillme = [0; 0x10];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(fillme[4..9], [1, 2, 0, 0, 3]);
+    }
+    fn commit_cb(paths: &[&Path]) {
+        let mut io = RIO::new();
+        let raw = io.open(&paths[0].to_string_lossy(), IoMode::COW).unwrap();
+        let b64 = io
+            .open(
+                &format!("b64://{}", paths[1].to_string_lossy()),
+                IoMode::COW,
+            )
+            .unwrap();
+        let ihex = io
+            .open(
+                &format!("ihex://{}", paths[2].to_string_lossy()),
+                IoMode::COW,
+            )
+            .unwrap();
+        let ro = io.open(&paths[3].to_string_lossy(), IoMode::READ).unwrap();
+        io.pwrite(0, &[0xff, 0xff]).unwrap();
+        let b64_base = io.hndl_to_desc(b64).unwrap().paddr_base();
+        io.pwrite(b64_base, b"J").unwrap();
+        let ihex_base = io.hndl_to_desc(ihex).unwrap().paddr_base();
+        io.pwrite(ihex_base + 1, &[0xff]).unwrap();
+
+        // commit into another file leaves the original file intact
+        io.commit(raw, Some(paths[3])).unwrap();
+        let mut expected = DATA.to_vec();
+        expected[0..2].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(fs::read(paths[3]).unwrap(), expected);
+        assert_eq!(fs::read(paths[0]).unwrap(), DATA);
+        io.commit(raw, None).unwrap();
+        assert_eq!(fs::read(paths[0]).unwrap(), expected);
+
+        io.commit(b64, None).unwrap();
+        assert_eq!(fs::read(paths[1]).unwrap(), b"SmVsbG8=");
+        io.commit(ihex, None).unwrap();
+        assert_eq!(
+            fs::read(paths[2]).unwrap(),
+            b":0300000001ff03fa\n:00000001FF\n"
+        );
+        let mut fillme = [0; 3];
+        io.pread(ihex_base, &mut fillme).unwrap();
+        assert_eq!(fillme, [1, 0xff, 3]);
+
+        assert_eq!(
+            io.commit(ro, None).err().unwrap(),
+            IoError::Custom("File is not opened in Copy-On-Write mode".to_owned())
+        );
+        assert_eq!(
+            io.commit(ro + 1, None).err().unwrap(),
+            IoError::HndlNotFoundError
+        );
+    }
+    #[test]
+    fn test_commit() {
+        operate_on_files(
+            &commit_cb,
+            &[DATA, b"aGVsbG8=", b":03000000010203F7\n:00000001FF\n", DATA],
+        );
+    }
+    fn nested_cb(paths: &[&Path]) {
+        let mut io = RIO::new();
+        let b64 = format!("b64://gzip://{}", paths[0].to_string_lossy());
+        let ihex = format!("ihex://b64://{}", paths[1].to_string_lossy());
+        let b64 = io.open(&b64, IoMode::COW).unwrap();
+        let ihex = io.open(&ihex, IoMode::COW).unwrap();
+        let b64_base = io.hndl_to_desc(b64).unwrap().paddr_base();
+        let ihex_base = io.hndl_to_desc(ihex).unwrap().paddr_base();
+        let mut fillme = [0; 5];
+        io.pread(b64_base, &mut fillme).unwrap();
+        assert_eq!(&fillme, b"hello");
+        let mut fillme = [0; 3];
+        io.pread(ihex_base, &mut fillme).unwrap();
+        assert_eq!(fillme, [1, 2, 3]);
+
+        // project save and load reopens every layer.
+        let serialized = serde_json::to_string(&io).unwrap();
+        drop(io);
+        let mut io: RIO = serde_json::from_str(&serialized).unwrap();
+        io.pwrite(b64_base, b"J").unwrap();
+        io.pwrite(ihex_base + 1, &[0xff]).unwrap();
+        io.commit(b64, None).unwrap();
+        let compressed = fs::read(paths[0]).unwrap();
+        let mut data = String::new();
+        GzDecoder::new(&*compressed)
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "SmVsbG8=");
+        io.commit(ihex, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            "OjAzMDAwMDAwMDFmZjAzZmEKOjAwMDAwMDAxRkYK"
+        );
+
+        let uri = format!("ihex://b64://{}", paths[1].to_string_lossy());
+        assert_eq!(
+            io.open(&uri, IoMode::READ | IoMode::WRITE)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Layered files can only be modified in Copy-On-Write mode"
+        );
+        assert_eq!(
+            io.open("b64://bz2://file", IoMode::READ).err().unwrap(),
+            IoError::IoPluginNotFoundError
+        );
+    }
+    #[test]
+    fn test_nested() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"aGVsbG8=").unwrap();
+        let gzip = encoder.finish().unwrap();
+        // base64 of ":03000000010203F7\n:00000001FF\n"
+        operate_on_files(
+            &nested_cb,
+            &[&gzip, b"OjAzMDAwMDAwMDEwMjAzRjcKOjAwMDAwMDAxRkYK"],
+        );
+    }
+
+    fn rot13_plugin() -> PathBuf {
+        example_plugin("rot13_plugin")
+    }
+    fn plugin_file_cb(path: &Path) {
+        let mut io = RIO::new();
+        let builtin = io.plugins().count();
+        assert_eq!(io.load_plugin_file(&rot13_plugin()).unwrap().name, "Rot13");
+        assert_eq!(io.plugins().count(), builtin + 1);
+        assert_eq!(io.plugins().last().unwrap().name, "Rot13");
+        let uri = format!("rot13://{}", path.to_string_lossy());
+        io.open(&uri, IoMode::READ).unwrap();
+        let mut fillme = [0; 5];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(&fillme, b"hello");
+        assert_eq!(
+            io.load_plugin_file(&rot13_plugin()).err().unwrap(),
+            IoError::Custom(format!(
+                "Plugin {} is already loaded",
+                fs::canonicalize(rot13_plugin()).unwrap().display()
+            ))
+        );
+
+        // projects load plugins again before reopening files.
+        let serialized = serde_json::to_string(&io).unwrap();
+        drop(io);
+        let mut io: RIO = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(io.plugins().count(), builtin + 1);
+        let mut fillme = [0; 5];
+        io.pread(0, &mut fillme).unwrap();
+        assert_eq!(&fillme, b"hello");
+    }
+    #[test]
+    fn test_plugin_file() {
+        operate_on_file(&plugin_file_cb, b"uryyb");
+    }
+    #[test]
+    fn test_plugin_dir() {
+        let mut io = RIO::new();
+        let builtin = io.plugins().count();
+        assert!(io
+            .load_plugin_dir(Path::new("dir_that_doesnt_exist"))
+            .is_empty());
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy(
+            rot13_plugin(),
+            dir.path().join(rot13_plugin().file_name().unwrap()),
+        )
+        .unwrap();
+        fs::write(dir.path().join(format!("broken.{DLL_EXTENSION}")), DATA).unwrap();
+        fs::write(dir.path().join("readme.txt"), DATA).unwrap();
+        let errors = io.load_plugin_dir(dir.path());
+        assert_eq!(io.plugins().count(), builtin + 1);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().starts_with("Failed to load plugin "));
+        assert!(matches!(
+            io.load_plugin_file(Path::new("file_that_doesnt_exist.so")),
+            Err(IoError::Parse(_))
+        ));
+    }
 }
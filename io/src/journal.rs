@@ -0,0 +1,65 @@
+//! Undo/redo history of writes done through [RIO](crate::RIO).
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes at physical address `paddr` before and after a write.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch {
+    pub paddr: u64,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+impl Patch {
+    fn overlaps(&self, paddr: u64, size: u64) -> bool {
+        self.paddr < paddr + size && paddr < self.paddr + self.new.len() as u64
+    }
+}
+
+/// Every write is recorded as one entry, an entry might consist of more than
+/// one [`Patch`] when the write spans non contiguous physical ranges.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Journal {
+    undo: Vec<Vec<Patch>>,
+    redo: Vec<Vec<Patch>>,
+}
+
+impl Journal {
+    /// Record new entry, this drops every entry that could have been redone.
+    pub(crate) fn record(&mut self, entry: Vec<Patch>) {
+        if !entry.is_empty() {
+            self.undo.push(entry);
+            self.redo = Vec::new();
+        }
+    }
+    pub(crate) fn pop_undo(&mut self) -> Option<Vec<Patch>> {
+        self.undo.pop()
+    }
+    pub(crate) fn push_undo(&mut self, entry: Vec<Patch>) {
+        self.undo.push(entry);
+    }
+    pub(crate) fn pop_redo(&mut self) -> Option<Vec<Patch>> {
+        self.redo.pop()
+    }
+    pub(crate) fn push_redo(&mut self, entry: Vec<Patch>) {
+        self.redo.push(entry);
+    }
+    /// Drop every entry that touches physical range `paddr..paddr + size`.
+    pub(crate) fn forget(&mut self, paddr: u64, size: u64) {
+        let keep = |entry: &Vec<Patch>| !entry.iter().any(|p| p.overlaps(paddr, size));
+        self.undo.retain(keep);
+        self.redo.retain(keep);
+    }
+    /// Iterate over applied entries starting from the oldest one.
+    pub fn applied(&self) -> impl Iterator<Item = &[Patch]> {
+        self.undo.iter().map(Vec::as_slice)
+    }
+    /// Iterate over undone entries starting from the next one to be redone.
+    pub fn undone(&self) -> impl Iterator<Item = &[Patch]> {
+        self.redo.iter().rev().map(Vec::as_slice)
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+}
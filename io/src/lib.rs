@@ -1,14 +1,23 @@
 //! Rair IO abstraction layer
 extern crate alloc;
+mod cache;
 mod desc;
 mod descquery;
+mod dylib;
 mod io;
+mod journal;
 mod mapsquery;
 mod plugin;
 mod plugins;
 mod utils;
+pub use crate::cache::*;
 pub use crate::desc::*;
+pub use crate::dylib::*;
 pub use crate::io::*;
+pub use crate::journal::*;
 pub use crate::mapsquery::*;
 pub use crate::plugin::*;
+pub use crate::plugins::archive::ArchiveMember;
+pub use crate::plugins::ihex::{encode_ihex, IHexAddrMode};
+pub use crate::plugins::srec::{encode_srec, SrecAddrMode};
 pub use crate::utils::*;
@@ -1,6 +1,6 @@
 //! Data structure that enables queries and reverse queries on vaddr <--> paddr.
 
-use crate::utils::IoError;
+use crate::utils::{IoError, MapPerm};
 use alloc::sync::Arc;
 use core::cmp::min;
 use rair_trees::ist::IST;
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 /// This struct describes a mapping between physical
 /// address space and virtual address space
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct RIOMap {
     /// physical address space
     pub paddr: u64,
@@ -16,34 +16,37 @@ pub struct RIOMap {
     pub vaddr: u64,
     /// size of the mapping
     pub size: u64,
+    /// Name of the mapping, empty if it is anonymous
+    #[serde(default)]
+    pub name: String,
+    /// Permissions of the mapping, only write permission is enforced
+    #[serde(default)]
+    pub perm: MapPerm,
+    /// Maps can overlap maps of different priority, the map with the highest priority is
+    /// the one visible
+    #[serde(default)]
+    pub priority: u64,
 }
 
 impl RIOMap {
-    fn has_paddr(&self, paddr: u64) -> bool {
-        paddr >= self.paddr && paddr < self.paddr + self.size
-    }
-
     fn has_vaddr(&self, vaddr: u64) -> bool {
         vaddr >= self.vaddr && vaddr < self.vaddr + self.size
     }
 
-    fn envelop(&self, map: &RIOMap) -> bool {
-        self.has_paddr(map.paddr)
-            && self.has_paddr(map.paddr + map.size - 1)
-            && self.has_vaddr(map.vaddr)
-            && self.has_vaddr(map.vaddr + map.size - 1)
-    }
     fn split(mut self, vaddr: u64) -> (RIOMap, RIOMap) {
         let delta = vaddr - self.vaddr;
         let new_map = RIOMap {
             vaddr,
             paddr: self.paddr + delta,
             size: self.size - delta,
+            name: self.name.clone(),
+            perm: self.perm,
+            priority: self.priority,
         };
         self.size = delta;
         (self, new_map)
     }
-    // This will only work IFF self.envelop(map) == true
+    // This will only work IFF self envelops map
     fn remove_projection(mut self, map: &RIOMap) -> Vec<RIOMap> {
         let mut maps = Vec::with_capacity(2);
         if self.vaddr < map.vaddr {
@@ -85,43 +88,96 @@ impl RIOMapQuery {
         }
     }
     pub fn map(&mut self, paddr: u64, vaddr: u64, size: u64) -> Result<(), IoError> {
-        // check if vaddr is previosly used or not
-        if !self.maps.overlap(vaddr, vaddr + size - 1).is_empty() {
+        self.add_map(RIOMap {
+            paddr,
+            vaddr,
+            size,
+            ..Default::default()
+        })
+    }
+    pub fn add_map(&mut self, map: RIOMap) -> Result<(), IoError> {
+        let RIOMap {
+            paddr,
+            vaddr,
+            size,
+            priority,
+            ..
+        } = map;
+        // maps can only overlap maps of different priority
+        if self
+            .maps
+            .overlap(vaddr, vaddr + size - 1)
+            .iter()
+            .any(|map| map.priority == priority)
+        {
             return Err(IoError::AddressesOverlapError);
         }
-        let mapping = Arc::new(RIOMap { paddr, vaddr, size });
+        let mapping = Arc::new(map);
         self.maps.insert(vaddr, vaddr + size - 1, mapping.clone());
         self.rev_maps.insert(paddr, paddr + size - 1, mapping);
         Ok(())
     }
-    pub fn split_vaddr_range(&self, vaddr: u64, size: u64) -> Option<Vec<RIOMap>> {
+    // Visible fragments of the given range sorted by virtual address, each along with the map
+    // it is part of. Where maps overlap only the map with highest priority is visible.
+    fn visible(&self, vaddr: u64, size: u64) -> Vec<(Arc<RIOMap>, RIOMap)> {
+        let end = vaddr + (size - 1);
         let maps: Vec<Arc<RIOMap>> = self
             .maps
-            .overlap(vaddr, vaddr + size - 1)
+            .overlap(vaddr, end)
             .iter()
             .map(|&x| x.clone())
             .collect();
-        if maps.is_empty() {
-            return None;
-        }
-        let mut ranges = Vec::with_capacity(maps.len());
+        let mut fragments = Vec::with_capacity(maps.len());
         let mut start = vaddr;
-        let mut remaining = size;
-        for map in maps {
-            if start < map.vaddr {
-                return None;
+        loop {
+            let top = maps
+                .iter()
+                .filter(|map| map.has_vaddr(start))
+                .max_by_key(|map| map.priority);
+            let Some(top) = top else {
+                match maps
+                    .iter()
+                    .map(|map| map.vaddr)
+                    .filter(|&v| v > start)
+                    .min()
+                {
+                    Some(next) => start = next,
+                    None => break,
+                }
+                continue;
+            };
+            // top is visible till it ends or till a map with higher priority starts
+            let mut last = min(top.vaddr + top.size - 1, end);
+            for map in &maps {
+                if map.priority > top.priority && map.vaddr > start && map.vaddr <= last {
+                    last = map.vaddr - 1;
+                }
             }
-            let delta = min(remaining, map.size - (start - map.vaddr));
             let frag = RIOMap {
-                paddr: map.paddr + (start - map.vaddr),
+                paddr: top.paddr + (start - top.vaddr),
                 vaddr: start,
-                size: delta,
+                size: last - start + 1,
+                ..RIOMap::clone(top)
             };
+            fragments.push((top.clone(), frag));
+            if last == end {
+                break;
+            }
+            start = last + 1;
+        }
+        fragments
+    }
+    pub fn split_vaddr_range(&self, vaddr: u64, size: u64) -> Option<Vec<RIOMap>> {
+        let mut ranges = Vec::new();
+        let mut start = vaddr;
+        for (_, frag) in self.visible(vaddr, size) {
+            if start != frag.vaddr {
+                return None;
+            }
+            start += frag.size;
             ranges.push(frag);
-            start += delta;
-            remaining -= delta;
         }
-        if remaining != 0 {
+        if ranges.is_empty() || start - vaddr != size {
             return None;
         }
         Some(ranges)
@@ -131,39 +187,61 @@ impl RIOMapQuery {
         if maps.is_empty() {
             return Vec::new();
         }
+        // only addresses where the map isn't hidden by maps of higher priority
         maps.iter()
-            .map(|map| paddr - map.paddr + map.vaddr)
+            .map(|map| (map, paddr - map.paddr + map.vaddr))
+            .filter(|(map, vaddr)| {
+                self.visible(*vaddr, 1)
+                    .first()
+                    .is_some_and(|(top, _)| top == *map)
+            })
+            .map(|(_, vaddr)| vaddr)
             .collect()
     }
     pub fn split_vaddr_sparce_range(&self, vaddr: u64, size: u64) -> Vec<RIOMap> {
-        let maps: Vec<Arc<RIOMap>> = self
-            .maps
-            .overlap(vaddr, vaddr + size - 1)
-            .iter()
-            .map(|&x| x.clone())
-            .collect();
-        if maps.is_empty() {
-            return Vec::new();
+        self.visible(vaddr, size)
+            .into_iter()
+            .map(|(_, frag)| frag)
+            .collect()
+    }
+    /// Move physical addresses of maps using `moved`, virtual addresses stay the same.
+    pub fn move_paddrs<F: Fn(u64) -> u64>(&mut self, moved: F) {
+        let maps: Vec<RIOMap> = self.into_iter().map(|map| RIOMap::clone(&map)).collect();
+        *self = RIOMapQuery::new();
+        for mut map in maps {
+            map.paddr = moved(map.paddr);
+            let mapping = Arc::new(map);
+            self.maps.insert(
+                mapping.vaddr,
+                mapping.vaddr + mapping.size - 1,
+                mapping.clone(),
+            );
+            self.rev_maps
+                .insert(mapping.paddr, mapping.paddr + mapping.size - 1, mapping);
         }
-        let mut ranged_hndl = Vec::with_capacity(maps.len());
-        let mut start = vaddr;
-        let mut remaining = size;
-        for map in maps {
-            if start < map.vaddr {
-                remaining -= map.vaddr - start;
-                start = map.vaddr;
+    }
+    /// Remove `map` as a whole, unlike [`RIOMapQuery::unmap`] it doesn't matter whether `map` is
+    /// visible or hidden under maps of higher priority.
+    pub fn remove_map(&mut self, map: &RIOMap) -> Result<(), IoError> {
+        let removed = self
+            .maps
+            .delete_envelop(map.vaddr, map.vaddr + map.size - 1);
+        if !removed.iter().any(|m| **m == *map) {
+            for m in removed {
+                self.maps.insert(m.vaddr, m.vaddr + m.size - 1, m);
             }
-            let delta = min(remaining, map.size - (start - map.vaddr));
-            let frag = RIOMap {
-                paddr: map.paddr + (start - map.vaddr),
-                vaddr: start,
-                size: delta,
-            };
-            ranged_hndl.push(frag);
-            start += delta;
-            remaining -= delta;
+            return Err(IoError::AddressNotFound);
         }
-        ranged_hndl
+        for m in removed.into_iter().filter(|m| **m != *map) {
+            self.maps.insert(m.vaddr, m.vaddr + m.size - 1, m);
+        }
+        let removed = self
+            .rev_maps
+            .delete_envelop(map.paddr, map.paddr + map.size - 1);
+        for m in removed.into_iter().filter(|m| **m != *map) {
+            self.rev_maps.insert(m.paddr, m.paddr + m.size - 1, m);
+        }
+        Ok(())
     }
     pub fn unmap(&mut self, vaddr: u64, size: u64) -> Result<(), IoError> {
         let fragments = self.split_vaddr_range(vaddr, size);
@@ -171,31 +249,34 @@ impl RIOMapQuery {
             return Err(IoError::AddressNotFound);
         }
         for frag in fragments.unwrap() {
-            let old_map = self
+            // the map fragment is part of might have been split by previous fragments
+            let (old_map, _) = self.visible(frag.vaddr, frag.size).remove(0);
+            // maps hidden under the unmapped map stay as they are, they become visible
+            for map in self
                 .maps
-                .delete_envelop(frag.vaddr, frag.vaddr + frag.size - 1)[0]
-                .clone();
-            let old_rev_maps = self
+                .delete_envelop(frag.vaddr, frag.vaddr + frag.size - 1)
+            {
+                if map != *old_map {
+                    self.maps.insert(map.vaddr, map.vaddr + map.size - 1, map);
+                }
+            }
+            // 1 paddr can be pointed to by many vaddrs
+            for map in self
                 .rev_maps
-                .delete_envelop(frag.paddr, frag.paddr + frag.size - 1);
-            // we will get 1 normal map and maybe many rev_maps,
-            // The reason is that 1 vaddr can only point to 1 paddr
-            // but 1 paddr can be pointed to by many vaddrs
-            old_map
-                .remove_projection(&frag)
-                .into_iter()
-                .for_each(|m| self.maps.insert(m.vaddr, m.vaddr + m.size - 1, Arc::new(m)));
-            for map in old_rev_maps {
-                if map.envelop(&frag) {
-                    map.remove_projection(&frag).into_iter().for_each(|m| {
-                        self.rev_maps
-                            .insert(m.paddr, m.paddr + m.size - 1, Arc::new(m));
-                    });
-                } else {
+                .delete_envelop(frag.paddr, frag.paddr + frag.size - 1)
+            {
+                if map != *old_map {
                     self.rev_maps
                         .insert(map.paddr, map.paddr + map.size - 1, map);
                 }
             }
+            for map in RIOMap::clone(&old_map).remove_projection(&frag) {
+                let map = Arc::new(map);
+                self.maps
+                    .insert(map.vaddr, map.vaddr + map.size - 1, map.clone());
+                self.rev_maps
+                    .insert(map.paddr, map.paddr + map.size - 1, map);
+            }
         }
         Ok(())
     }
@@ -250,7 +331,8 @@ mod maps_query_test {
             vec![RIOMap {
                 vaddr: 0x1000,
                 paddr: 0,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             }]
         );
         assert_eq!(
@@ -258,7 +340,8 @@ mod maps_query_test {
             vec![RIOMap {
                 vaddr: 0x1200,
                 paddr: 0x200,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             }]
         );
         assert_eq!(map_query.split_vaddr_range(0x1100, 0x100), None);
@@ -288,7 +371,8 @@ mod maps_query_test {
             RIOMap {
                 paddr: 0x200,
                 vaddr: 0x2000,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             },
             iter.next().unwrap()
         );
@@ -296,7 +380,8 @@ mod maps_query_test {
             RIOMap {
                 paddr: 0x300,
                 vaddr: 0x3000,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             },
             iter.next().unwrap()
         );
@@ -304,7 +389,8 @@ mod maps_query_test {
             RIOMap {
                 paddr: 0,
                 vaddr: 0x4000,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             },
             iter.next().unwrap()
         );
@@ -312,7 +398,8 @@ mod maps_query_test {
             RIOMap {
                 paddr: 0x100,
                 vaddr: 0x5000,
-                size: 0x100
+                size: 0x100,
+                ..Default::default()
             },
             iter.next().unwrap()
         );
@@ -332,22 +419,26 @@ mod maps_query_test {
                 RIOMap {
                     paddr: 0x200,
                     vaddr: 0x2000,
-                    size: 0x90
+                    size: 0x90,
+                    ..Default::default()
                 },
                 RIOMap {
                     paddr: 0x300,
                     vaddr: 0x3000,
-                    size: 0x90
+                    size: 0x90,
+                    ..Default::default()
                 },
                 RIOMap {
                     paddr: 0x0,
                     vaddr: 0x4000,
-                    size: 0x90
+                    size: 0x90,
+                    ..Default::default()
                 },
                 RIOMap {
                     paddr: 0x100,
                     vaddr: 0x5000,
-                    size: 0x90
+                    size: 0x90,
+                    ..Default::default()
                 }
             ]
         );
@@ -371,4 +462,167 @@ mod maps_query_test {
         assert_eq!(map_query.rev_query(0x145), vec![0x5045]);
         assert_eq!(map_query.rev_query(700), Vec::<u64>::new());
     }
+
+    #[test]
+    fn test_priority() {
+        let mut map_query = RIOMapQuery::new();
+        map_query.map(0, 0x1000, 0x100).unwrap();
+        let overlay = RIOMap {
+            paddr: 0x500,
+            vaddr: 0x1080,
+            size: 0x100,
+            name: "overlay".to_owned(),
+            priority: 1,
+            ..Default::default()
+        };
+        map_query.add_map(overlay.clone()).unwrap();
+        let e = map_query.add_map(RIOMap {
+            vaddr: 0x1100,
+            ..overlay.clone()
+        });
+        assert_eq!(e.err().unwrap(), IoError::AddressesOverlapError);
+        assert_eq!(
+            map_query.split_vaddr_range(0x1070, 0x20).unwrap(),
+            vec![
+                RIOMap {
+                    paddr: 0x70,
+                    vaddr: 0x1070,
+                    size: 0x10,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x500,
+                    vaddr: 0x1080,
+                    size: 0x10,
+                    ..overlay.clone()
+                }
+            ]
+        );
+        // lower priority map under a higher priority one
+        map_query
+            .add_map(RIOMap {
+                paddr: 0x800,
+                vaddr: 0x1000,
+                size: 0x200,
+                priority: 2,
+                ..Default::default()
+            })
+            .unwrap();
+        map_query
+            .add_map(RIOMap {
+                paddr: 0x900,
+                vaddr: 0x1040,
+                size: 0x10,
+                priority: 3,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            map_query.split_vaddr_sparce_range(0x1000, 0x300),
+            vec![
+                RIOMap {
+                    paddr: 0x800,
+                    vaddr: 0x1000,
+                    size: 0x40,
+                    priority: 2,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x900,
+                    vaddr: 0x1040,
+                    size: 0x10,
+                    priority: 3,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x850,
+                    vaddr: 0x1050,
+                    size: 0x1b0,
+                    priority: 2,
+                    ..Default::default()
+                }
+            ]
+        );
+        assert_eq!(map_query.rev_query(0x10), Vec::<u64>::new());
+        assert_eq!(map_query.rev_query(0x850), vec![0x1050]);
+        // unmapping only removes what is visible, revealing the maps under it
+        map_query.unmap(0x1000, 0x200).unwrap();
+        assert_eq!(map_query.rev_query(0x10), vec![0x1010]);
+        assert_eq!(
+            map_query.split_vaddr_sparce_range(0x1000, 0x300),
+            vec![
+                RIOMap {
+                    paddr: 0,
+                    vaddr: 0x1000,
+                    size: 0x40,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x840,
+                    vaddr: 0x1040,
+                    size: 0x10,
+                    priority: 2,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x50,
+                    vaddr: 0x1050,
+                    size: 0x30,
+                    ..Default::default()
+                },
+                RIOMap {
+                    paddr: 0x500,
+                    vaddr: 0x1080,
+                    size: 0x100,
+                    ..overlay
+                }
+            ]
+        );
+        assert_eq!(map_query.maps.size(), 3);
+        assert_eq!(map_query.rev_maps.size(), 3);
+        map_query.unmap(0x1040, 0x10).unwrap();
+        map_query.unmap(0x1080, 0x100).unwrap();
+        assert_eq!(
+            map_query.split_vaddr_range(0x1000, 0x100).unwrap(),
+            vec![RIOMap {
+                paddr: 0,
+                vaddr: 0x1000,
+                size: 0x100,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(map_query.maps.size(), 1);
+        assert_eq!(map_query.rev_maps.size(), 1);
+    }
+    #[test]
+    fn test_remove_map() {
+        let mut map_query = RIOMapQuery::new();
+        map_query.map(0, 0x1000, 0x100).unwrap();
+        let hidden = RIOMap {
+            paddr: 0x500,
+            vaddr: 0x1000,
+            size: 0x100,
+            priority: 1,
+            ..Default::default()
+        };
+        map_query.add_map(hidden.clone()).unwrap();
+        map_query
+            .add_map(RIOMap {
+                priority: 2,
+                ..hidden.clone()
+            })
+            .unwrap();
+        map_query.remove_map(&hidden).unwrap();
+        assert_eq!(
+            map_query.remove_map(&hidden).err().unwrap(),
+            IoError::AddressNotFound
+        );
+        assert_eq!(map_query.maps.size(), 2);
+        assert_eq!(map_query.rev_maps.size(), 2);
+        assert_eq!(map_query.rev_query(0x510), vec![0x1010]);
+        assert_eq!(
+            map_query.split_vaddr_range(0x1000, 0x100).unwrap()[0].priority,
+            2
+        );
+    }
 }
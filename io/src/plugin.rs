@@ -1,6 +1,9 @@
 //! RIO interface for implementing new plugin.
 
+use crate::dylib::{PluginDeclaration, PluginVersions};
 use crate::utils::{IoError, IoMode};
+use core::ops::Range;
+use std::path::Path;
 
 /// Metadata that describes the plugin
 #[derive(PartialEq)]
@@ -31,6 +34,20 @@ pub struct RIOPluginDesc {
     pub plugin_operations: Box<dyn RIOPluginOperations + Sync + Send>,
 }
 
+/// Part of a file that lives at a fixed virtual address, such regions are mapped into the
+/// virtual address space as soon as the file is opened.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RIORegion {
+    /// Real address of the region in the file
+    pub raddr: u64,
+    /// Virtual address the region belongs at
+    pub vaddr: u64,
+    /// Size of the region
+    pub size: u64,
+    /// Name of the region, empty if it is anonymous
+    pub name: String,
+}
+
 /// This trait should be implemented by object that allows plugin to open files or check metadata
 /// of the plugin.
 pub trait RIOPlugin {
@@ -41,6 +58,24 @@ pub trait RIOPlugin {
     /// Check if the given file can be opened wit the current plugin (only by checking the uri
     /// without opening the file)
     fn accept_uri(&self, uri: &str) -> bool;
+    /// Plugins that decode data of other URIs, as in `b64://gzip://file`, return the URI they
+    /// are layered over along with the permissions it should be opened with. [`RIO`] opens it
+    /// through its own plugins then hands it to [`RIOPlugin::open_backed`].
+    ///
+    /// [`RIO`]: crate::RIO
+    fn backing_uri<'a>(&self, _uri: &'a str, _flags: IoMode) -> Option<(&'a str, IoMode)> {
+        None
+    }
+    /// Same as [`RIOPlugin::open`] except that data is read from and written to `backing`,
+    /// the already opened URI returned by [`RIOPlugin::backing_uri`].
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        _backing: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        self.open(uri, flags)
+    }
 }
 /// A call to [`RIOPlugin::open`] would normally return [`RioPluginDesc`] that contains member that
 /// implements [`RIOPluginOperations`]. This way we always have way of reading and writing from file
@@ -52,6 +87,65 @@ pub trait RIOPluginOperations {
     /// Function that writes to a file represented by an object opened
     /// by [`RIOPlugin::open`] raddr is the real address of the in the file.
     fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError>;
+    /// Save current content of the file to `path` using the plugin's own encoding. This is how
+    /// changes done to files opened as [`IoMode::COW`] are persisted.
+    fn commit(&mut self, _path: &Path) -> Result<(), IoError> {
+        Err(IoError::Custom(
+            "Plugin doesn't support committing changes".to_owned(),
+        ))
+    }
+    /// Replace the whole content of the file with `data` then save it to `path`, this is how
+    /// plugins that encode their data persist changes through the plugin they are layered over.
+    fn commit_data(&mut self, _path: &Path, _data: &[u8]) -> Result<(), IoError> {
+        Err(IoError::Custom(
+            "Plugin doesn't support committing changes".to_owned(),
+        ))
+    }
+    /// Same as [`RIOPluginOperations::read`] except that parts which cannot be read are left
+    /// out instead of failing the whole read. Returns the ranges of `buffer` that got filled.
+    fn read_sparce(
+        &mut self,
+        raddr: usize,
+        buffer: &mut [u8],
+    ) -> Result<Vec<Range<usize>>, IoError> {
+        self.read(raddr, buffer)?;
+        let filled = 0..buffer.len();
+        Ok(vec![filled])
+    }
+    /// Regions of the file that should be mapped into virtual address space once it is opened.
+    fn regions(&self) -> Vec<RIORegion> {
+        Vec::new()
+    }
+    /// Ranges of real addresses within the file that hold no data, such as gaps between
+    /// records of sparse formats.
+    fn holes(&self) -> Vec<Range<u64>> {
+        Vec::new()
+    }
+    /// Truncate or grow the file so that its data ends right before the real address `end`,
+    /// that is `end - raddr` bytes starting from the `raddr` it was opened at. Grown parts are
+    /// zero filled unless the plugin has its own fill value.
+    fn resize(&mut self, _end: u64) -> Result<(), IoError> {
+        Err(IoError::Custom(
+            "Plugin doesn't support resizing".to_owned(),
+        ))
+    }
+    /// Insert `data` at `raddr` moving everything after it forward. Plugins of sparse formats
+    /// implement this by rewriting their records and return `Ok(true)`, otherwise `Ok(false)`
+    /// is returned and the file gets resized then its tail gets moved using
+    /// [`RIOPluginOperations::read`] and [`RIOPluginOperations::write`].
+    fn insert(&mut self, _raddr: usize, _data: &[u8]) -> Result<bool, IoError> {
+        Ok(false)
+    }
+    /// Remove `size` bytes at `raddr` moving everything after them backward, same as
+    /// [`RIOPluginOperations::insert`] `Ok(false)` is returned unless the plugin handles it.
+    fn remove(&mut self, _raddr: usize, _size: usize) -> Result<bool, IoError> {
+        Ok(false)
+    }
+    /// Returns true if written data only lives in memory and is gone once the file is closed.
+    /// Writes recorded in the journal are done again when such files are reopened by projects.
+    fn is_volatile(&self) -> bool {
+        false
+    }
 }
 
 struct DefPluginOperations;
@@ -69,3 +163,69 @@ impl Default for Box<dyn RIOPluginOperations + Sync + Send> {
         Box::new(DefPluginOperations)
     }
 }
+
+/// Version of the interface between [`RIO`] and plugins loaded from shared libraries, it is
+/// bumped whenever [`RIOPluginDeclaration`], [`RIOPlugin`], [`RIOPluginOperations`] or any type
+/// they use changes.
+///
+/// [`RIO`]: crate::RIO
+pub const RIO_PLUGIN_ABI_VERSION: u32 = 6;
+
+/// Output of `rustc --version` for the compiler that built rair.
+pub const RIO_PLUGIN_RUSTC_VERSION: &str = env!("RAIR_RUSTC_VERSION");
+
+/// Versions every shared library plugin must be built with.
+pub const RIO_PLUGIN_VERSIONS: PluginVersions = PluginVersions {
+    abi: RIO_PLUGIN_ABI_VERSION,
+    rustc: RIO_PLUGIN_RUSTC_VERSION,
+    crate_version: env!("CARGO_PKG_VERSION"),
+};
+
+/// Name of the symbol every shared library plugin exports, see [`export_rio_plugin`].
+pub const RIO_PLUGIN_SYMBOL: &str = "rair_io_plugin";
+
+/// Entry point of a shared library plugin. This is not a stable ABI, the plugin hands a Rust
+/// trait object over to [`RIO`] so it must be built by the same compiler against the same version
+/// of rair-io. `versions` is checked against [`RIO_PLUGIN_VERSIONS`] before `plugin` is ever
+/// called.
+///
+/// [`RIO`]: crate::RIO
+#[repr(C)]
+pub struct RIOPluginDeclaration {
+    /// [`RIO_PLUGIN_VERSIONS`] the plugin was built with.
+    pub versions: PluginVersions,
+    /// Function that creates the plugin.
+    pub plugin: fn() -> Box<dyn RIOPlugin + Sync + Send>,
+}
+
+impl PluginDeclaration for RIOPluginDeclaration {
+    const SYMBOL: &'static str = RIO_PLUGIN_SYMBOL;
+    const VERSIONS: PluginVersions = RIO_PLUGIN_VERSIONS;
+    fn versions(&self) -> &PluginVersions {
+        &self.versions
+    }
+}
+
+/// Export the plugin created by function `$plugin` out of a `cdylib` crate so that it can be
+/// loaded using [`RIO::load_plugin_file`].
+///
+/// ```ignore
+/// fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+///     Box::new(MyPlugin)
+/// }
+///
+/// rair_io::export_rio_plugin!(plugin);
+/// ```
+///
+/// [`RIO::load_plugin_file`]: crate::RIO::load_plugin_file
+#[macro_export]
+macro_rules! export_rio_plugin {
+    ($plugin:path) => {
+        #[allow(non_upper_case_globals)]
+        #[no_mangle]
+        pub static rair_io_plugin: $crate::RIOPluginDeclaration = $crate::RIOPluginDeclaration {
+            versions: $crate::RIO_PLUGIN_VERSIONS,
+            plugin: $plugin,
+        };
+    };
+}
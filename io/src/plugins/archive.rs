@@ -0,0 +1,582 @@
+//! RIO plugin that opens single members of zip and tar archives.
+
+use super::compress::Codec;
+use super::defaultplugin;
+use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations};
+use crate::utils::{IoError, IoMode};
+use flate2::read::DeflateDecoder;
+use std::io::{self, Read};
+
+const METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Archive",
+    desc: "This plugin is used to open a single member of zip or tar archives as in \
+           zip://archive.zip!path/inside or tar://archive.tar!path/inside. Tar archives \
+           may be compressed with gzip, xz or zstd. Members can only be modified in \
+           Copy-On-Write mode and changes can't be committed back to the archive.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+const BLOCK_SIZE: usize = 512; // tar header and data alignment
+
+/// Member of an archive as returned by [`RIO::archive_members`].
+///
+/// [`RIO::archive_members`]: crate::RIO::archive_members
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ArchiveMember {
+    /// Path of the member inside the archive.
+    pub name: String,
+    /// Size of the member once extracted.
+    pub size: u64,
+    /// URI that opens the member.
+    pub uri: String,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Zip,
+    Tar,
+}
+
+impl Format {
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme {
+            "zip" => Some(Format::Zip),
+            "tar" => Some(Format::Tar),
+            _ => None,
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::Tar => "tar",
+        }
+    }
+}
+
+/// Location of a member inside the (decompressed) archive data.
+struct Entry {
+    name: String,
+    size: usize,
+    offset: usize,
+    stored_size: usize,
+    method: u16, // zip compression method, tar members are always stored.
+}
+
+fn le(data: &[u8], offset: usize, size: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset.checked_add(size)?)?;
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |acc, b| (acc << 8u32) | *b as usize),
+    )
+}
+
+fn zip_entries(data: &[u8]) -> Option<Vec<Entry>> {
+    // end of central directory record is 22 bytes followed by an optional comment.
+    let eocd = (0..=data.len().checked_sub(22)?)
+        .rev()
+        .find(|i| data[*i..*i + 4] == [0x50, 0x4b, 0x05, 0x06])?;
+    let count = le(data, eocd + 10, 2)?;
+    let mut offset = le(data, eocd + 16, 4)?;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if le(data, offset, 4)? != 0x0201_4b50 {
+            return None;
+        }
+        let name_len = le(data, offset + 28, 2)?;
+        let name = data.get(offset + 46..offset + 46 + name_len)?;
+        let local = le(data, offset + 42, 4)?;
+        if le(data, local, 4)? != 0x0403_4b50 {
+            return None;
+        }
+        // local header has its own name and extra field lengths.
+        let data_offset = local + 30 + le(data, local + 26, 2)? + le(data, local + 28, 2)?;
+        let entry = Entry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            size: le(data, offset + 24, 4)?,
+            offset: data_offset,
+            stored_size: le(data, offset + 20, 4)?,
+            method: le(data, offset + 10, 2)? as u16,
+        };
+        if !entry.name.ends_with('/') {
+            entries.push(entry);
+        }
+        offset += 46 + name_len + le(data, offset + 30, 2)? + le(data, offset + 32, 2)?;
+    }
+    Some(entries)
+}
+
+fn c_str(data: &[u8]) -> String {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn octal(data: &[u8]) -> Option<usize> {
+    let text = c_str(data);
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(0);
+    }
+    usize::from_str_radix(text, 8).ok()
+}
+
+fn tar_entries(data: &[u8]) -> Option<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    let mut long_name = None;
+    while let Some(header) = data.get(offset..offset + BLOCK_SIZE) {
+        // archives end with zero filled blocks.
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let size = octal(&header[124..136])?;
+        let start = offset + BLOCK_SIZE;
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = c_str(&header[..100]);
+            let prefix = c_str(&header[345..500]);
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{prefix}/{name}")
+            } else {
+                name
+            }
+        });
+        match header[156] {
+            b'0' | 0 => entries.push(Entry {
+                name,
+                size,
+                offset: start,
+                stored_size: size,
+                method: 0,
+            }),
+            // GNU long name of the next member.
+            b'L' => long_name = Some(c_str(data.get(start..start + size)?)),
+            _ => (),
+        }
+        offset = start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    }
+    Some(entries)
+}
+
+/// Decompress `data` if needed and locate every member inside it.
+fn unpack(format: Format, data: Vec<u8>) -> Result<(Vec<u8>, Vec<Entry>), IoError> {
+    let (data, entries) = match format {
+        Format::Zip => {
+            let entries = zip_entries(&data);
+            (data, entries)
+        }
+        Format::Tar => {
+            let data = match Codec::from_magic(&data) {
+                Some(codec) => codec.decompress(&data)?,
+                None => data,
+            };
+            let entries = tar_entries(&data);
+            (data, entries)
+        }
+    };
+    match entries {
+        Some(entries) => Ok((data, entries)),
+        None => Err(IoError::Custom(format!(
+            "Corrupted {} archive",
+            format.name()
+        ))),
+    }
+}
+
+fn extract(data: &[u8], entry: &Entry) -> Result<Vec<u8>, IoError> {
+    let Some(stored) = data.get(entry.offset..entry.offset + entry.stored_size) else {
+        return Err(IoError::Custom(format!(
+            "Member {} is out of archive bounds",
+            entry.name
+        )));
+    };
+    let extracted = match entry.method {
+        0 => stored.to_vec(),
+        8 => {
+            // size in the header is not trusted, so nothing is preallocated. Inflating stops
+            // right after that size so a member that inflates further is caught below.
+            let mut extracted = Vec::new();
+            DeflateDecoder::new(stored)
+                .take(entry.size as u64 + 1)
+                .read_to_end(&mut extracted)
+                .map_err(|e| IoError::Custom(format!("Failed to inflate {}: {e}", entry.name)))?;
+            extracted
+        }
+        method => {
+            return Err(IoError::Custom(format!(
+                "Unsupported zip compression method {method}"
+            )))
+        }
+    };
+    if extracted.len() != entry.size {
+        return Err(IoError::Custom(format!(
+            "Size of member {} doesn't match its header",
+            entry.name
+        )));
+    }
+    Ok(extracted)
+}
+
+/// List members of the archive opened as `uri` whose content is `data`.
+pub(crate) fn members(uri: &str, data: Vec<u8>) -> Result<Vec<ArchiveMember>, IoError> {
+    let format = if data.starts_with(b"PK") {
+        Format::Zip
+    } else {
+        Format::Tar
+    };
+    let (_, entries) = unpack(format, data)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| ArchiveMember {
+            uri: format!("{}://{uri}!{}", format.name(), entry.name),
+            name: entry.name,
+            size: entry.size as u64,
+        })
+        .collect())
+}
+
+struct ArchiveInternal {
+    data: Vec<u8>,
+}
+
+impl RIOPluginOperations for ArchiveInternal {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        if self.data.len() < raddr + buffer.len() {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "BufferOverflow",
+            )));
+        }
+        buffer.copy_from_slice(&self.data[raddr..raddr + buffer.len()]);
+        Ok(())
+    }
+
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        if raddr + buffer.len() > self.data.len() {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "BufferOverflow",
+            )));
+        }
+        self.data[raddr..raddr + buffer.len()].copy_from_slice(buffer);
+        Ok(())
+    }
+}
+
+struct ArchivePlugin {
+    defaultplugin: Box<dyn RIOPlugin + Sync + Send>, // defaultplugin
+}
+
+impl ArchivePlugin {
+    /// Split `scheme://archive!member` into format, archive and member.
+    fn split_uri(uri: &str) -> Option<(Format, &str, &str)> {
+        let (scheme, path) = uri.split_once("://")?;
+        let (archive, member) = path.rsplit_once('!')?;
+        Some((Format::from_scheme(scheme)?, archive, member))
+    }
+}
+
+impl RIOPlugin for ArchivePlugin {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &METADATA
+    }
+
+    fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        let Some((_, archive, _)) = ArchivePlugin::split_uri(uri) else {
+            return Err(IoError::Custom(
+                "Expected [zip|tar]://[archive]![member]".to_owned(),
+            ));
+        };
+        let def_desc = self.defaultplugin.open(archive, IoMode::READ)?;
+        self.open_backed(uri, flags, def_desc)
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.split_once("://")
+            .is_some_and(|(scheme, _)| Format::from_scheme(scheme).is_some())
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, _flags: IoMode) -> Option<(&'a str, IoMode)> {
+        let (_, archive, _) = ArchivePlugin::split_uri(uri)?;
+        Some((archive, IoMode::READ))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        mut backing: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        let Some((format, _, member)) = ArchivePlugin::split_uri(uri) else {
+            return Err(IoError::Custom(
+                "Expected [zip|tar]://[archive]![member]".to_owned(),
+            ));
+        };
+        if flags.contains(IoMode::WRITE) && !flags.contains(IoMode::COW) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Archive members can only be modified in Copy-On-Write mode",
+            )));
+        }
+        let mut data = vec![0; backing.size as usize];
+        backing.plugin_operations.read(0, &mut data)?;
+        let (data, entries) = unpack(format, data)?;
+        let Some(entry) = entries.iter().find(|entry| entry.name == member) else {
+            return Err(IoError::Custom(format!(
+                "Archive has no member named {member}"
+            )));
+        };
+        let internal = ArchiveInternal {
+            data: extract(&data, entry)?,
+        };
+        Ok(RIOPluginDesc {
+            name: uri.to_owned(),
+            perm: flags,
+            raddr: 0,
+            size: internal.data.len() as u64,
+            plugin_operations: Box::new(internal),
+        })
+    }
+}
+
+pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+    Box::new(ArchivePlugin {
+        defaultplugin: defaultplugin::plugin(),
+    })
+}
+
+#[cfg(test)]
+mod test_archive {
+    use super::*;
+    use crate::RIO;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+    use std::path::Path;
+    use test_file::*;
+
+    const HELLO: &[u8] = b"Hello, World!\n";
+
+    fn u16le(out: &mut Vec<u8>, value: usize) {
+        out.extend((0..2u32).map(|i| (value >> (i * 8)) as u8));
+    }
+
+    fn u32le(out: &mut Vec<u8>, value: usize) {
+        out.extend((0..4u32).map(|i| (value >> (i * 8)) as u8));
+    }
+
+    /// zip archive with one stored and one deflated member, crc32 is never checked.
+    fn zip() -> Vec<u8> {
+        zip_sized(DATA.len())
+    }
+
+    /// Same as [`zip`] but the header claims the deflated member is `data_size` bytes.
+    fn zip_sized(data_size: usize) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(DATA).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let members: [(&str, u16, &[u8], usize); 3] = [
+            ("dir/", 0, b"", 0),
+            ("dir/hello.txt", 0, HELLO, HELLO.len()),
+            ("data.bin", 8, &deflated, data_size),
+        ];
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, method, stored, size) in members {
+            let local = out.len();
+            u32le(&mut out, 0x0403_4b50);
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            u16le(&mut out, method as usize);
+            out.extend_from_slice(&[0; 8]);
+            u32le(&mut out, stored.len());
+            u32le(&mut out, size);
+            u16le(&mut out, name.len());
+            u16le(&mut out, 0);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(stored);
+            u32le(&mut central, 0x0201_4b50);
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            u16le(&mut central, method as usize);
+            central.extend_from_slice(&[0; 8]);
+            u32le(&mut central, stored.len());
+            u32le(&mut central, size);
+            u16le(&mut central, name.len());
+            central.extend_from_slice(&[0; 12]);
+            u32le(&mut central, local);
+            central.extend_from_slice(name.as_bytes());
+        }
+        let offset = out.len();
+        out.extend_from_slice(&central);
+        u32le(&mut out, 0x0605_4b50);
+        out.extend_from_slice(&[0; 4]);
+        u16le(&mut out, members.len());
+        u16le(&mut out, members.len());
+        u32le(&mut out, central.len());
+        u32le(&mut out, offset);
+        u16le(&mut out, 0);
+        out
+    }
+
+    fn archive() -> Vec<u8> {
+        let long_name = format!("{}/data.bin", "a".repeat(120));
+        tar(&[("dir/", &[]), ("hello.txt", HELLO), (&long_name, DATA)])
+    }
+
+    fn test_zip_cb(path: &Path) {
+        let mut io = RIO::new();
+        let path = path.to_string_lossy();
+        let members = io.archive_members(&path).unwrap();
+        assert_eq!(
+            members,
+            vec![
+                ArchiveMember {
+                    name: "dir/hello.txt".to_owned(),
+                    size: HELLO.len() as u64,
+                    uri: format!("zip://{path}!dir/hello.txt"),
+                },
+                ArchiveMember {
+                    name: "data.bin".to_owned(),
+                    size: DATA.len() as u64,
+                    uri: format!("zip://{path}!data.bin"),
+                },
+            ]
+        );
+        io.open_at(&members[0].uri, IoMode::READ, 0x1000).unwrap();
+        io.open_at(&members[1].uri, IoMode::COW, 0x2000).unwrap();
+        let mut hello = vec![0; HELLO.len()];
+        io.pread(0x1000, &mut hello).unwrap();
+        assert_eq!(hello, HELLO);
+        let mut data = vec![0; DATA.len()];
+        io.pread(0x2000, &mut data).unwrap();
+        assert_eq!(data, DATA);
+        io.pwrite(0x2000, &[0xff]).unwrap();
+        io.pread(0x2000, &mut data[..1]).unwrap();
+        assert_eq!(data[0], 0xff);
+        assert_eq!(
+            io.open(&format!("zip://{path}!missing"), IoMode::READ)
+                .err()
+                .unwrap(),
+            IoError::Custom("Archive has no member named missing".to_owned())
+        );
+        assert_eq!(
+            io.open(&members[0].uri, IoMode::READ | IoMode::WRITE)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Archive members can only be modified in Copy-On-Write mode"
+        );
+    }
+
+    #[test]
+    fn test_zip() {
+        operate_on_file(&test_zip_cb, &zip());
+    }
+
+    fn test_tar_cb(path: &Path) {
+        let mut io = RIO::new();
+        let path = path.to_string_lossy();
+        let members = io.archive_members(&path).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].uri, format!("tar://{path}!hello.txt"));
+        assert_eq!(members[1].name, format!("{}/data.bin", "a".repeat(120)));
+        assert_eq!(members[1].size, DATA.len() as u64);
+        io.open_at(&members[0].uri, IoMode::READ, 0x1000).unwrap();
+        io.open_at(&members[1].uri, IoMode::READ, 0x2000).unwrap();
+        let mut hello = vec![0; HELLO.len()];
+        io.pread(0x1000, &mut hello).unwrap();
+        assert_eq!(hello, HELLO);
+        let mut data = vec![0; DATA.len()];
+        io.pread(0x2000, &mut data).unwrap();
+        assert_eq!(data, DATA);
+    }
+
+    #[test]
+    fn test_tar() {
+        operate_on_file(&test_tar_cb, &archive());
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&archive()).unwrap();
+        operate_on_file(&test_tar_cb, &encoder.finish().unwrap());
+    }
+
+    fn test_nested_cb(paths: &[&Path]) {
+        let mut io = RIO::new();
+        // tar archive stored inside a zip archive.
+        let uri = format!(
+            "tar://zip://{}!inner.tar!hello.txt",
+            paths[0].to_string_lossy()
+        );
+        let hndl = io.open(&uri, IoMode::READ).unwrap();
+        assert_eq!(io.hndl_to_desc(hndl).unwrap().size(), HELLO.len() as u64);
+        let uri = format!("zip://{}!inner.tar", paths[0].to_string_lossy());
+        assert_eq!(io.archive_members(&uri).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_nested() {
+        let tar = archive();
+        let mut zip = Vec::new();
+        u32le(&mut zip, 0x0403_4b50);
+        zip.extend_from_slice(&[20, 0, 0, 0, 0, 0]);
+        zip.extend_from_slice(&[0; 8]);
+        u32le(&mut zip, tar.len());
+        u32le(&mut zip, tar.len());
+        u16le(&mut zip, 9);
+        u16le(&mut zip, 0);
+        zip.extend_from_slice(b"inner.tar");
+        zip.extend_from_slice(&tar);
+        let offset = zip.len();
+        u32le(&mut zip, 0x0201_4b50);
+        zip.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0]);
+        zip.extend_from_slice(&[0; 8]);
+        u32le(&mut zip, tar.len());
+        u32le(&mut zip, tar.len());
+        u16le(&mut zip, 9);
+        zip.extend_from_slice(&[0; 12]);
+        u32le(&mut zip, 0);
+        zip.extend_from_slice(b"inner.tar");
+        let central = zip.len() - offset;
+        u32le(&mut zip, 0x0605_4b50);
+        zip.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        u32le(&mut zip, central);
+        u32le(&mut zip, offset);
+        u16le(&mut zip, 0);
+        operate_on_files(&test_nested_cb, &[&zip]);
+    }
+
+    fn test_bad_size_cb(path: &Path) {
+        let mut io = RIO::new();
+        let uri = format!("zip://{}!data.bin", path.to_string_lossy());
+        assert_eq!(
+            io.open(&uri, IoMode::READ).err().unwrap(),
+            IoError::Custom("Size of member data.bin doesn't match its header".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_bad_size() {
+        operate_on_file(&test_bad_size_cb, &zip_sized(0xffff_ffff));
+        operate_on_file(&test_bad_size_cb, &zip_sized(4));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut p = plugin();
+        assert!(p.accept_uri("zip://file!member"));
+        assert!(p.accept_uri("tar://gzip://file!member"));
+        assert!(!p.accept_uri("rar://file!member"));
+        assert_eq!(
+            p.open("zip://file", IoMode::READ).err().unwrap(),
+            IoError::Custom("Expected [zip|tar]://[archive]![member]".to_owned())
+        );
+        assert_eq!(
+            members("file", b"PK garbage".to_vec()).err().unwrap(),
+            IoError::Custom("Corrupted zip archive".to_owned())
+        );
+        assert_eq!(
+            members("file", vec![1; BLOCK_SIZE]).err().unwrap(),
+            IoError::Custom("Corrupted tar archive".to_owned())
+        );
+    }
+}
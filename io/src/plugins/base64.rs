@@ -201,6 +201,27 @@ impl RIOPluginOperations for Base64Internal {
         self.write_aligned_blocks(raddr, buffer)?;
         Ok(())
     }
+
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        // the underlying file already holds the base64 encoded data.
+        self.file.commit(path)
+    }
+
+    fn commit_data(&mut self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        self.file
+            .commit_data(path, BASE64_STANDARD.encode(data).as_bytes())
+    }
+
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        let mut data = vec![0; self.len() as usize];
+        self.read(0, &mut data)?;
+        data.resize(end as usize, 0);
+        let encoded = BASE64_STANDARD.encode(&data);
+        self.file.resize(encoded.len() as u64)?;
+        self.file.write(0, encoded.as_bytes())?;
+        self.len = end;
+        Ok(())
+    }
 }
 
 struct Base64Plugin {
@@ -225,14 +246,32 @@ impl RIOPlugin for Base64Plugin {
     }
 
     fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
-        let mut def_desc = self
+        let def_desc = self
             .defaultplugin
             .open(&Base64Plugin::uri_to_path(uri).to_string_lossy(), flags)?;
+        self.open_backed(uri, flags, def_desc)
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.starts_with("b64://")
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, flags: IoMode) -> Option<(&'a str, IoMode)> {
+        Some((uri.strip_prefix("b64://")?, flags))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        mut def_desc: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
         let mut paddings = [0; 4];
-        if def_desc
-            .plugin_operations
-            .read(def_desc.size as usize - paddings.len(), &mut paddings)
-            .is_err()
+        if def_desc.size < paddings.len() as u64
+            || def_desc
+                .plugin_operations
+                .read(def_desc.size as usize - paddings.len(), &mut paddings)
+                .is_err()
         {
             return Err(IoError::Custom("Corrupted base64 data".to_owned()));
         };
@@ -251,11 +290,6 @@ impl RIOPlugin for Base64Plugin {
         };
         Ok(desc)
     }
-
-    fn accept_uri(&self, uri: &str) -> bool {
-        let split: Vec<&str> = uri.split("://").collect();
-        split.len() == 2 && split[0] == "b64"
-    }
 }
 
 pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
@@ -265,6 +299,7 @@ pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
 #[cfg(test)]
 mod test_base64 {
     use super::*;
+    use std::fs;
     use test_file::*;
 
     #[test]
@@ -429,4 +464,26 @@ mod test_base64 {
             "../testing_binaries/rio/base64/two_pad.b64",
         );
     }
+
+    fn resize_cb(path: &Path) {
+        let mut p = plugin();
+        let mut uri = "b64://".to_owned();
+        uri.push_str(&path.to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        file.plugin_operations.resize(2).unwrap();
+        let mut data = [0; 2];
+        file.plugin_operations.read(0, &mut data).unwrap();
+        assert_eq!(data, [1, 2]);
+        assert!(file.plugin_operations.read(0, &mut [0; 3]).is_err());
+        file.plugin_operations.resize(6).unwrap();
+        let mut data = [0xff; 6];
+        file.plugin_operations.read(0, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 0, 0, 0, 0]);
+        drop(file);
+        assert_eq!(fs::read(path).unwrap(), b"AQIAAAAA");
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_file(&resize_cb, b"AQIDBA==");
+    }
 }
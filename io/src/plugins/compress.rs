@@ -0,0 +1,278 @@
+//! RIO plugin that opens gzip, zlib, xz and zstd compressed files.
+
+use super::defaultplugin;
+use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations};
+use crate::utils::{IoError, IoMode};
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use ruzstd::decoding::StreamingDecoder;
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Compress",
+    desc: "This plugin is used to open compressed files as in gzip://, zlib://, xz:// \
+           or zstd://path/to/file. Decompressed data can only be modified in \
+           Copy-On-Write mode and gets compressed again once committed.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+#[derive(Clone, Copy)]
+pub(crate) enum Codec {
+    Gzip,
+    Zlib,
+    Xz,
+    Zstd,
+}
+
+impl Codec {
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme {
+            "gzip" => Some(Codec::Gzip),
+            "zlib" => Some(Codec::Zlib),
+            "xz" => Some(Codec::Xz),
+            "zstd" => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+    /// Guess codec of compressed `data` from its magic bytes.
+    pub(crate) fn from_magic(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x1f, 0x8b, ..] => Some(Codec::Gzip),
+            [0xfd, b'7', b'z', b'X', b'Z', 0, ..] => Some(Codec::Xz),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zlib => "zlib",
+            Codec::Xz => "xz",
+            Codec::Zstd => "zstd",
+        }
+    }
+    pub(crate) fn decompress(self, data: &[u8]) -> Result<Vec<u8>, IoError> {
+        let mut out = Vec::new();
+        let result = match self {
+            Codec::Gzip => MultiGzDecoder::new(data).read_to_end(&mut out).map(drop),
+            Codec::Zlib => ZlibDecoder::new(data).read_to_end(&mut out).map(drop),
+            Codec::Xz => lzma_rs::xz_decompress(&mut &*data, &mut out)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            Codec::Zstd => StreamingDecoder::new(data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+                .and_then(|mut decoder| decoder.read_to_end(&mut out).map(drop)),
+        };
+        match result {
+            Ok(()) => Ok(out),
+            Err(e) => Err(IoError::Custom(format!(
+                "Failed to decompress {} data: {e}",
+                self.name()
+            ))),
+        }
+    }
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, IoError> {
+        match self {
+            Codec::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Codec::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Codec::Xz => {
+                let mut out = Vec::new();
+                lzma_rs::xz_compress(&mut &*data, &mut out)?;
+                Ok(out)
+            }
+            Codec::Zstd => Ok(compress_to_vec(data, CompressionLevel::Fastest)),
+        }
+    }
+}
+
+struct CompressInternal {
+    file: Box<dyn RIOPluginOperations + Sync + Send>, // compressed data
+    codec: Codec,
+    data: Vec<u8>,
+}
+
+impl RIOPluginOperations for CompressInternal {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        if self.data.len() < raddr + buffer.len() {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "BufferOverflow",
+            )));
+        }
+        buffer.copy_from_slice(&self.data[raddr..raddr + buffer.len()]);
+        Ok(())
+    }
+
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        if raddr + buffer.len() > self.data.len() {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "BufferOverflow",
+            )));
+        }
+        self.data[raddr..raddr + buffer.len()].copy_from_slice(buffer);
+        Ok(())
+    }
+
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        let data = self.codec.compress(&self.data)?;
+        self.file.commit_data(path, &data)
+    }
+
+    fn commit_data(&mut self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        self.data = data.to_vec();
+        self.commit(path)
+    }
+}
+
+struct CompressPlugin {
+    defaultplugin: Box<dyn RIOPlugin + Sync + Send>, // defaultplugin
+}
+
+impl CompressPlugin {
+    /// Split `scheme://path` into codec and path.
+    fn split_uri(uri: &str) -> Option<(Codec, &str)> {
+        let (scheme, path) = uri.split_once("://")?;
+        Some((Codec::from_scheme(scheme)?, path))
+    }
+}
+
+impl RIOPlugin for CompressPlugin {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &METADATA
+    }
+
+    fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        let Some((_, path)) = CompressPlugin::split_uri(uri) else {
+            return Err(IoError::Custom(
+                "Expected [gzip|zlib|xz|zstd]://[path]".to_owned(),
+            ));
+        };
+        let def_desc = self.defaultplugin.open(path, IoMode::READ)?;
+        self.open_backed(uri, flags, def_desc)
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        CompressPlugin::split_uri(uri).is_some()
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, _flags: IoMode) -> Option<(&'a str, IoMode)> {
+        let (_, path) = CompressPlugin::split_uri(uri)?;
+        Some((path, IoMode::READ))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        mut backing: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        let Some((codec, _)) = CompressPlugin::split_uri(uri) else {
+            return Err(IoError::Custom(
+                "Expected [gzip|zlib|xz|zstd]://[path]".to_owned(),
+            ));
+        };
+        if flags.contains(IoMode::WRITE) && !flags.contains(IoMode::COW) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Compressed files can only be modified in Copy-On-Write mode",
+            )));
+        }
+        let mut compressed = vec![0; backing.size as usize];
+        backing.plugin_operations.read(0, &mut compressed)?;
+        let data = codec.decompress(&compressed)?;
+        let internal = CompressInternal {
+            file: backing.plugin_operations,
+            codec,
+            data,
+        };
+        Ok(RIOPluginDesc {
+            name: uri.to_owned(),
+            perm: flags,
+            raddr: 0,
+            size: internal.data.len() as u64,
+            plugin_operations: Box::new(internal),
+        })
+    }
+}
+
+pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+    Box::new(CompressPlugin {
+        defaultplugin: defaultplugin::plugin(),
+    })
+}
+
+#[cfg(test)]
+mod test_compress {
+    use super::*;
+    use crate::RIO;
+    use std::fs;
+    use test_file::*;
+
+    const DATA: &[u8] = b"The quick brown fox jumps over the lazy dog.\n";
+    const CODECS: [Codec; 4] = [Codec::Gzip, Codec::Zlib, Codec::Xz, Codec::Zstd];
+
+    fn test_open_cb(path: &Path, codec: Codec) {
+        let mut io = RIO::new();
+        let uri = format!("{}://{}", codec.name(), path.to_string_lossy());
+        let hndl = io.open(&uri, IoMode::READ | IoMode::COW).unwrap();
+        assert_eq!(io.hndl_to_desc(hndl).unwrap().size(), DATA.len() as u64);
+        let mut data = vec![0; DATA.len()];
+        io.pread(0, &mut data).unwrap();
+        assert_eq!(data, DATA);
+        io.pwrite(4, b"QUICK").unwrap();
+        // the compressed file is left untouched until changes are committed.
+        let compressed = fs::read(path).unwrap();
+        assert_eq!(codec.decompress(&compressed).unwrap(), DATA);
+        io.commit(hndl, None).unwrap();
+        let compressed = fs::read(path).unwrap();
+        let decompressed = codec.decompress(&compressed).unwrap();
+        assert_eq!(
+            decompressed,
+            b"The QUICK brown fox jumps over the lazy dog.\n"
+        );
+    }
+
+    #[test]
+    fn test_open() {
+        for codec in CODECS {
+            let compressed = codec.compress(DATA).unwrap();
+            operate_on_file(&|path| test_open_cb(path, codec), &compressed);
+        }
+    }
+
+    fn test_errors_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = format!("gzip://{}", path.to_string_lossy());
+        assert_eq!(
+            p.open(&uri, IoMode::READ).err().unwrap().to_string(),
+            "Failed to decompress gzip data: invalid gzip header."
+        );
+        assert!(p.open(&uri, IoMode::READ | IoMode::WRITE).is_err());
+        let uri = format!("zstd://{}", path.to_string_lossy());
+        assert!(p.open(&uri, IoMode::READ).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        operate_on_file(&test_errors_cb, DATA);
+        let p = plugin();
+        for scheme in ["gzip", "zlib", "xz", "zstd"] {
+            assert!(p.accept_uri(&format!("{scheme}://file")));
+        }
+        assert!(!p.accept_uri("bz2://file"));
+        assert!(!p.accept_uri("file"));
+    }
+}
@@ -4,26 +4,30 @@ use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperat
 use crate::utils::{IoError, IoMode};
 use core::ops::Deref;
 use memmap::{Mmap, MmapMut, MmapOptions};
-use std::fs::OpenOptions;
+use std::fs::{self, File, OpenOptions};
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 enum FileInternals {
     Map(Mmap),
-    MutMap(MmapMut),
+    // the file is kept if writes go through to it, otherwise it is Copy-On-Write.
+    MutMap(MmapMut, Option<File>),
+    // Copy-On-Write file that got resized.
+    Buffer(Vec<u8>),
 }
 
 impl FileInternals {
     fn len(&self) -> usize {
         match self {
             FileInternals::Map(m) => m.len(),
-            FileInternals::MutMap(m) => m.len(),
+            FileInternals::MutMap(m, _) => m.len(),
+            FileInternals::Buffer(b) => b.len(),
         }
     }
-    fn as_mut(&mut self) -> Option<&mut MmapMut> {
-        if let FileInternals::MutMap(mutmap) = self {
-            Some(mutmap)
-        } else {
-            None
+    fn as_mut(&mut self) -> Option<&mut [u8]> {
+        match self {
+            FileInternals::Map(_) => None,
+            FileInternals::MutMap(mutmap, _) => Some(mutmap),
+            FileInternals::Buffer(buffer) => Some(buffer),
         }
     }
 }
@@ -39,7 +43,8 @@ impl Deref for FileInternals {
     fn deref(&self) -> &[u8] {
         match self {
             FileInternals::Map(m) => m,
-            FileInternals::MutMap(m) => m,
+            FileInternals::MutMap(m, _) => m,
+            FileInternals::Buffer(b) => b,
         }
     }
 }
@@ -72,10 +77,64 @@ impl RIOPluginOperations for FileInternals {
             )))
         }
     }
+
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        // copy the data out first, `path` might be the very file that is mapped.
+        let data = self.to_vec();
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    fn commit_data(&mut self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        match self {
+            FileInternals::Map(_) => Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            ))),
+            FileInternals::MutMap(mutmap, Some(file)) => {
+                mutmap.flush()?;
+                file.set_len(end)?;
+                *mutmap = unsafe { MmapOptions::new().map_mut(&*file)? };
+                Ok(())
+            }
+            FileInternals::MutMap(mutmap, None) => {
+                let mut buffer = mutmap.to_vec();
+                buffer.resize(end as usize, 0);
+                *self = FileInternals::Buffer(buffer);
+                Ok(())
+            }
+            FileInternals::Buffer(buffer) => {
+                buffer.resize(end as usize, 0);
+                Ok(())
+            }
+        }
+    }
 }
 
 struct FilePlugin;
 
+/// Path of the file on disk that got opened as `name`, plugins that keep their own copy of the
+/// data save it there after every write unless they are opened in Copy-On-Write mode. That is
+/// not possible when `name` is layered over another URI.
+pub(crate) fn backing_path(name: &str, flags: IoMode) -> Result<PathBuf, IoError> {
+    let path = FilePlugin::uri_to_path(name);
+    if flags.contains(IoMode::WRITE)
+        && !flags.contains(IoMode::COW)
+        && path.to_string_lossy().contains("://")
+    {
+        return Err(IoError::Parse(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Layered files can only be modified in Copy-On-Write mode",
+        )));
+    }
+    Ok(path.to_owned())
+}
+
 impl FilePlugin {
     fn uri_to_path(uri: &str) -> &Path {
         let path = uri.trim_start_matches("file://");
@@ -107,13 +166,13 @@ impl RIOPlugin for FilePlugin {
             let f = OpenOptions::new()
                 .read(true)
                 .open(FilePlugin::uri_to_path(uri))?;
-            file = FileInternals::MutMap(unsafe { MmapOptions::new().map_copy(&f)? });
+            file = FileInternals::MutMap(unsafe { MmapOptions::new().map_copy(&f)? }, None);
         } else if flags.contains(IoMode::WRITE) {
             let f = OpenOptions::new()
                 .read(true)
                 .write(true)
                 .open(FilePlugin::uri_to_path(uri))?;
-            file = FileInternals::MutMap(unsafe { MmapOptions::new().map_mut(&f)? });
+            file = FileInternals::MutMap(unsafe { MmapOptions::new().map_mut(&f)? }, Some(f));
         } else {
             let f = OpenOptions::new()
                 .read(true)
@@ -336,4 +395,29 @@ mod default_plugin_tests {
     fn test_write_errors() {
         operate_on_file(&test_write_errors_cb, DATA);
     }
+
+    fn test_resize_cb(path: &Path) {
+        let mut plugin = plugin();
+        let uri = path.to_string_lossy();
+        let mut desc = plugin.open(&uri, IoMode::READ).unwrap();
+        assert!(desc.plugin_operations.resize(4).is_err());
+        // copy on write files are only resized in memory
+        let mut desc = plugin.open(&uri, IoMode::COW).unwrap();
+        desc.plugin_operations.resize(4).unwrap();
+        desc.plugin_operations.resize(6).unwrap();
+        let buffer: &mut [u8] = &mut [0xff; 6];
+        desc.plugin_operations.read(0, buffer).unwrap();
+        assert_eq!(buffer, [0x00, 0x01, 0x01, 0x02, 0x00, 0x00]);
+        assert_eq!(fs::read(path).unwrap(), DATA);
+        let mut desc = plugin.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        desc.plugin_operations.resize(2).unwrap();
+        desc.plugin_operations.resize(3).unwrap();
+        desc.plugin_operations.write(2, &[0xff]).unwrap();
+        drop(desc);
+        assert_eq!(fs::read(path).unwrap(), [0x00, 0x01, 0xff]);
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_file(&test_resize_cb, DATA);
+    }
 }
@@ -0,0 +1,403 @@
+//! RIO plugin that opens a single architecture slice of Mach-O fat (universal) binaries.
+
+use super::defaultplugin;
+use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations};
+use crate::utils::{IoError, IoMode};
+use std::path::Path;
+
+const METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Fat",
+    desc: "This plugin is used to open one architecture out of Mach-O fat binaries, \
+           slices are selected by index or by architecture name as in \
+           fat://arm64/path/to/file or fat://0/path/to/file.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+
+/// Architecture names used for selecting slices along with their cpu types.
+const ARCHES: [(&str, u32); 7] = [
+    ("x86", 7),
+    ("x86_64", 0x0100_0007),
+    ("arm", 12),
+    ("arm64", 0x0100_000c),
+    ("arm64_32", 0x0200_000c),
+    ("ppc", 18),
+    ("ppc64", 0x0100_0012),
+];
+
+/// Header of one architecture slice inside a fat binary.
+struct FatSlice {
+    cputype: u32,
+    offset: u64,
+    size: u64,
+    // real address of the size field in the fat header, it is 64 bits wide in 64 bit headers.
+    size_raddr: usize,
+    is64: bool,
+}
+
+impl FatSlice {
+    /// Architecture name of the slice if known.
+    fn arch(&self) -> Option<&'static str> {
+        ARCHES
+            .iter()
+            .find(|(_, cputype)| *cputype == self.cputype)
+            .map(|(name, _)| *name)
+    }
+}
+
+fn read_u32(file: &mut dyn RIOPluginOperations, raddr: usize) -> Result<u32, IoError> {
+    let mut data = [0; 4];
+    file.read(raddr, &mut data)?;
+    Ok(u32::from_be_bytes(data))
+}
+
+fn read_u64(file: &mut dyn RIOPluginOperations, raddr: usize) -> Result<u64, IoError> {
+    let mut data = [0; 8];
+    file.read(raddr, &mut data)?;
+    Ok(u64::from_be_bytes(data))
+}
+
+/// Parse headers of all architecture slices in the fat binary `file`.
+fn parse_slices(file: &mut dyn RIOPluginOperations) -> Result<Vec<FatSlice>, IoError> {
+    let not_fat = || IoError::Custom("File is not a Mach-O fat binary".to_owned());
+    let magic = read_u32(file, 0).map_err(|_| not_fat())?;
+    let is64 = match magic {
+        FAT_MAGIC => false,
+        FAT_MAGIC_64 => true,
+        _ => return Err(not_fat()),
+    };
+    let nfat_arch = read_u32(file, 4)? as usize;
+    // nfat_arch is not trusted, reading headers fails long before it is reached.
+    let mut slices = Vec::new();
+    for i in 0..nfat_arch {
+        let slice = if is64 {
+            let raddr = 8 + i * 32;
+            FatSlice {
+                cputype: read_u32(file, raddr)?,
+                offset: read_u64(file, raddr + 8)?,
+                size: read_u64(file, raddr + 16)?,
+                size_raddr: raddr + 16,
+                is64,
+            }
+        } else {
+            let raddr = 8 + i * 20;
+            FatSlice {
+                cputype: read_u32(file, raddr)?,
+                offset: u64::from(read_u32(file, raddr + 8)?),
+                size: u64::from(read_u32(file, raddr + 12)?),
+                size_raddr: raddr + 12,
+                is64,
+            }
+        };
+        slices.push(slice);
+    }
+    Ok(slices)
+}
+
+/// Operations of the file a slice was opened from. Only the last slice of the file can be
+/// resized since no other slice comes after it, its size in the fat header is updated as well.
+struct FatSliceOperations {
+    file: Box<dyn RIOPluginOperations + Sync + Send>,
+    offset: u64,
+    size_raddr: usize,
+    is64: bool,
+    last: bool,
+}
+
+impl RIOPluginOperations for FatSliceOperations {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        self.file.read(raddr, buffer)
+    }
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        self.file.write(raddr, buffer)
+    }
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        self.file.commit(path)
+    }
+    fn commit_data(&mut self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        self.file.commit_data(path, data)
+    }
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        if !self.last {
+            return Err(IoError::Custom(
+                "Only the last slice of a fat binary can be resized".to_owned(),
+            ));
+        }
+        let size = end.saturating_sub(self.offset);
+        let size_field = if self.is64 {
+            size.to_be_bytes().to_vec()
+        } else {
+            let size =
+                u32::try_from(size).map_err(|_| IoError::Custom("Slice is too big".to_owned()))?;
+            size.to_be_bytes().to_vec()
+        };
+        self.file.resize(end)?;
+        self.file.write(self.size_raddr, &size_field)
+    }
+}
+
+struct FatPlugin {
+    defaultplugin: Box<dyn RIOPlugin + Sync + Send>,
+}
+
+impl FatPlugin {
+    /// Split `fat://slice/path` into slice selector and path.
+    fn split_uri(uri: &str) -> Result<(&str, &Path), IoError> {
+        let rest = uri.trim_start_matches("fat://");
+        match rest.split_once('/') {
+            Some((slice, path)) if !slice.is_empty() && !path.is_empty() => {
+                Ok((slice, Path::new(path)))
+            }
+            _ => Err(IoError::Custom(
+                "Expected fat://[index or architecture]/[path]".to_owned(),
+            )),
+        }
+    }
+    fn new() -> Self {
+        Self {
+            defaultplugin: defaultplugin::plugin(),
+        }
+    }
+}
+
+impl RIOPlugin for FatPlugin {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &METADATA
+    }
+    fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        let (_, path) = Self::split_uri(uri)?;
+        let def_desc = self.defaultplugin.open(&path.to_string_lossy(), flags)?;
+        self.open_backed(uri, flags, def_desc)
+    }
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.starts_with("fat://")
+    }
+    fn backing_uri<'a>(&self, uri: &'a str, flags: IoMode) -> Option<(&'a str, IoMode)> {
+        let (_, path) = uri.strip_prefix("fat://")?.split_once('/')?;
+        Some((path, flags))
+    }
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        mut def_desc: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        let (selector, _) = Self::split_uri(uri)?;
+        let slices = parse_slices(&mut *def_desc.plugin_operations)?;
+        let slice = match selector.parse::<usize>() {
+            Ok(index) => slices.get(index),
+            Err(_) => slices.iter().find(|slice| slice.arch() == Some(selector)),
+        };
+        let Some(slice) = slice else {
+            return Err(IoError::Custom(format!(
+                "Fat binary has no slice matching `{selector}`"
+            )));
+        };
+        if slice.offset.saturating_add(slice.size) > def_desc.size {
+            return Err(IoError::Custom("Slice is out of file bounds".to_owned()));
+        }
+        let last = slices.iter().all(|other| other.offset <= slice.offset);
+        let operations = FatSliceOperations {
+            file: def_desc.plugin_operations,
+            offset: slice.offset,
+            size_raddr: slice.size_raddr,
+            is64: slice.is64,
+            last,
+        };
+        Ok(RIOPluginDesc {
+            name: uri.to_owned(),
+            perm: flags,
+            raddr: slice.offset,
+            size: slice.size,
+            plugin_operations: Box::new(operations),
+        })
+    }
+}
+
+pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+    Box::new(FatPlugin::new())
+}
+
+#[cfg(test)]
+mod test_fat {
+    use super::*;
+    use crate::RIO;
+    use std::fs;
+    use test_file::*;
+
+    /// Fat binary with an `x86_64` slice at 0x50 and an arm64 slice at 0x60.
+    fn fat(is64: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        if is64 {
+            data.extend(FAT_MAGIC_64.to_be_bytes());
+            data.extend(2u32.to_be_bytes());
+            for (cputype, offset) in [(0x0100_0007u32, 0x50u64), (0x0100_000c, 0x60)] {
+                data.extend(cputype.to_be_bytes());
+                data.extend(3u32.to_be_bytes());
+                data.extend(offset.to_be_bytes());
+                data.extend(0x10u64.to_be_bytes());
+                data.extend([0; 8]);
+            }
+        } else {
+            data.extend(FAT_MAGIC.to_be_bytes());
+            data.extend(2u32.to_be_bytes());
+            for (cputype, offset) in [(0x0100_0007u32, 0x50u32), (0x0100_000c, 0x60)] {
+                data.extend(cputype.to_be_bytes());
+                data.extend(3u32.to_be_bytes());
+                data.extend(offset.to_be_bytes());
+                data.extend(0x10u32.to_be_bytes());
+                data.extend(0u32.to_be_bytes());
+            }
+        }
+        data.resize(0x50, 0);
+        data.extend([0xaa; 0x10]);
+        data.extend([0xbb; 0x10]);
+        data
+    }
+
+    fn test_open_cb(path: &Path) {
+        let mut p = plugin();
+        for (selector, byte) in [("0", 0xaa), ("x86_64", 0xaa), ("1", 0xbb), ("arm64", 0xbb)] {
+            let uri = format!("fat://{selector}/{}", path.to_string_lossy());
+            assert!(p.accept_uri(&uri));
+            let mut desc = p.open(&uri, IoMode::READ).unwrap();
+            assert_eq!(desc.size, 0x10);
+            let mut data = [0; 0x10];
+            let raddr = desc.raddr as usize;
+            desc.plugin_operations.read(raddr, &mut data).unwrap();
+            assert_eq!(data, [byte; 0x10]);
+        }
+        let uri = format!("fat://ppc/{}", path.to_string_lossy());
+        assert_eq!(
+            p.open(&uri, IoMode::READ).err().unwrap(),
+            IoError::Custom("Fat binary has no slice matching `ppc`".to_owned())
+        );
+        let uri = format!("fat://2/{}", path.to_string_lossy());
+        assert!(p.open(&uri, IoMode::READ).is_err());
+    }
+
+    #[test]
+    fn test_open() {
+        operate_on_file(&test_open_cb, &fat(false));
+        operate_on_file(&test_open_cb, &fat(true));
+    }
+
+    fn test_resize_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = |selector: &str| format!("fat://{selector}/{}", path.to_string_lossy());
+        let flags = IoMode::READ | IoMode::WRITE;
+        let mut desc = p.open(&uri("x86_64"), flags).unwrap();
+        assert_eq!(
+            desc.plugin_operations.resize(0x58).err().unwrap(),
+            IoError::Custom("Only the last slice of a fat binary can be resized".to_owned())
+        );
+        let mut desc = p.open(&uri("arm64"), flags).unwrap();
+        desc.plugin_operations.resize(0x68).unwrap();
+        drop(desc);
+        let desc = p.open(&uri("arm64"), flags).unwrap();
+        assert_eq!(desc.size, 0x8);
+        let mut desc = p.open(&uri("x86_64"), flags).unwrap();
+        assert_eq!(desc.size, 0x10);
+        let mut data = [0; 0x10];
+        desc.plugin_operations.read(0x50, &mut data).unwrap();
+        assert_eq!(data, [0xaa; 0x10]);
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x68);
+    }
+
+    #[test]
+    fn test_resize() {
+        operate_on_file(&test_resize_cb, &fat(false));
+        operate_on_file(&test_resize_cb, &fat(true));
+    }
+
+    fn test_rio_resize_cb(path: &Path) {
+        let mut io = RIO::new();
+        let uri = format!("fat://arm64/{}", path.to_string_lossy());
+        let hndl = io.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        io.resize(hndl, 0x20).unwrap();
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x80);
+        io.resize(hndl, 0x4).unwrap();
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x64);
+        let base = io.hndl_to_desc(hndl).unwrap().paddr_base();
+        let mut data = [0; 0x4];
+        io.pread(base, &mut data).unwrap();
+        assert_eq!(data, [0xbb; 0x4]);
+        let uri = format!("fat://x86_64/{}", path.to_string_lossy());
+        let hndl = io.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        let base = io.hndl_to_desc(hndl).unwrap().paddr_base();
+        let mut data = [0; 0x10];
+        io.pread(base, &mut data).unwrap();
+        assert_eq!(data, [0xaa; 0x10]);
+    }
+
+    #[test]
+    fn test_rio_resize() {
+        operate_on_file(&test_rio_resize_cb, &fat(false));
+    }
+
+    fn test_rio_insert_remove_cb(path: &Path) {
+        let mut io = RIO::new();
+        let uri = format!("fat://arm64/{}", path.to_string_lossy());
+        let hndl = io.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        let base = io.hndl_to_desc(hndl).unwrap().paddr_base();
+        io.insert(base + 0x2, &[0xcc; 4], false).unwrap();
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x74);
+        io.remove(base, 0x8, false).unwrap();
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x6c);
+        let mut data = [0; 0xc];
+        io.pread(base, &mut data).unwrap();
+        assert_eq!(data, [0xbb; 0xc]);
+        let mut io = RIO::new();
+        let uri = format!("fat://x86_64/{}", path.to_string_lossy());
+        let hndl = io.open(&uri, IoMode::READ | IoMode::WRITE).unwrap();
+        let base = io.hndl_to_desc(hndl).unwrap().paddr_base();
+        let err = IoError::Custom("Only the last slice of a fat binary can be resized".to_owned());
+        assert_eq!(io.insert(base, &[0], false).unwrap_err(), err);
+        io.pwrite(base, &[0xdd]).unwrap();
+        assert_eq!(io.remove(base, 0x1, false).unwrap_err(), err);
+        let mut data = [0; 0x10];
+        io.pread(base, &mut data).unwrap();
+        assert_eq!(data[0], 0xdd);
+        assert_eq!(data[1..], [0xaa; 0xf]);
+        assert_eq!(fs::metadata(path).unwrap().len(), 0x6c);
+    }
+
+    #[test]
+    fn test_rio_insert_remove() {
+        operate_on_file(&test_rio_insert_remove_cb, &fat(true));
+    }
+
+    fn test_not_fat_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = format!("fat://0/{}", path.to_string_lossy());
+        assert_eq!(
+            p.open(&uri, IoMode::READ).err().unwrap(),
+            IoError::Custom("File is not a Mach-O fat binary".to_owned())
+        );
+    }
+
+    fn test_truncated_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = format!("fat://0/{}", path.to_string_lossy());
+        p.open(&uri, IoMode::READ).err().unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        operate_on_file(&test_not_fat_cb, &[0x7f, b'E', b'L', b'F']);
+        operate_on_file(
+            &test_truncated_cb,
+            &[0xca, 0xfe, 0xba, 0xbe, 0xff, 0xff, 0xff, 0xff],
+        );
+        let mut p = plugin();
+        assert!(!p.accept_uri("b64://file"));
+        assert_eq!(
+            p.open("fat://file", IoMode::READ).err().unwrap(),
+            IoError::Custom("Expected fat://[index or architecture]/[path]".to_owned())
+        );
+    }
+}
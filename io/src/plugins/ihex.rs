@@ -1,28 +1,29 @@
 //! RIO plugin that opens intel hex files.
 
-use super::defaultplugin;
-use super::dummy::Dummy;
+use super::defaultplugin::{self, backing_path};
+use super::sparse::{self, SparseOptions};
 use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations};
 use crate::utils::{IoError, IoMode};
 use alloc::collections::BTreeMap;
 use core::num::ParseIntError;
-use core::{fmt::Write as _, str};
+use core::ops::Range;
+use core::str;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while_m_n},
     {combinator::map_res, sequence::tuple, IResult},
 };
 use std::{
-    fs::{File, OpenOptions},
     io,
     io::Write as _,
-    path::Path,
+    path::{Path, PathBuf},
 };
 const METADATA: RIOPluginMetadata = RIOPluginMetadata {
     name: "IHex",
     desc: "This IO plugin is used to open Intel IHex files,\
            this plugin would fill sparce intel ihex files with\
-           zeros when doing read operation but in case of writes,\
+           zeros (or the byte set by ?fill=) when doing read operation,\
+           or report them as holes with ?holes, but in case of writes,\
            unfilled bytes will remain unfilled",
     author: "Oddcoder",
     license: "LGPL",
@@ -30,10 +31,11 @@ const METADATA: RIOPluginMetadata = RIOPluginMetadata {
 };
 
 struct FileInternals {
-    file: Box<dyn RIOPluginOperations + Sync + Send>, // defaultplugin
-    uri: String,
+    file: Box<dyn RIOPluginOperations + Sync + Send>, // backing ihex text
+    path: PathBuf,
     bytes: BTreeMap<u64, u8>, // sparce array of bytes
     prot: IoMode,
+    options: SparseOptions,
     ssa: Option<u32>, // used for Record 03
     sla: Option<u32>, // used for Record 05
 }
@@ -140,6 +142,117 @@ fn parse_record05(input: &[u8]) -> IResult<&[u8], Record> {
     Ok((input, Record::Sla(addr)))
 }
 
+/// Extended address records used when encoding Intel HEX files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum IHexAddrMode {
+    /// Use the smallest of the following modes that covers all addresses.
+    #[default]
+    Auto,
+    /// 16 bit addresses only (I8HEX).
+    I8,
+    /// Extended segment address records, up to 1 MiB (I16HEX).
+    I16,
+    /// Extended linear address records, up to 4 GiB (I32HEX).
+    I32,
+}
+
+impl IHexAddrMode {
+    fn resolve(self, max: u64) -> Result<Self, IoError> {
+        let mode = match self {
+            Self::Auto if max > 0xf_ffff => Self::I32,
+            Self::Auto if max > 0xffff => Self::I16,
+            Self::Auto => Self::I8,
+            mode @ (Self::I8 | Self::I16 | Self::I32) => mode,
+        };
+        let limit: u64 = match mode {
+            Self::I8 => 0xffff,
+            Self::I16 => 0xf_ffff,
+            Self::I32 | Self::Auto => 0xffff_ffff,
+        };
+        if max > limit {
+            return Err(IoError::Custom(format!(
+                "Address 0x{max:x} doesn't fit in {mode:?} Intel HEX"
+            )));
+        }
+        Ok(mode)
+    }
+}
+
+fn write_record(file: &mut Vec<u8>, kind: u8, addr: u16, data: &[u8]) -> Result<(), IoError> {
+    let [hi, lo] = addr.to_be_bytes();
+    let mut checksum = (data.len() as u8).wrapping_add(hi).wrapping_add(lo);
+    checksum = checksum.wrapping_add(kind);
+    write!(file, ":{:02x}{addr:04x}{kind:02x}", data.len())?;
+    for byte in data {
+        checksum = checksum.wrapping_add(*byte);
+        write!(file, "{byte:02x}")?;
+    }
+    writeln!(file, "{:02x}", checksum.wrapping_neg())?;
+    Ok(())
+}
+
+/// Split `bytes` into runs of contiguous bytes of at most `record_len` bytes that never cross
+/// a 64 KiB boundary.
+fn records(bytes: &BTreeMap<u64, u8>, record_len: usize) -> Vec<(u64, Vec<u8>)> {
+    let mut records: Vec<(u64, Vec<u8>)> = Vec::new();
+    for (addr, byte) in bytes {
+        match records.last_mut() {
+            Some((start, data))
+                if *start + data.len() as u64 == *addr
+                    && data.len() < record_len
+                    && addr & 0xffff != 0 =>
+            {
+                data.push(*byte);
+            }
+            _ => records.push((*addr, vec![*byte])),
+        }
+    }
+    records
+}
+
+fn write_data(
+    file: &mut Vec<u8>,
+    bytes: &BTreeMap<u64, u8>,
+    record_len: u8,
+    mode: IHexAddrMode,
+) -> Result<(), IoError> {
+    let max = bytes.keys().next_back().copied().unwrap_or(0);
+    let mode = mode.resolve(max)?;
+    let mut upper = 0u64;
+    for (addr, data) in records(bytes, record_len as usize) {
+        if addr >> 16u64 != upper {
+            upper = addr >> 16u64;
+            match mode {
+                // record 02
+                IHexAddrMode::I16 => {
+                    write_record(file, 2, 0, &((upper << 12) as u16).to_be_bytes())?;
+                }
+                // record 04
+                IHexAddrMode::I8 | IHexAddrMode::I32 | IHexAddrMode::Auto => {
+                    write_record(file, 4, 0, &(upper as u16).to_be_bytes())?;
+                }
+            }
+        }
+        write_record(file, 0, addr as u16, &data)?;
+    }
+    Ok(())
+}
+
+/// Encode sparse `bytes` as Intel HEX file with data records of at most `record_len` bytes.
+pub fn encode_ihex(
+    bytes: &BTreeMap<u64, u8>,
+    record_len: u8,
+    mode: IHexAddrMode,
+) -> Result<Vec<u8>, IoError> {
+    if record_len == 0 {
+        return Err(IoError::Custom("Record length cannot be zero".to_owned()));
+    }
+    let mut file = Vec::new();
+    write_data(&mut file, bytes, record_len, mode)?;
+    writeln!(file, ":00000001FF")?;
+    Ok(file)
+}
+
 impl FileInternals {
     fn parse_record(input: &[u8]) -> IResult<&[u8], Record> {
         alt((
@@ -176,102 +289,17 @@ impl FileInternals {
         }
         Ok(())
     }
-    fn write_sa(&self, file: &mut File) -> Result<(), IoError> {
+    fn save_ihex(&self) -> Result<Vec<u8>, IoError> {
+        let mut file = Vec::new();
         if let Some(ssa) = self.ssa {
-            let mut checksum: u16 = 4 + 3;
-            for byte in &ssa.to_be_bytes() {
-                checksum = (checksum + *byte as u16) & 0xFF;
-            }
-            checksum = 256 - checksum;
-            writeln!(file, ":04000003{ssa:08x}{checksum:02x}")?;
+            write_record(&mut file, 3, 0, &ssa.to_be_bytes())?;
         }
         if let Some(sla) = self.sla {
-            let mut checksum: u16 = 4 + 5;
-            for byte in &sla.to_be_bytes() {
-                checksum = (checksum + *byte as u16) & 0xFF;
-            }
-            checksum = 256 - checksum;
-            writeln!(file, ":04000005{sla:08x}{checksum:02x}")?;
-        }
-        Ok(())
-    }
-    fn write_record04(file: &mut File, addr: u64) -> Result<(), IoError> {
-        let addr = (addr >> 16i32) as u16;
-        let mut checksum = 6;
-        for byte in &addr.to_be_bytes() {
-            checksum = (checksum + *byte as u16) & 0xFF;
-        }
-        checksum = 256 - checksum;
-        writeln!(file, ":02000004{addr:04x}{checksum:02x}")?;
-        Ok(())
-    }
-
-    fn write_record02(file: &mut File, addr: u64) -> Result<(), IoError> {
-        let addr = (addr >> 4i32) as u16 & 0xf000;
-        let mut checksum = 4;
-        for byte in &addr.to_be_bytes() {
-            checksum = (checksum + *byte as u16) & 0xFF;
+            write_record(&mut file, 5, 0, &sla.to_be_bytes())?;
         }
-        checksum = 256 - checksum;
-        writeln!(file, ":02000002{addr:04x}{checksum:02x}")?;
-        Ok(())
-    }
-
-    fn write_data(&self, file: &mut File) -> Result<(), IoError> {
-        let mut checksum: u16 = 0x10;
-        let mut addr = self.base();
-        let mut data = String::new();
-        let mut i = 0i32;
-        for (k, v) in &self.bytes {
-            if i != 0i32 {
-                if i == 0x10i32 || *k != addr + 1 {
-                    writeln!(file, ":{:02x}{}{:02x}", i, data, (256 - checksum) & 0xff)?;
-                    data.clear();
-                    checksum = 0x10;
-                    i = 0i32;
-                } else {
-                    // we know that *k == addr + 1
-                    addr = *k;
-                    write!(data, "{:02x}", *v).unwrap();
-                    checksum = (checksum + *v as u16) & 0xff;
-                }
-            }
-            if i == 0i32 {
-                if *k > 0xfffff {
-                    // record 04
-                    Self::write_record04(file, *k)?;
-                } else if *k > 0xffff {
-                    // record 02
-                    Self::write_record02(file, *k)?;
-                }
-                let offset = (*k & 0xffff) as u16;
-                for byte in &offset.to_be_bytes() {
-                    checksum = (checksum + *byte as u16) & 0xff;
-                }
-                addr = *k;
-                write!(data, "{:04x}00{:02x}", offset, *v).unwrap();
-                checksum = (checksum + *v as u16) & 0xff;
-            }
-            i += 1i32;
-        }
-        if !data.is_empty() {
-            writeln!(file, ":{:02x}{}{:02x}", i, data, 256 - checksum)?;
-        }
-        Ok(())
-    }
-    fn save_ihex(&self) -> Result<(), IoError> {
-        // truncate the current file.
-        let mut file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(IHexPlugin::uri_to_path(&self.uri))?;
-        //write ssa and sla
-        self.write_sa(&mut file)?;
-        //write data
-        self.write_data(&mut file)?;
-        // write EOF
+        write_data(&mut file, &self.bytes, 0x10, IHexAddrMode::Auto)?;
         writeln!(file, ":00000001FF")?;
-        Ok(())
+        Ok(file)
     }
     fn size(&self) -> u64 {
         let Some((min, _)) = self.bytes.iter().next() else {
@@ -293,17 +321,22 @@ impl FileInternals {
 
 impl RIOPluginOperations for FileInternals {
     fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
-        for (i, item) in buffer.iter_mut().enumerate() {
-            let addr = (i + raddr) as u64;
-            if let Some(v) = self.bytes.get(&addr) {
-                *item = *v;
-            } else {
-                *item = 0;
-            }
-        }
+        self.options.read(&self.bytes, raddr, buffer);
         Ok(())
     }
 
+    fn read_sparce(
+        &mut self,
+        raddr: usize,
+        buffer: &mut [u8],
+    ) -> Result<Vec<Range<usize>>, IoError> {
+        Ok(self.options.read_sparce(&self.bytes, raddr, buffer))
+    }
+
+    fn holes(&self) -> Vec<Range<u64>> {
+        sparse::holes(&self.bytes)
+    }
+
     fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
         // if we are dealing with cow or write firs write data to the sparce array
         if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
@@ -317,20 +350,61 @@ impl RIOPluginOperations for FileInternals {
         }
 
         if self.prot.contains(IoMode::WRITE) {
-            // drop old file descriptor
-            self.file = Box::new(Dummy {});
-            // write data to new file with old file name
-            self.save_ihex()?;
-            // mmap new file
-            let mut plug = defaultplugin::plugin();
-            let def_desc = plug.open(
-                &IHexPlugin::uri_to_path(&self.uri).to_string_lossy(),
-                IoMode::READ,
-            )?;
-            self.file = def_desc.plugin_operations;
+            let path = self.path.clone();
+            self.commit(&path)?;
         }
         Ok(())
     }
+
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::resize(&mut self.bytes, end, self.options.fill);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, raddr: usize, data: &[u8]) -> Result<bool, IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::insert(&mut self.bytes, raddr as u64, data);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(true)
+    }
+
+    fn remove(&mut self, raddr: usize, size: usize) -> Result<bool, IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::remove(&mut self.bytes, raddr as u64, size as u64);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(true)
+    }
+
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        let data = self.save_ihex()?;
+        self.file.commit_data(path, &data)
+    }
 }
 
 struct IHexPlugin {
@@ -339,7 +413,7 @@ struct IHexPlugin {
 
 impl IHexPlugin {
     fn uri_to_path(uri: &str) -> &Path {
-        let path = uri.trim_start_matches("ihex://");
+        let path = SparseOptions::split(uri).0.trim_start_matches("ihex://");
         Path::new(path)
     }
     fn new() -> IHexPlugin {
@@ -362,13 +436,34 @@ impl RIOPlugin for IHexPlugin {
             &IHexPlugin::uri_to_path(uri).to_string_lossy(),
             IoMode::READ,
         )?;
+        self.open_backed(uri, flags, def_desc)
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.starts_with("ihex://")
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, _flags: IoMode) -> Option<(&'a str, IoMode)> {
+        let (uri, _) = SparseOptions::split(uri);
+        Some((uri.strip_prefix("ihex://")?, IoMode::READ))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        def_desc: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        let path = backing_path(&def_desc.name, flags)?;
+        let (_, options) = SparseOptions::split(uri);
         let mut internal = FileInternals {
             file: def_desc.plugin_operations,
             bytes: BTreeMap::new(),
             ssa: None,
             sla: None,
             prot: flags,
-            uri: uri.to_owned(),
+            path,
+            options,
         };
         let mut data = vec![0; def_desc.size as usize];
         internal.file.read(0x0, &mut data)?;
@@ -382,11 +477,6 @@ impl RIOPlugin for IHexPlugin {
         };
         Ok(desc)
     }
-
-    fn accept_uri(&self, uri: &str) -> bool {
-        let split: Vec<&str> = uri.split("://").collect();
-        split.len() == 2 && split[0] == "ihex"
-    }
 }
 
 pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
@@ -396,8 +486,59 @@ pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
 #[cfg(test)]
 mod test_ihex {
     use super::*;
+    use std::fs;
     use test_file::*;
 
+    fn insert_remove_cb(paths: &[&Path]) {
+        let mut p = plugin();
+        let uri = format!("ihex://{}", paths[0].to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        file.plugin_operations.insert(0x12, &[0xaa]).unwrap_err();
+        file.plugin_operations.remove(0x12, 1).unwrap_err();
+        file = p.open(&uri, IoMode::COW).unwrap();
+        assert!(file.plugin_operations.insert(0x12, &[0xaa]).unwrap());
+        assert!(file.plugin_operations.remove(0x11, 2).unwrap());
+        // holes move along with data
+        let hole = 0x13..0x17;
+        assert_eq!(file.plugin_operations.holes(), [hole]);
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            ":03001000010304e5\n:020017000506dc\n:00000001FF\n"
+        );
+    }
+    #[test]
+    fn test_insert_remove() {
+        operate_on_files(
+            &insert_remove_cb,
+            &[b":0400100001020304E2\n:020018000506DB\n:00000001FF\n", b""],
+        );
+    }
+
+    fn resize_cb(paths: &[&Path]) {
+        let mut p = plugin();
+        let uri = format!("ihex://{}", paths[0].to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        assert!(file.plugin_operations.resize(0x12).is_err());
+        file = p.open(&format!("{uri}?fill=0xff"), IoMode::COW).unwrap();
+        file.plugin_operations.resize(0x16).unwrap();
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            ":0600100001020304ffffe2\n:00000001FF\n"
+        );
+        file.plugin_operations.resize(0x12).unwrap();
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            ":020010000102eb\n:00000001FF\n"
+        );
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_files(&resize_cb, &[b":0400100001020304E2\n:00000001FF\n", b""]);
+    }
+
     #[test]
     fn test_accept_uri() {
         let p = plugin();
@@ -762,4 +903,84 @@ mod test_ihex {
             .unwrap();
         assert_eq!(f.size, 0);
     }
+
+    fn sparse_options_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = format!("ihex://{}", path.to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        let mut buffer = vec![0xaa; 6];
+        let ranges = file.plugin_operations.read_sparce(0, &mut buffer).unwrap();
+        let filled = 0..6;
+        assert_eq!(ranges, [filled]);
+        assert_eq!(buffer, [1, 2, 0, 0, 3, 4]);
+        let hole = 2..4;
+        assert_eq!(file.plugin_operations.holes(), [hole]);
+        let mut file = p
+            .open(&format!("{uri}?fill=0xff&holes"), IoMode::READ)
+            .unwrap();
+        assert_eq!(file.name, format!("{uri}?fill=0xff&holes"));
+        file.plugin_operations.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 0xff, 0xff, 3, 4]);
+        let ranges = file.plugin_operations.read_sparce(0, &mut buffer).unwrap();
+        assert_eq!(ranges, [0..2, 4..6]);
+    }
+    #[test]
+    fn test_sparse_options() {
+        operate_on_file(
+            &sparse_options_cb,
+            b":020000000102FB\n:020004000304F3\n:00000001FF\n",
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes: BTreeMap<u64, u8> = [(0, 1), (1, 2), (4, 3), (5, 4)].into_iter().collect();
+        let out = encode_ihex(&bytes, 0x10, IHexAddrMode::Auto).unwrap();
+        assert_eq!(
+            str::from_utf8(&out).unwrap(),
+            ":020000000102fb\n:020004000304f3\n:00000001FF\n"
+        );
+        let out = encode_ihex(&bytes, 1, IHexAddrMode::I8).unwrap();
+        assert_eq!(out.iter().filter(|c| **c == b'\n').count(), 5);
+        bytes.insert(0x1_0000, 0xaa);
+        let out = encode_ihex(&bytes, 0x10, IHexAddrMode::I16).unwrap();
+        assert!(str::from_utf8(&out)
+            .unwrap()
+            .ends_with(":020000021000ec\n:01000000aa55\n:00000001FF\n"));
+        let out = encode_ihex(&bytes, 0x10, IHexAddrMode::Auto).unwrap();
+        assert!(str::from_utf8(&out)
+            .unwrap()
+            .ends_with(":020000021000ec\n:01000000aa55\n:00000001FF\n"));
+        let out = encode_ihex(&bytes, 0x10, IHexAddrMode::I32).unwrap();
+        assert!(str::from_utf8(&out)
+            .unwrap()
+            .ends_with(":020000040001f9\n:01000000aa55\n:00000001FF\n"));
+        assert_eq!(
+            encode_ihex(&bytes, 0x10, IHexAddrMode::I8).err().unwrap(),
+            IoError::Custom("Address 0x10000 doesn't fit in I8 Intel HEX".to_owned())
+        );
+        assert_eq!(
+            encode_ihex(&bytes, 0, IHexAddrMode::I8).err().unwrap(),
+            IoError::Custom("Record length cannot be zero".to_owned())
+        );
+    }
+
+    fn encode_round_trip_cb(path: &Path) {
+        // crosses a 64 KiB boundary and needs extended linear address records.
+        let bytes: BTreeMap<u64, u8> = (0x12_fff0..0x13_0020).map(|i| (i, i as u8)).collect();
+        let out = encode_ihex(&bytes, 0x20, IHexAddrMode::Auto).unwrap();
+        fs::write(path, out).unwrap();
+        let mut p = plugin();
+        let uri = format!("ihex://{}", path.to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        assert_eq!(file.raddr, 0x12_fff0);
+        assert_eq!(file.size, 0x30);
+        let mut data = vec![0; 0x30];
+        file.plugin_operations.read(0x12_fff0, &mut data).unwrap();
+        assert_eq!(data, bytes.values().copied().collect::<Vec<_>>());
+    }
+    #[test]
+    fn test_encode_round_trip() {
+        operate_on_file(&encode_round_trip_cb, b"");
+    }
 }
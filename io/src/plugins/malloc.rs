@@ -49,6 +49,15 @@ impl RIOPluginOperations for MallocInternal {
         self.data[raddr..raddr + buffer.len()].copy_from_slice(buffer);
         Ok(())
     }
+
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        self.data.resize(end as usize, 0);
+        Ok(())
+    }
+
+    fn is_volatile(&self) -> bool {
+        true
+    }
 }
 
 struct MallocPlugin;
@@ -148,6 +157,23 @@ mod test_malloc {
             .unwrap();
     }
 
+    #[test]
+    fn test_resize() {
+        let mut p = plugin();
+        let mut file = p
+            .open("malloc://0x10", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        let operations = &mut file.plugin_operations;
+        operations.write(0x0, &[0xab; 0x10]).unwrap();
+        operations.resize(0x8).unwrap();
+        let mut buffer = [0; 0x10];
+        assert!(operations.read(0x0, &mut buffer).is_err());
+        operations.resize(0x10).unwrap();
+        operations.read(0x0, &mut buffer).unwrap();
+        assert_eq!(buffer[..0x8], [0xab; 0x8]);
+        assert_eq!(buffer[0x8..], [0; 0x8]);
+    }
+
     #[test]
     fn test_malloc_errors() {
         let mut p = plugin();
@@ -1,11 +1,16 @@
 //! List of built-in RIO plugins.
 
 use crate::io::RIO;
+pub mod archive;
 pub mod base64;
+pub mod compress;
 pub mod defaultplugin;
-pub mod dummy;
+pub mod fat;
 pub mod ihex;
 pub mod malloc;
+#[cfg(target_os = "linux")]
+pub mod proc;
+mod sparse;
 pub mod srec;
 pub(crate) fn load_plugins(io: &mut RIO) {
     io.load_plugin(defaultplugin::plugin());
@@ -13,4 +18,9 @@ pub(crate) fn load_plugins(io: &mut RIO) {
     io.load_plugin(malloc::plugin());
     io.load_plugin(base64::plugin());
     io.load_plugin(srec::plugin());
+    io.load_plugin(fat::plugin());
+    io.load_plugin(compress::plugin());
+    io.load_plugin(archive::plugin());
+    #[cfg(target_os = "linux")]
+    io.load_plugin(proc::plugin());
 }
@@ -0,0 +1,261 @@
+//! RIO plugin that opens memory of running processes.
+
+use crate::plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations, RIORegion};
+use crate::utils::{IoError, IoMode};
+use core::cmp::min;
+use core::ops::Range;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+
+const METADATA: RIOPluginMetadata = RIOPluginMetadata {
+    name: "Proc",
+    desc: "This plugin is used to open memory of running processes as in proc://1234, \
+           every region listed in /proc/<pid>/maps is mapped at its real virtual address \
+           and pages that cannot be read are reported as holes.",
+    author: "Oddcoder",
+    license: "LGPL",
+    version: "0.0.1",
+};
+
+const PAGE_SIZE: usize = 0x1000;
+
+/// Parse content of `/proc/<pid>/maps`, regions are laid next to each other in
+/// the opened file in the same order they appear in the maps file.
+fn parse_maps(maps: &str) -> Option<Vec<RIORegion>> {
+    let mut regions = Vec::new();
+    let mut raddr = 0;
+    for line in maps.lines() {
+        let mut fields = line.split_whitespace();
+        let (start, end) = fields.next()?.split_once('-')?;
+        let vaddr = u64::from_str_radix(start, 16).ok()?;
+        let size = u64::from_str_radix(end, 16).ok()?.checked_sub(vaddr)?;
+        let name = fields.nth(4).unwrap_or_default().to_owned();
+        if size == 0 {
+            continue;
+        }
+        regions.push(RIORegion {
+            raddr,
+            vaddr,
+            size,
+            name,
+        });
+        raddr += size;
+    }
+    Some(regions)
+}
+
+struct ProcInternal {
+    file: File,
+    regions: Vec<RIORegion>,
+}
+
+impl ProcInternal {
+    /// Split `size` bytes starting at `raddr` into chunks that each lie within a single
+    /// region, every chunk is returned as (offset in buffer, virtual address, size).
+    fn split(&self, raddr: usize, size: usize) -> Result<Vec<(usize, u64, usize)>, IoError> {
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        while offset < size {
+            let addr = (raddr + offset) as u64;
+            let Some(region) = self
+                .regions
+                .iter()
+                .find(|r| r.raddr <= addr && addr < r.raddr + r.size)
+            else {
+                return Err(IoError::AddressNotFound);
+            };
+            let delta = min(size - offset, (region.raddr + region.size - addr) as usize);
+            chunks.push((offset, addr - region.raddr + region.vaddr, delta));
+            offset += delta;
+        }
+        Ok(chunks)
+    }
+}
+
+impl RIOPluginOperations for ProcInternal {
+    fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
+        for (offset, vaddr, size) in self.split(raddr, buffer.len())? {
+            self.file
+                .read_exact_at(&mut buffer[offset..offset + size], vaddr)
+                .map_err(IoError::Parse)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
+        for (offset, vaddr, size) in self.split(raddr, buffer.len())? {
+            self.file
+                .write_all_at(&buffer[offset..offset + size], vaddr)
+                .map_err(IoError::Parse)?;
+        }
+        Ok(())
+    }
+
+    fn read_sparce(
+        &mut self,
+        raddr: usize,
+        buffer: &mut [u8],
+    ) -> Result<Vec<Range<usize>>, IoError> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (offset, vaddr, size) in self.split(raddr, buffer.len())? {
+            let chunk = &mut buffer[offset..offset + size];
+            if self.file.read_exact_at(chunk, vaddr).is_ok() {
+                ranges.push(offset..offset + size);
+                continue;
+            }
+            // fall back to reading page by page skipping those that are not readable.
+            let mut start = 0;
+            while start < size {
+                let page_end = (vaddr as usize + start) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE;
+                let end = min(size, page_end - vaddr as usize);
+                let page = &mut chunk[start..end];
+                if self.file.read_exact_at(page, vaddr + start as u64).is_ok() {
+                    match ranges.last_mut() {
+                        Some(last) if last.end == offset + start => last.end = offset + end,
+                        _ => ranges.push(offset + start..offset + end),
+                    }
+                }
+                start = end;
+            }
+        }
+        Ok(ranges)
+    }
+
+    fn regions(&self) -> Vec<RIORegion> {
+        self.regions.clone()
+    }
+}
+
+struct ProcPlugin;
+
+impl RIOPlugin for ProcPlugin {
+    fn get_metadata(&self) -> &'static RIOPluginMetadata {
+        &METADATA
+    }
+
+    fn open(&mut self, uri: &str, flags: IoMode) -> Result<RIOPluginDesc, IoError> {
+        if flags.contains(IoMode::COW) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Can't open process memory with permission Copy-On-Write",
+            )));
+        }
+        let Ok(pid) = uri.trim_start_matches("proc://").parse::<u32>() else {
+            return Err(IoError::Custom("Expected proc://[pid]".to_owned()));
+        };
+        let maps = fs::read_to_string(format!("/proc/{pid}/maps")).map_err(IoError::Parse)?;
+        let Some(regions) = parse_maps(&maps) else {
+            return Err(IoError::Custom(format!(
+                "Failed to parse memory maps of process {pid}"
+            )));
+        };
+        let file = OpenOptions::new()
+            .read(true)
+            .write(flags.contains(IoMode::WRITE))
+            .open(format!("/proc/{pid}/mem"))
+            .map_err(IoError::Parse)?;
+        let size = regions.iter().map(|r| r.size).sum();
+        Ok(RIOPluginDesc {
+            name: uri.to_owned(),
+            perm: flags,
+            raddr: 0,
+            size,
+            plugin_operations: Box::new(ProcInternal { file, regions }),
+        })
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        let split: Vec<&str> = uri.split("://").collect();
+        split.len() == 2 && split[0] == "proc"
+    }
+}
+
+pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
+    Box::new(ProcPlugin)
+}
+
+#[cfg(test)]
+mod test_proc {
+    use super::*;
+    use crate::RIO;
+    use std::process::{Child, Command};
+
+    fn spawn() -> Child {
+        Command::new("sleep").arg("60").spawn().unwrap()
+    }
+
+    #[test]
+    fn test_parse_maps() {
+        let maps = "00400000-00401000 r-xp 00000000 08:01 1234       /bin/sleep\n\
+                    7ffd0000-7ffd2000 rw-p 00000000 00:00 0          [stack]\n\
+                    7ffd3000-7ffd4000 rw-p 00000000 00:00 0\n";
+        let regions = parse_maps(maps).unwrap();
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[0].name, "/bin/sleep");
+        assert_eq!((regions[1].raddr, regions[1].vaddr), (0x1000, 0x7ffd_0000));
+        assert_eq!(regions[1].size, 0x2000);
+        assert_eq!(regions[1].name, "[stack]");
+        assert_eq!((regions[2].raddr, regions[2].name.as_str()), (0x3000, ""));
+        assert_eq!(parse_maps("garbage"), None);
+    }
+
+    #[test]
+    fn test_proc() {
+        let mut child = spawn();
+        let mut io = RIO::new();
+        let uri = format!("proc://{}", child.id());
+        io.open(&uri, IoMode::READ).unwrap();
+        let maps = fs::read_to_string(format!("/proc/{}/maps", child.id())).unwrap();
+        let regions = parse_maps(&maps).unwrap();
+        let stack = regions.iter().find(|r| r.name == "[stack]").unwrap();
+        assert!(io.vir_to_phy(stack.vaddr, stack.size).is_some());
+        let mut data = vec![0; 0x10];
+        io.vread(stack.vaddr + stack.size - 0x10, &mut data)
+            .unwrap();
+        let sparce = io.vread_sparce(stack.vaddr, stack.size).unwrap();
+        assert_eq!(sparce.len() as u64, stack.size);
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_read_sparce() {
+        let mut child = spawn();
+        let maps = fs::read_to_string(format!("/proc/{}/maps", child.id())).unwrap();
+        let mut regions = parse_maps(&maps).unwrap();
+        let stack = regions
+            .iter()
+            .find(|r| r.name == "[stack]")
+            .unwrap()
+            .clone();
+        // region at an address that is not mapped in the child.
+        regions.push(RIORegion {
+            raddr: stack.raddr + stack.size,
+            vaddr: 0x1000,
+            size: 0x1000,
+            name: String::new(),
+        });
+        let file = File::open(format!("/proc/{}/mem", child.id())).unwrap();
+        let mut proc = ProcInternal { file, regions };
+        let mut data = vec![0; stack.size as usize + 0x1000];
+        let raddr = stack.raddr as usize;
+        assert!(proc.read(raddr, &mut data).is_err());
+        let ranges = proc.read_sparce(raddr, &mut data).unwrap();
+        assert_eq!(ranges, vec![0..stack.size as usize]);
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut p = plugin();
+        assert!(p.accept_uri("proc://1"));
+        assert!(!p.accept_uri("malloc://1"));
+        assert_eq!(
+            p.open("proc://self", IoMode::READ).err().unwrap(),
+            IoError::Custom("Expected proc://[pid]".to_owned())
+        );
+        assert!(p.open("proc://1", IoMode::READ | IoMode::COW).is_err());
+    }
+}
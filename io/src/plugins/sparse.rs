@@ -0,0 +1,213 @@
+//! Options and helpers shared by plugins of sparse formats such as ihex and srec.
+//!
+//! Options follow the URI after the last `?` separated by `&`:
+//! - `fill=BYTE` byte returned when reading holes, defaults to 0.
+//! - `holes` leave holes out of sparse reads instead of filling them.
+//!
+//! As in `ihex://firmware.hex?fill=0xff&holes`.
+
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct SparseOptions {
+    pub(crate) fill: u8,
+    pub(crate) holes: bool,
+}
+
+fn parse_byte(value: &str) -> Option<u8> {
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u8::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+impl SparseOptions {
+    fn parse(options: &str) -> Option<Self> {
+        let mut result = Self::default();
+        for option in options.split('&') {
+            match option.split_once('=') {
+                Some(("fill", value)) => result.fill = parse_byte(value)?,
+                None if option == "holes" => result.holes = true,
+                _ => return None,
+            }
+        }
+        Some(result)
+    }
+    /// Split `uri` into the URI without options and the options, anything after the last `?`
+    /// that isn't made of valid options is left in the URI as part of the path.
+    pub(crate) fn split(uri: &str) -> (&str, Self) {
+        uri.rsplit_once('?')
+            .and_then(|(uri, options)| Some((uri, Self::parse(options)?)))
+            .unwrap_or((uri, Self::default()))
+    }
+    /// Read `buffer` starting at `raddr` out of `bytes`, holes are filled with the fill byte.
+    pub(crate) fn read(self, bytes: &BTreeMap<u64, u8>, raddr: usize, buffer: &mut [u8]) {
+        for (i, item) in buffer.iter_mut().enumerate() {
+            let addr = (i + raddr) as u64;
+            *item = bytes.get(&addr).copied().unwrap_or(self.fill);
+        }
+    }
+    /// Same as [`SparseOptions::read`] but returns ranges of `buffer` that are not holes if
+    /// holes are reported.
+    pub(crate) fn read_sparce(
+        self,
+        bytes: &BTreeMap<u64, u8>,
+        raddr: usize,
+        buffer: &mut [u8],
+    ) -> Vec<Range<usize>> {
+        self.read(bytes, raddr, buffer);
+        if !self.holes {
+            let filled = 0..buffer.len();
+            return vec![filled];
+        }
+        let start = raddr as u64;
+        let end = start + buffer.len() as u64;
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for addr in bytes
+            .range(start..end)
+            .map(|(addr, _)| (addr - start) as usize)
+        {
+            match ranges.last_mut() {
+                Some(range) if range.end == addr => range.end += 1,
+                _ => ranges.push(addr..addr + 1),
+            }
+        }
+        ranges
+    }
+}
+
+/// Ranges of real addresses between the first and last populated bytes that hold no data.
+pub(crate) fn holes(bytes: &BTreeMap<u64, u8>) -> Vec<Range<u64>> {
+    let mut holes = Vec::new();
+    let mut addrs = bytes.keys();
+    let Some(mut last) = addrs.next().copied() else {
+        return holes;
+    };
+    for addr in addrs {
+        if *addr != last + 1 {
+            holes.push(last + 1..*addr);
+        }
+        last = *addr;
+    }
+    holes
+}
+
+/// Truncate or grow populated bytes so that they end right before `end`, grown bytes are set
+/// to `fill`.
+pub(crate) fn resize(bytes: &mut BTreeMap<u64, u8>, end: u64, fill: u8) {
+    bytes.split_off(&end);
+    let last = bytes.keys().next_back().map_or(0, |addr| addr + 1);
+    bytes.extend((last..end).map(|addr| (addr, fill)));
+}
+
+/// Insert `data` at `addr` moving populated bytes after it forward.
+pub(crate) fn insert(bytes: &mut BTreeMap<u64, u8>, addr: u64, data: &[u8]) {
+    let tail = bytes.split_off(&addr);
+    let len = data.len() as u64;
+    bytes.extend((addr..).zip(data.iter().copied()));
+    bytes.extend(tail.into_iter().map(|(addr, byte)| (addr + len, byte)));
+}
+
+/// Remove `size` bytes at `addr` moving populated bytes after them backward.
+pub(crate) fn remove(bytes: &mut BTreeMap<u64, u8>, addr: u64, size: u64) {
+    let mut tail = bytes.split_off(&addr);
+    let tail = tail.split_off(&(addr + size));
+    bytes.extend(tail.into_iter().map(|(addr, byte)| (addr - size, byte)));
+}
+
+#[cfg(test)]
+mod test_sparse {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let fill = SparseOptions {
+            fill: 0xff,
+            holes: false,
+        };
+        let both = SparseOptions {
+            fill: 10,
+            holes: true,
+        };
+        assert_eq!(
+            SparseOptions::split("ihex://a.hex"),
+            ("ihex://a.hex", SparseOptions::default())
+        );
+        assert_eq!(
+            SparseOptions::split("ihex://a.hex?fill=0xff"),
+            ("ihex://a.hex", fill)
+        );
+        assert_eq!(
+            SparseOptions::split("ihex://a?b.hex?holes&fill=10"),
+            ("ihex://a?b.hex", both)
+        );
+        assert_eq!(
+            SparseOptions::split("ihex://a?b.hex"),
+            ("ihex://a?b.hex", SparseOptions::default())
+        );
+        assert_eq!(
+            SparseOptions::split("ihex://a.hex?fill=0x100"),
+            ("ihex://a.hex?fill=0x100", SparseOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        let bytes: BTreeMap<u64, u8> = [(1, 1), (2, 2), (5, 5)].into_iter().collect();
+        let mut options = SparseOptions::default();
+        let mut buffer = [0xaa; 6];
+        let filled = 0..6;
+        assert_eq!(options.read_sparce(&bytes, 0, &mut buffer), [filled]);
+        assert_eq!(buffer, [0, 1, 2, 0, 0, 5]);
+        options.fill = 0xff;
+        options.holes = true;
+        assert_eq!(options.read_sparce(&bytes, 0, &mut buffer), [1..3, 5..6]);
+        assert_eq!(buffer, [0xff, 1, 2, 0xff, 0xff, 5]);
+        let mut buffer = [0; 3];
+        let filled = 0..1;
+        assert_eq!(options.read_sparce(&bytes, 2, &mut buffer), [filled]);
+        assert_eq!(buffer, [2, 0xff, 0xff]);
+        let hole = 3..5;
+        assert_eq!(holes(&bytes), [hole]);
+        assert!(holes(&BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut bytes: BTreeMap<u64, u8> = [(1, 1), (2, 2), (5, 5)].into_iter().collect();
+        resize(&mut bytes, 4, 0xff);
+        assert_eq!(
+            bytes.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (2, 2), (3, 0xff)]
+        );
+        let mut bytes: BTreeMap<u64, u8> = [(1, 1), (2, 2), (5, 5)].into_iter().collect();
+        resize(&mut bytes, 8, 0);
+        assert_eq!(
+            bytes.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (2, 2), (5, 5), (6, 0), (7, 0)]
+        );
+        let mut bytes = BTreeMap::new();
+        resize(&mut bytes, 2, 0);
+        assert_eq!(bytes.into_iter().collect::<Vec<_>>(), [(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut bytes: BTreeMap<u64, u8> = [(1, 1), (2, 2), (5, 5)].into_iter().collect();
+        insert(&mut bytes, 2, &[7, 8]);
+        assert_eq!(
+            bytes.iter().map(|(a, b)| (*a, *b)).collect::<Vec<_>>(),
+            [(1, 1), (2, 7), (3, 8), (4, 2), (7, 5)]
+        );
+        remove(&mut bytes, 3, 3);
+        assert_eq!(
+            bytes.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (2, 7), (4, 5)]
+        );
+    }
+}
@@ -1,12 +1,13 @@
 //! RIO plugin that opens  Motorola S-records files.
 
-use super::{defaultplugin, dummy::Dummy};
+use super::defaultplugin::{self, backing_path};
+use super::sparse::{self, SparseOptions};
 use crate::{
     plugin::{RIOPlugin, RIOPluginDesc, RIOPluginMetadata, RIOPluginOperations},
     utils::{IoError, IoMode},
 };
 use alloc::collections::BTreeMap;
-use core::{fmt::Write as _, num::ParseIntError, str};
+use core::{num::ParseIntError, ops::Range, str};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while_m_n},
@@ -15,27 +16,28 @@ use nom::{
     IResult,
 };
 use std::{
-    fs::{File, OpenOptions},
     io::{self, Write as _},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 const METADATA: RIOPluginMetadata = RIOPluginMetadata {
     name: "Srec",
     desc: "This IO plugin is used to open Motorola \
            S-records(srec) files, this plugin would fill \
-           sparce Motorola srec files with zeros when doing \
-           read operation but in case of writes, unfilled \
-           bytes will remain unfilled",
+           sparce Motorola srec files with zeros (or the byte \
+           set by ?fill=) when doing read operation, or report \
+           them as holes with ?holes, but in case of writes, \
+           unfilled bytes will remain unfilled",
     author: "Oddcoder",
     license: "LGPL",
     version: "0.0.1",
 };
 struct SrecInternal {
-    file: Box<dyn RIOPluginOperations + Sync + Send>, // defaultplugin
+    file: Box<dyn RIOPluginOperations + Sync + Send>, // backing srec text
     bytes: BTreeMap<u64, u8>,                         // sparce array of bytes
-    uri: String,
+    path: PathBuf,
     prot: IoMode,
+    options: SparseOptions,
     start_address: Option<u64>, // I am not sure if this will always exist or not
     header: Vec<u8>,
 }
@@ -175,6 +177,123 @@ fn parse_record9(input: &[u8]) -> IResult<&[u8], Record> {
     Ok((input, Record::Eof(start as u64)))
 }
 
+/// Address size used when encoding Motorola S-record files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SrecAddrMode {
+    /// Use the smallest of the following modes that covers all addresses.
+    #[default]
+    Auto,
+    /// 16 bit addresses, S1 data and S9 termination records.
+    S19,
+    /// 24 bit addresses, S2 data and S8 termination records.
+    S28,
+    /// 32 bit addresses, S3 data and S7 termination records.
+    S37,
+}
+
+impl SrecAddrMode {
+    fn resolve(self, max: u64) -> Result<Self, IoError> {
+        let mode = match self {
+            Self::Auto if max > 0xff_ffff => Self::S37,
+            Self::Auto if max > 0xffff => Self::S28,
+            Self::Auto => Self::S19,
+            mode @ (Self::S19 | Self::S28 | Self::S37) => mode,
+        };
+        if max > mode.max_addr() {
+            return Err(IoError::Custom(format!(
+                "Address 0x{max:x} doesn't fit in {mode:?} S-record"
+            )));
+        }
+        Ok(mode)
+    }
+    fn max_addr(self) -> u64 {
+        match self {
+            Self::S19 => 0xffff,
+            Self::S28 => 0xff_ffff,
+            Self::S37 | Self::Auto => 0xffff_ffff,
+        }
+    }
+    fn addr_len(self) -> usize {
+        match self {
+            Self::S19 => 2,
+            Self::S28 => 3,
+            Self::S37 | Self::Auto => 4,
+        }
+    }
+}
+
+fn write_record(file: &mut Vec<u8>, kind: u8, addr: &[u8], data: &[u8]) -> Result<(), IoError> {
+    let size = addr.len() + data.len() + 1;
+    if size > 0xff {
+        return Err(IoError::Custom(format!(
+            "Cannot write S{kind} record with size 0x{size:x} > 0xff"
+        )));
+    }
+    let mut checksum = size as u8;
+    write!(file, "S{kind}{size:02x}")?;
+    for byte in addr.iter().chain(data) {
+        checksum = checksum.wrapping_add(*byte);
+        write!(file, "{byte:02x}")?;
+    }
+    writeln!(file, "{:02x}", !checksum)?;
+    Ok(())
+}
+
+fn write_srec(
+    file: &mut Vec<u8>,
+    header: &[u8],
+    bytes: &BTreeMap<u64, u8>,
+    record_len: u8,
+    mode: SrecAddrMode,
+    start: Option<u64>,
+) -> Result<(), IoError> {
+    let max = bytes.keys().next_back().copied().unwrap_or(0);
+    let mode = mode.resolve(max.max(start.unwrap_or(0)))?;
+    let addr_len = mode.addr_len();
+    if addr_len + record_len as usize + 1 > 0xff {
+        return Err(IoError::Custom(format!(
+            "Record length 0x{record_len:x} is too long for {mode:?} S-record"
+        )));
+    }
+    let kind = addr_len as u8 - 1;
+    write_record(file, 0, &[0, 0], header)?;
+    let mut records: Vec<(u64, Vec<u8>)> = Vec::new();
+    for (addr, byte) in bytes {
+        match records.last_mut() {
+            Some((start, data))
+                if *start + data.len() as u64 == *addr && data.len() < record_len as usize =>
+            {
+                data.push(*byte);
+            }
+            _ => records.push((*addr, vec![*byte])),
+        }
+    }
+    for (addr, data) in records {
+        let addr = addr.to_be_bytes();
+        write_record(file, kind, &addr[8 - addr_len..], &data)?;
+    }
+    if let Some(start) = start {
+        let start = start.to_be_bytes();
+        write_record(file, 10 - kind, &start[8 - addr_len..], &[])?;
+    }
+    Ok(())
+}
+
+/// Encode sparse `bytes` as Motorola S-record file with data records of at most `record_len`
+/// bytes, the termination record holds start address 0.
+pub fn encode_srec(
+    bytes: &BTreeMap<u64, u8>,
+    record_len: u8,
+    mode: SrecAddrMode,
+) -> Result<Vec<u8>, IoError> {
+    if record_len == 0 {
+        return Err(IoError::Custom("Record length cannot be zero".to_owned()));
+    }
+    let mut file = Vec::new();
+    write_srec(&mut file, &[], bytes, record_len, mode, Some(0))?;
+    Ok(file)
+}
+
 impl SrecInternal {
     fn parse_record(input: &[u8]) -> IResult<&[u8], Record> {
         alt((
@@ -230,128 +349,38 @@ impl SrecInternal {
             0
         }
     }
-    fn write_header(&mut self, file: &mut File) -> Result<(), IoError> {
-        if self.header.len() > 0xff {
-            return Err(IoError::Custom(
-                "Cannot write S0 Entry with size > 0xff".to_owned(),
-            ));
-        }
-        write!(file, "S0{:02x}0000", self.header.len() + 3).unwrap();
-        let mut checksum = self.header.len() as u16;
-        for byte in &self.header {
-            checksum = (checksum + *byte as u16) & 0xff;
-            write!(file, "{byte:02x}").unwrap();
-        }
-        writeln!(file, "{:02x}", !((checksum & 0xff) as u8)).unwrap();
-        Ok(())
-    }
-    fn write_data(&mut self, file: &mut File) -> Result<(), IoError> {
-        let mut checksum: u16 = 0x10;
-        let mut data = String::new();
-        let mut record = "S1";
-        let mut addr = 0;
-        let mut i = 0;
-        let mut extra_data = 0;
-        for (k, v) in &self.bytes {
-            if i != 0 {
-                if i == 0x10 || *k != addr + 1 {
-                    let size = i + extra_data;
-                    checksum = (!(checksum + size)) & 0xff;
-                    writeln!(file, "{record}{size:02x}{data}{checksum:02x}")?;
-
-                    data.clear();
-                    checksum = 0;
-                    i = 0;
-                } else {
-                    // we know that *k == addr + 1
-                    addr = *k;
-                    write!(data, "{:02x}", *v).unwrap();
-                    checksum = (checksum + *v as u16) & 0xff;
-                }
-            }
-            if i == 0 {
-                if *k > 0x00ff_ffff {
-                    // record S3
-                    record = "S3";
-                    extra_data = 5;
-                    write!(data, "{:08x}", *k).unwrap();
-                } else if *k > 0xffff {
-                    // record S2
-                    record = "S2";
-                    extra_data = 4;
-                    write!(data, "{:06x}", *k).unwrap();
-                } else {
-                    // record S1
-                    record = "S1";
-                    extra_data = 3;
-                    write!(data, "{:04x}", *k).unwrap();
-                }
-                for byte in &k.to_be_bytes() {
-                    checksum = (checksum + *byte as u16) & 0xff;
-                }
-                write!(data, "{:02x}", *v).unwrap();
-                checksum = (checksum + *v as u16) & 0xff;
-                addr = *k;
-            }
-            i += 1;
-        }
-        if !data.is_empty() {
-            let size = i + extra_data;
-            checksum = (!(checksum + size)) & 0xff;
-            writeln!(file, "{record}{size:02x}{data}{checksum:02x}")?;
-        }
-
-        Ok(())
-    }
-    fn write_eof(&mut self, file: &mut File) -> Result<(), IoError> {
-        let Some(start) = self.start_address else {
-            return Ok(());
-        };
-        let mut checksum: u16;
-        if start > 0x00ff_ffff {
-            // record S7
-            write!(file, "S705{start:08x}")?;
-            checksum = 0x5;
-        } else if start > 0xffff {
-            //record S8
-            write!(file, "S804{start:06x}")?;
-            checksum = 0x4;
-        } else {
-            // record S9
-            write!(file, "S903{start:04x}")?;
-            checksum = 0x3;
-        }
-        for byte in &start.to_be_bytes() {
-            checksum = (checksum + *byte as u16) & 0xff;
-        }
-        writeln!(file, "{checksum:02x}").unwrap();
-        Ok(())
-    }
-    fn save_srec(&mut self) -> Result<(), IoError> {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(SrecPlugin::uri_to_path(&self.uri))?;
-        self.write_header(&mut file)?;
-        self.write_data(&mut file)?;
-        self.write_eof(&mut file)?;
-        Ok(())
+    fn save_srec(&mut self) -> Result<Vec<u8>, IoError> {
+        let mut file = Vec::new();
+        write_srec(
+            &mut file,
+            &self.header,
+            &self.bytes,
+            0x10,
+            SrecAddrMode::Auto,
+            self.start_address,
+        )?;
+        Ok(file)
     }
 }
 
 impl RIOPluginOperations for SrecInternal {
     fn read(&mut self, raddr: usize, buffer: &mut [u8]) -> Result<(), IoError> {
-        for (i, item) in buffer.iter_mut().enumerate() {
-            let addr = (i + raddr) as u64;
-            if let Some(v) = self.bytes.get(&addr) {
-                *item = *v;
-            } else {
-                *item = 0;
-            }
-        }
+        self.options.read(&self.bytes, raddr, buffer);
         Ok(())
     }
 
+    fn read_sparce(
+        &mut self,
+        raddr: usize,
+        buffer: &mut [u8],
+    ) -> Result<Vec<Range<usize>>, IoError> {
+        Ok(self.options.read_sparce(&self.bytes, raddr, buffer))
+    }
+
+    fn holes(&self) -> Vec<Range<u64>> {
+        sparse::holes(&self.bytes)
+    }
+
     fn write(&mut self, raddr: usize, buffer: &[u8]) -> Result<(), IoError> {
         // if we are dealing with cow or write first write data to the sparce array
         if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
@@ -365,20 +394,61 @@ impl RIOPluginOperations for SrecInternal {
         }
 
         if self.prot.contains(IoMode::WRITE) {
-            // drop old file descriptor
-            self.file = Box::new(Dummy {});
-            // write data to new file with old file name
-            self.save_srec()?;
-            // mmap new file
-            let mut plug = defaultplugin::plugin();
-            let def_desc = plug.open(
-                &SrecPlugin::uri_to_path(&self.uri).to_string_lossy(),
-                IoMode::READ,
-            )?;
-            self.file = def_desc.plugin_operations;
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(())
+    }
+
+    fn resize(&mut self, end: u64) -> Result<(), IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::resize(&mut self.bytes, end, self.options.fill);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
         }
         Ok(())
     }
+
+    fn insert(&mut self, raddr: usize, data: &[u8]) -> Result<bool, IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::insert(&mut self.bytes, raddr as u64, data);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(true)
+    }
+
+    fn remove(&mut self, raddr: usize, size: usize) -> Result<bool, IoError> {
+        if !self.prot.contains(IoMode::COW) && !self.prot.contains(IoMode::WRITE) {
+            return Err(IoError::Parse(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File Not Writable",
+            )));
+        }
+        sparse::remove(&mut self.bytes, raddr as u64, size as u64);
+        if self.prot.contains(IoMode::WRITE) {
+            let path = self.path.clone();
+            self.commit(&path)?;
+        }
+        Ok(true)
+    }
+
+    fn commit(&mut self, path: &Path) -> Result<(), IoError> {
+        let data = self.save_srec()?;
+        self.file.commit_data(path, &data)
+    }
 }
 
 struct SrecPlugin {
@@ -387,7 +457,7 @@ struct SrecPlugin {
 
 impl SrecPlugin {
     fn uri_to_path(uri: &str) -> &Path {
-        let path = uri.trim_start_matches("srec://");
+        let path = SparseOptions::split(uri).0.trim_start_matches("srec://");
         Path::new(path)
     }
     fn new() -> Self {
@@ -410,11 +480,32 @@ impl RIOPlugin for SrecPlugin {
             &SrecPlugin::uri_to_path(uri).to_string_lossy(),
             IoMode::READ,
         )?;
+        self.open_backed(uri, flags, def_desc)
+    }
+
+    fn accept_uri(&self, uri: &str) -> bool {
+        uri.starts_with("srec://")
+    }
+
+    fn backing_uri<'a>(&self, uri: &'a str, _flags: IoMode) -> Option<(&'a str, IoMode)> {
+        let (uri, _) = SparseOptions::split(uri);
+        Some((uri.strip_prefix("srec://")?, IoMode::READ))
+    }
+
+    fn open_backed(
+        &mut self,
+        uri: &str,
+        flags: IoMode,
+        def_desc: RIOPluginDesc,
+    ) -> Result<RIOPluginDesc, IoError> {
+        let path = backing_path(&def_desc.name, flags)?;
+        let (_, options) = SparseOptions::split(uri);
         let mut internal = SrecInternal {
             file: def_desc.plugin_operations,
             bytes: BTreeMap::new(),
             prot: flags,
-            uri: uri.to_owned(),
+            path,
+            options,
             start_address: None,
             header: Vec::new(),
         };
@@ -430,11 +521,6 @@ impl RIOPlugin for SrecPlugin {
         };
         Ok(desc)
     }
-
-    fn accept_uri(&self, uri: &str) -> bool {
-        let split: Vec<&str> = uri.split("://").collect();
-        split.len() == 2 && split[0] == "srec"
-    }
 }
 
 pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
@@ -444,7 +530,61 @@ pub fn plugin() -> Box<dyn RIOPlugin + Sync + Send> {
 #[cfg(test)]
 mod test_srec {
     use super::*;
+    use std::fs;
     use test_file::*;
+
+    fn insert_remove_cb(paths: &[&Path]) {
+        let mut p = plugin();
+        let uri = format!("srec://{}", paths[0].to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        file.plugin_operations.insert(0x12, &[0xaa]).unwrap_err();
+        file.plugin_operations.remove(0x12, 1).unwrap_err();
+        file = p.open(&uri, IoMode::COW).unwrap();
+        assert!(file.plugin_operations.insert(0x12, &[0xaa]).unwrap());
+        assert!(file.plugin_operations.remove(0x11, 2).unwrap());
+        // holes move along with data
+        let hole = 0x13..0x17;
+        assert_eq!(file.plugin_operations.holes(), [hole]);
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            "S0030000fc\nS1060010010304e1\nS10500170506d8\nS9030000fc\n"
+        );
+    }
+    #[test]
+    fn test_insert_remove() {
+        operate_on_files(
+            &insert_remove_cb,
+            &[
+                b"S0030000FC\nS107001001020304DE\nS10500180506D7\nS9030000FC\n",
+                b"",
+            ],
+        );
+    }
+
+    fn resize_cb(paths: &[&Path]) {
+        let mut p = plugin();
+        let uri = format!("srec://{}", paths[0].to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        assert!(file.plugin_operations.resize(0x12).is_err());
+        file = p.open(&format!("{uri}?fill=0xff"), IoMode::COW).unwrap();
+        file.plugin_operations.resize(0x16).unwrap();
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            "S0030000fc\nS109001001020304ffffde\nS9030000fc\n"
+        );
+        file.plugin_operations.resize(0x12).unwrap();
+        file.plugin_operations.commit(paths[1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths[1]).unwrap(),
+            "S0030000fc\nS10500100102e7\nS9030000fc\n"
+        );
+    }
+    #[test]
+    fn test_resize() {
+        operate_on_files(&resize_cb, &[b"S107001001020304DE\nS9030000FC\n", b""]);
+    }
     #[test]
     fn test_record0() {
         let input = b"S021000036384B50524F47202020323043524541544544204259204541535936384B6D\n";
@@ -819,4 +959,76 @@ mod test_srec {
             IoError::Custom("Invalid S-record at line: 2".to_owned())
         );
     }
+
+    fn sparse_options_cb(path: &Path) {
+        let mut p = plugin();
+        let uri = format!("srec://{}", path.to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        let mut buffer = vec![0xaa; 6];
+        let ranges = file.plugin_operations.read_sparce(0, &mut buffer).unwrap();
+        let filled = 0..6;
+        assert_eq!(ranges, [filled]);
+        assert_eq!(buffer, [1, 2, 0, 0, 3, 4]);
+        let hole = 2..4;
+        assert_eq!(file.plugin_operations.holes(), [hole]);
+        let mut file = p
+            .open(&format!("{uri}?fill=0xff&holes"), IoMode::READ)
+            .unwrap();
+        assert_eq!(file.name, format!("{uri}?fill=0xff&holes"));
+        file.plugin_operations.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 0xff, 0xff, 3, 4]);
+        let ranges = file.plugin_operations.read_sparce(0, &mut buffer).unwrap();
+        assert_eq!(ranges, [0..2, 4..6]);
+    }
+    #[test]
+    fn test_sparse_options() {
+        operate_on_file(
+            &sparse_options_cb,
+            b"S10500000102F7\nS10500040304EF\nS9030000FC\n",
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes: BTreeMap<u64, u8> = [(0, 1), (1, 2)].into_iter().collect();
+        let out = encode_srec(&bytes, 0x10, SrecAddrMode::Auto).unwrap();
+        assert_eq!(
+            str::from_utf8(&out).unwrap(),
+            "S0030000fc\nS10500000102f7\nS9030000fc\n"
+        );
+        bytes.insert(0x1_0000, 0xaa);
+        let out = encode_srec(&bytes, 1, SrecAddrMode::S28).unwrap();
+        assert_eq!(
+            str::from_utf8(&out).unwrap(),
+            "S0030000fc\nS20500000001f9\nS20500000102f7\nS205010000aa4f\nS804000000fb\n"
+        );
+        let out = encode_srec(&bytes, 0x10, SrecAddrMode::S37).unwrap();
+        assert!(str::from_utf8(&out).unwrap().ends_with("S70500000000fa\n"));
+        assert_eq!(
+            encode_srec(&bytes, 0x10, SrecAddrMode::S19).err().unwrap(),
+            IoError::Custom("Address 0x10000 doesn't fit in S19 S-record".to_owned())
+        );
+        assert_eq!(
+            encode_srec(&bytes, 0xff, SrecAddrMode::S37).err().unwrap(),
+            IoError::Custom("Record length 0xff is too long for S37 S-record".to_owned())
+        );
+    }
+
+    fn encode_round_trip_cb(path: &Path) {
+        let bytes: BTreeMap<u64, u8> = (0x12_fff0..0x13_0020).map(|i| (i, i as u8)).collect();
+        let out = encode_srec(&bytes, 0x20, SrecAddrMode::Auto).unwrap();
+        fs::write(path, out).unwrap();
+        let mut p = plugin();
+        let uri = format!("srec://{}", path.to_string_lossy());
+        let mut file = p.open(&uri, IoMode::READ).unwrap();
+        assert_eq!(file.raddr, 0x12_fff0);
+        assert_eq!(file.size, 0x30);
+        let mut data = vec![0; 0x30];
+        file.plugin_operations.read(0x12_fff0, &mut data).unwrap();
+        assert_eq!(data, bytes.values().copied().collect::<Vec<_>>());
+    }
+    #[test]
+    fn test_encode_round_trip() {
+        operate_on_file(&encode_round_trip_cb, b"");
+    }
 }
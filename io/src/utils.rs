@@ -2,6 +2,7 @@
 
 use alloc::fmt;
 use bitflags::bitflags;
+use core::str::FromStr;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::io;
@@ -26,6 +27,57 @@ impl fmt::Display for IoMode {
     }
 }
 
+bitflags! {
+    /// Permissions of memory maps in virtual address space.
+    #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct MapPerm: u64 {
+    /// Map can be executed.
+    const EXEC = 1;
+    /// Map can be written to.
+    const WRITE = 2;
+    /// Map can be read.
+    const READ = 4;
+    }
+}
+
+impl Default for MapPerm {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl fmt::Display for MapPerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |perm, c| if self.contains(perm) { c } else { '-' };
+        write!(
+            f,
+            "{}{}{}",
+            flag(Self::READ, 'r'),
+            flag(Self::WRITE, 'w'),
+            flag(Self::EXEC, 'x')
+        )
+    }
+}
+
+impl FromStr for MapPerm {
+    type Err = IoError;
+    /// Parse permissions written as in `r-x` or `rw`.
+    fn from_str(s: &str) -> Result<Self, IoError> {
+        let mut perm = Self::empty();
+        for c in s.chars() {
+            let flag = match c {
+                'r' => Self::READ,
+                'w' => Self::WRITE,
+                'x' => Self::EXEC,
+                '-' => Self::empty(),
+                _ => return Err(IoError::Custom(format!("Invalid permissions {s}"))),
+            };
+            perm |= flag;
+        }
+        Ok(perm)
+    }
+}
+
 /// Errors resultion from operations on [RIO]
 #[derive(Debug)]
 #[non_exhaustive]
@@ -40,6 +92,8 @@ pub enum IoError {
     HndlNotFoundError,
     /// Too many files are opened.
     TooManyFilesError,
+    /// Writing to virtual address that belongs to a map without write permission.
+    MapNotWritableError(u64),
     /// Custom error message.
     Custom(String),
     /// Error that is originating from [`std::io`]
@@ -73,6 +127,11 @@ impl PartialEq for IoError {
                     return true;
                 }
             }
+            IoError::MapNotWritableError(vaddr) => {
+                if let IoError::MapNotWritableError(vaddr2) = other {
+                    return vaddr == vaddr2;
+                }
+            }
             IoError::Custom(s) => {
                 if let IoError::Custom(s2) = other {
                     return s == s2;
@@ -95,6 +154,9 @@ impl fmt::Display for IoError {
             IoError::IoPluginNotFoundError => write!(f, "Can not find Suitable IO Plugin."),
             IoError::TooManyFilesError => write!(f, "You have too many open files."),
             IoError::HndlNotFoundError => write!(f, "Handle Does not exist."),
+            IoError::MapNotWritableError(vaddr) => {
+                write!(f, "Virtual address 0x{vaddr:x} is not writable.")
+            }
             IoError::Custom(s) => write!(f, "{s}."),
             IoError::Parse(e) => e.fmt(f),
         }
@@ -106,3 +168,32 @@ impl From<io::Error> for IoError {
         IoError::Parse(err)
     }
 }
+
+/// Describes how physical addresses of a file moved after bytes got inserted into it or
+/// removed from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shift {
+    /// Physical address of the edit point.
+    pub paddr: u64,
+    /// End of the edited file before the edit.
+    pub end: u64,
+    /// Number of bytes removed at the edit point.
+    pub removed: u64,
+    /// Number of bytes inserted at the edit point.
+    pub inserted: u64,
+}
+
+impl Shift {
+    /// Returns the physical address that the byte at `paddr` moved to, bytes that got removed
+    /// are considered moved to the edit point.
+    #[must_use]
+    pub fn apply(&self, paddr: u64) -> u64 {
+        if paddr < self.paddr || paddr >= self.end {
+            paddr
+        } else if paddr < self.paddr + self.removed {
+            self.paddr
+        } else {
+            paddr - self.removed + self.inserted
+        }
+    }
+}
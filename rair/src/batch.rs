@@ -0,0 +1,107 @@
+//! Non-interactive execution of scripts and commands.
+
+use crate::cli::Batch;
+use core::mem;
+use rair_core::{rair_source, Core, Writer};
+use rair_eval::rair_eval;
+use std::io::Write;
+
+/// Run `f` and report whether it wrote anything to `core.stderr`.
+pub fn fails<F: FnOnce(&mut Core)>(core: &mut Core, f: F) -> bool {
+    let stderr = mem::replace(&mut core.stderr, Writer::new_buf());
+    f(core);
+    let err = mem::replace(&mut core.stderr, stderr).bytes().unwrap();
+    core.stderr.write_all(&err).unwrap();
+    !err.is_empty()
+}
+
+/// Run all scripts then all commands of `batch`, returns `true` if any of them
+/// wrote to `core.stderr`.
+pub fn run_batch(core: &mut Core, batch: &Batch) -> bool {
+    let mut failed = false;
+    for script in &batch.scripts {
+        failed |= fails(core, |core| rair_source(core, script));
+    }
+    for cmd in &batch.cmds {
+        failed |= fails(core, |core| rair_eval(core, cmd));
+    }
+    failed
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use rair_io::IoMode;
+    use std::path::Path;
+    use test_file::operate_on_file;
+
+    fn new_core() -> Core {
+        let mut core = Core::new_no_colors();
+        core.set_evaluator(rair_eval);
+        core.stderr = Writer::new_buf();
+        core.stdout = Writer::new_buf();
+        core.io
+            .open("malloc://0x20", IoMode::READ | IoMode::WRITE)
+            .unwrap();
+        core
+    }
+
+    #[test]
+    fn test_cmds() {
+        let mut core = new_core();
+        let batch = Batch {
+            cmds: vec!["wx 4142".to_owned(), "px 0x2".to_owned()],
+            ..Batch::default()
+        };
+        assert!(!run_batch(&mut core, &batch));
+        assert_eq!(
+            core.stdout.utf8_string().unwrap(),
+            "- offset -  0 1  2 3  4 5  6 7  8 9  A B  C D  E F  0123456789ABCDEF\n\
+             0x00000000 4142                                     AB\n"
+        );
+        assert_eq!(core.stderr.utf8_string().unwrap(), "");
+    }
+
+    fn test_script_cb(path: &Path) {
+        let script = path.to_string_lossy().to_string();
+        let mut core = new_core();
+        let batch = Batch {
+            scripts: vec![script.clone()],
+            ..Batch::default()
+        };
+        assert!(run_batch(&mut core, &batch));
+        let mut data = [0; 2];
+        core.io.pread(0, &mut data).unwrap();
+        assert_eq!(data, *b"AB");
+        assert_eq!(core.stdout.utf8_string().unwrap(), "");
+        assert!(core
+            .stderr
+            .utf8_string()
+            .unwrap()
+            .starts_with(&format!("{script}:4: Error: Execution failed\n")));
+    }
+
+    #[test]
+    fn test_script() {
+        operate_on_file(
+            &test_script_cb,
+            b"# write some data\nwx 41\n\nnonexistent\nwx 42 @ 1\n",
+        );
+    }
+
+    #[test]
+    fn test_missing_script() {
+        let mut core = new_core();
+        let batch = Batch {
+            scripts: vec!["batch_test_missing.rair".to_owned()],
+            cmds: vec!["wx 41".to_owned()],
+            quit: true,
+        };
+        assert!(run_batch(&mut core, &batch));
+        assert_eq!(
+            core.stderr.utf8_string().unwrap(),
+            "Error: Failed to read script\n\
+             batch_test_missing.rair: No such file or directory (os error 2).\n"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use clap::Parser;
+use core::mem;
 use rair_core::str_to_num;
 use rair_io::IoMode;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Default)]
 #[command(version)]
 #[command(about = "reverse engineering framework")]
 struct ArgsInner {
@@ -21,10 +22,42 @@ struct ArgsInner {
     #[arg(value_name = "/path/to/project")]
     pub proj: Option<String>,
 
+    /// Run commands from script file, can be given more than once
+    #[arg(short = 'i', long = "script")]
+    #[arg(value_name = "/path/to/script")]
+    pub scripts: Vec<String>,
+
+    /// Run command after scripts are done, can be given more than once
+    #[arg(short = 'c', long = "cmd")]
+    #[arg(value_name = "command")]
+    pub cmds: Vec<String>,
+
+    /// Quit after running commands instead of entering interactive mode
+    #[arg(short = 'q', long = "quit")]
+    pub quit: bool,
+
     /// Binary file to be loaded
     pub file: Option<String>,
 }
 
+/// Commands to run before (or instead of) the interactive prompt.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct Batch {
+    pub scripts: Vec<String>,
+    pub cmds: Vec<String>,
+    pub quit: bool,
+}
+
+impl From<&mut ArgsInner> for Batch {
+    fn from(ai: &mut ArgsInner) -> Self {
+        Batch {
+            scripts: mem::take(&mut ai.scripts),
+            cmds: mem::take(&mut ai.cmds),
+            quit: ai.quit,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum Args {
     Proj(String),
@@ -48,8 +81,10 @@ impl Args {
         Ok(perm)
     }
     /// parse command line arguments
-    pub fn parse() -> Result<Self, String> {
-        ArgsInner::parse().try_into()
+    pub fn parse() -> Result<(Self, Batch), String> {
+        let mut ai = ArgsInner::parse();
+        let batch = Batch::from(&mut ai);
+        Ok((ai.try_into()?, batch))
     }
 }
 
@@ -80,9 +115,10 @@ impl TryFrom<ArgsInner> for Args {
 
 #[cfg(test)]
 mod cli_tests {
+    use clap::Parser;
     use rair_io::IoMode;
 
-    use super::{Args, ArgsInner};
+    use super::{Args, ArgsInner, Batch};
 
     #[test]
     fn parse_perm_small() {
@@ -123,6 +159,7 @@ mod cli_tests {
             base: None,
             proj: Some("hello".to_owned()),
             file: None,
+            ..ArgsInner::default()
         };
         let args: Args = ai.try_into().unwrap();
         assert_eq!(args, Args::Proj("hello".to_owned()));
@@ -134,6 +171,7 @@ mod cli_tests {
             base: None,
             proj: None,
             file: Some("hello".to_owned()),
+            ..ArgsInner::default()
         };
         let args: Args = ai.try_into().unwrap();
         assert_eq!(
@@ -152,6 +190,7 @@ mod cli_tests {
             base: Some("0x1000".to_owned()),
             proj: None,
             file: Some("hello".to_owned()),
+            ..ArgsInner::default()
         };
         let args: Args = ai.try_into().unwrap();
         assert_eq!(
@@ -171,6 +210,7 @@ mod cli_tests {
             base: None,
             proj: Some("hello".to_owned()),
             file: Some("hello".to_owned()),
+            ..ArgsInner::default()
         };
         let err: Result<Args, _> = ai.try_into();
         let err = err.err().unwrap();
@@ -187,6 +227,7 @@ mod cli_tests {
             base: Some("0x1000".to_owned()),
             proj: Some("hello".to_owned()),
             file: None,
+            ..ArgsInner::default()
         };
         let err: Result<Args, _> = ai.try_into();
         let err = err.err().unwrap();
@@ -199,9 +240,36 @@ mod cli_tests {
             base: None,
             proj: Some("hello".to_owned()),
             file: None,
+            ..ArgsInner::default()
         };
         let err: Result<Args, _> = ai.try_into();
         let err = err.err().unwrap();
         assert_eq!(err, "You cannot set permissions when opening a project");
     }
+
+    #[test]
+    fn batch() {
+        let mut ai = ArgsInner::try_parse_from([
+            "rair", "-i", "a.rair", "-c", "px 0x20", "-q", "-c", "pd 2", "-i", "b.rair", "file",
+        ])
+        .unwrap();
+        let batch = Batch::from(&mut ai);
+        assert_eq!(
+            batch,
+            Batch {
+                scripts: vec!["a.rair".to_owned(), "b.rair".to_owned()],
+                cmds: vec!["px 0x20".to_owned(), "pd 2".to_owned()],
+                quit: true,
+            }
+        );
+        let args: Args = ai.try_into().unwrap();
+        assert_eq!(
+            args,
+            Args::File {
+                uri: "file".to_owned(),
+                base: 0,
+                perms: IoMode::READ
+            }
+        );
+    }
 }
@@ -13,6 +13,24 @@ pub fn hist_file() -> PathBuf {
     history
 }
 
+pub fn plugin_dir() -> PathBuf {
+    let mut plugins = project_dir().data_dir().to_owned();
+    plugins.push("plugins");
+    plugins
+}
+
+pub fn cmd_plugin_dir() -> PathBuf {
+    let mut plugins = project_dir().data_dir().to_owned();
+    plugins.push("cmd_plugins");
+    plugins
+}
+
+pub fn rc_file() -> PathBuf {
+    let mut rc = project_dir().config_dir().to_owned();
+    rc.push("rairrc");
+    rc
+}
+
 #[cfg(test)]
 
 mod test_files {
@@ -22,4 +40,19 @@ mod test_files {
         let hist = hist_file();
         assert!(hist.ends_with("history"));
     }
+    #[test]
+    fn test_plugin_dir() {
+        let plugins = plugin_dir();
+        assert!(plugins.ends_with("plugins"));
+    }
+    #[test]
+    fn test_cmd_plugin_dir() {
+        let plugins = cmd_plugin_dir();
+        assert!(plugins.ends_with("cmd_plugins"));
+    }
+    #[test]
+    fn test_rc() {
+        let rc = rc_file();
+        assert!(rc.ends_with("rair/rairrc"));
+    }
 }
@@ -1,22 +1,39 @@
 //! rair CLI.
 extern crate alloc;
 
+mod batch;
 mod cli;
 mod files;
 mod init;
 mod lineformatter;
 mod rpel;
 
+use batch::{fails, run_batch};
 use cli::Args;
 use core::mem;
+use files::{cmd_plugin_dir, plugin_dir, rc_file};
 use init::init_editor_from_core;
-use rair_core::{panic_msg, Core, Writer};
+use rair_core::{auto_load, error_msg, panic_msg, rair_source, Core, Writer};
+use rair_eval::rair_eval;
 use rpel::prompt_read_parse_evaluate_loop;
+use std::process::exit;
 
 fn main() {
     let mut core = Core::new();
+    core.set_evaluator(rair_eval);
+    for e in core.io.load_plugin_dir(&plugin_dir()) {
+        error_msg(&mut core, "Failed to load plugin", &e.to_string());
+    }
+    for e in core.load_cmd_plugin_dir(&cmd_plugin_dir()) {
+        error_msg(&mut core, "Failed to load plugin", &e);
+    }
     let editor = init_editor_from_core(&mut core);
-    let args = Args::parse().unwrap_or_else(|e| panic_msg(&mut core, &e, ""));
+    let (args, batch) = Args::parse().unwrap_or_else(|e| panic_msg(&mut core, &e, ""));
+    let rc = rc_file();
+    let mut failed = false;
+    if rc.exists() {
+        failed |= fails(&mut core, |core| rair_source(core, &rc.to_string_lossy()));
+    }
     match args {
         Args::Proj(proj) => {
             let stderr = mem::replace(&mut core.stderr, Writer::new_buf());
@@ -29,11 +46,17 @@ fn main() {
             }
         }
         Args::File { uri, base, perms } => {
-            core.io
+            let hndl = core
+                .io
                 .open_at(&uri, perms, base)
                 .unwrap_or_else(|e| panic_msg(&mut core, &e.to_string(), ""));
             core.set_loc(base);
+            failed |= fails(&mut core, |core| auto_load(core, hndl));
         }
     }
+    failed |= run_batch(&mut core, &batch);
+    if batch.quit {
+        exit(i32::from(failed));
+    }
     prompt_read_parse_evaluate_loop(core, editor);
 }
@@ -2,9 +2,11 @@
 
 extern crate tempfile;
 
+use std::env::{self, consts::DLL_EXTENSION, consts::DLL_PREFIX};
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use tempfile::NamedTempFile;
 
 pub const DATA: &[u8] = &[
@@ -17,6 +19,34 @@ pub const DATA: &[u8] = &[
     0x80, 0x41, 0xc1, 0x02, 0xc3, 0xc5, 0x88, 0x4d, 0xd5,
 ];
 
+fn tar_header(name: &str, size: usize, typ: u8) -> Vec<u8> {
+    let mut header = vec![0; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[124..135].copy_from_slice(format!("{size:011o}").as_bytes());
+    header[156] = typ;
+    header
+}
+
+/// Tar archive of `members`, names ending with `/` are directories and names longer than 100
+/// bytes are stored in GNU long name entries.
+#[must_use]
+pub fn tar(members: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (name, data) in members {
+        if name.len() > 100 {
+            out.extend(tar_header("././@LongLink", name.len(), b'L'));
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len().div_ceil(512) * 512, 0);
+        }
+        let typ = if name.ends_with('/') { b'5' } else { b'0' };
+        out.extend(tar_header(name, data.len(), typ));
+        out.extend_from_slice(data);
+        out.resize(out.len().div_ceil(512) * 512, 0);
+    }
+    out.extend_from_slice(&[0; 1024]);
+    out
+}
+
 pub fn operate_on_file(test_function: &dyn Fn(&Path), data: &[u8]) {
     let mut file = NamedTempFile::new().unwrap();
     file.write_all(data).unwrap();
@@ -43,3 +73,30 @@ pub fn operate_on_files(test_function: &dyn Fn(&[&Path]), files_data: &[&[u8]])
     }
     test_function(&paths);
 }
+
+/// Build the shared library example `name` of the package under test with the profile of the
+/// running test, then return its path.
+#[must_use]
+pub fn example_plugin(name: &str) -> PathBuf {
+    // tests run from target/<profile>/deps.
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    path.pop();
+    let profile = match path.file_name().unwrap().to_str().unwrap() {
+        "debug" => "dev".to_owned(),
+        profile => profile.to_owned(),
+    };
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    let output = Command::new(cargo)
+        .args(["build", "--example", name, "--profile", &profile])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    path.push("examples");
+    path.push(format!("{DLL_PREFIX}{name}.{DLL_EXTENSION}"));
+    path
+}